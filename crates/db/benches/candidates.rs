@@ -0,0 +1,90 @@
+use {
+  criterion::{criterion_group, criterion_main, BenchmarkId, Criterion},
+  db::Db,
+  model::{Action, Interests, MessageId, Prompt, Update, UserId},
+  std::convert::TryFrom,
+};
+
+mod common;
+
+/// Populating the larger database takes several minutes
+const USERS: &[u64] = &[10_000, 100_000];
+
+async fn onboard(db: &Db, id: u64) {
+  let user_id = UserId(id);
+
+  db.user(user_id).await.unwrap();
+
+  let updates = [
+    Update {
+      action:      Some(Action::Welcome),
+      next_prompt: Prompt::Bio,
+    },
+    Update {
+      action:      Some(Action::SetBio {
+        text: format!("User {}'s bio", id),
+      }),
+      next_prompt: Prompt::Tags,
+    },
+    Update {
+      action:      Some(Action::SetTags {
+        tags: vec![
+          Interests::CURATED[usize::try_from(id).unwrap() % Interests::CURATED.len()].to_owned(),
+        ],
+      }),
+      next_prompt: Prompt::Quiescent,
+    },
+  ];
+
+  for update in &updates {
+    db.prepare(user_id, update)
+      .await
+      .unwrap()
+      .commit(MessageId(id))
+      .await
+      .unwrap();
+  }
+}
+
+async fn candidate(db: &Db, id: u64) {
+  let update = Update {
+    action:      None,
+    next_prompt: Prompt::Quiescent,
+  };
+
+  let tx = db.prepare(UserId(id), &update).await.unwrap();
+
+  assert!(matches!(tx.prompt(), Prompt::Candidate { .. }));
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+  let runtime = tokio::runtime::Builder::new_multi_thread()
+    .enable_all()
+    .build()
+    .unwrap();
+
+  for &users in USERS {
+    let db = common::connect(&runtime, &format!("quwue-bench-candidates-{}", users));
+
+    runtime.block_on(async {
+      for id in 1..=users {
+        onboard(&db, id).await;
+      }
+    });
+
+    c.bench_with_input(BenchmarkId::new("candidate", users), &db, |b, db| {
+      b.to_async(&runtime).iter(|| candidate(db, users));
+    });
+
+    let mut id = users;
+    c.bench_with_input(BenchmarkId::new("onboard", users), &db, |b, db| {
+      b.to_async(&runtime).iter(|| {
+        id += 1;
+        onboard(db, id)
+      });
+    });
+  }
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,28 @@
+use {
+  db::{Db, PoolOptions},
+  std::{env, time::SystemTime},
+  tokio::runtime::Runtime,
+};
+
+pub(crate) fn connect(runtime: &Runtime, prefix: &str) -> Db {
+  let db_name = format!(
+    "{}-{}",
+    prefix,
+    SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap()
+      .as_millis()
+  );
+
+  let db_url = match env::var("QUWUE_BENCH_DATABASE_URL") {
+    Ok(url) => db_url::with_name(&url, &db_name).unwrap(),
+    Err(env::VarError::NotPresent) => db_url::db_url(&db_name),
+    Err(err) => panic!("Invalid `QUWUE_BENCH_DATABASE_URL`: {}", err),
+  };
+
+  runtime.block_on(async {
+    Db::connect_url(&db_url, PoolOptions::default())
+      .await
+      .unwrap()
+  })
+}
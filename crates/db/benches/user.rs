@@ -1,29 +1,22 @@
 use {
   criterion::{criterion_group, criterion_main, BenchmarkId, Criterion},
   db::Db,
-  std::time::SystemTime,
-  twilight_model::id::UserId,
+  model::UserId,
 };
 
+mod common;
+
 async fn benchmark(db: &Db, id: u64) {
   let user_id = UserId(id);
   db.user(user_id).await.unwrap();
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-  let db_url = db_url::db_url(&format!(
-    "quwue-bench-{}",
-    SystemTime::now()
-      .duration_since(SystemTime::UNIX_EPOCH)
-      .unwrap()
-      .as_millis()
-  ));
-
   let runtime = tokio::runtime::Builder::new_multi_thread()
     .enable_all()
     .build()
     .unwrap();
-  let db = runtime.block_on(async { Db::connect(&db_url).await.unwrap() });
+  let db = common::connect(&runtime, "quwue-bench");
   let mut id = 0;
   c.bench_with_input(BenchmarkId::new("benchmark", 1000), &db, |b, s| {
     b.to_async(&runtime).iter(|| {
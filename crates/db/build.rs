@@ -1,10 +1,24 @@
 use {
   sqlx::{migrate::MigrateDatabase, PgPool, Postgres},
-  std::time::SystemTime,
+  std::{env, time::SystemTime},
 };
 
 #[tokio::main]
 async fn main() {
+  println!("cargo:rerun-if-changed=migrations");
+  println!("cargo:rerun-if-env-changed=QUWUE_CHECK_QUERIES");
+
+  // Only the Postgres backend's queries are checked at compile time
+  if env::var_os("CARGO_FEATURE_POSTGRES").is_none() {
+    return;
+  }
+
+  // Without a live database, queries are checked against `sqlx-data.json`
+  if env::var_os("QUWUE_CHECK_QUERIES").is_none() {
+    println!("cargo:rustc-env=SQLX_OFFLINE=true");
+    return;
+  }
+
   let db_url = db_url::db_url(&format!(
     "quwue-build-{}",
     SystemTime::now()
@@ -20,6 +34,4 @@ async fn main() {
   sqlx::migrate!("./migrations").run(&pool).await.unwrap();
 
   println!("cargo:rustc-env=DATABASE_URL={}", db_url);
-
-  println!("cargo:rerun-if-changed=migrations");
 }
@@ -0,0 +1,5 @@
+-- Times are seconds since the Unix epoch, and `exposure` is decayed as of
+-- `last_shown_at`
+ALTER TABLE users ADD COLUMN exposure REAL NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN last_shown_at REAL;
+ALTER TABLE users ADD COLUMN last_active_at REAL;
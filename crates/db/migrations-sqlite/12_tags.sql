@@ -0,0 +1,21 @@
+CREATE TABLE IF NOT EXISTS tags (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS user_tags (
+  discord_id BIGINT NOT NULL,
+  tag_id BIGINT NOT NULL,
+  PRIMARY KEY(discord_id, tag_id),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id),
+  FOREIGN KEY(tag_id) REFERENCES tags(id)
+);
+
+CREATE INDEX IF NOT EXISTS user_tags_tag_id ON user_tags(tag_id);
+
+-- Users who onboarded before tags existed can add them with `!tags`
+ALTER TABLE users ADD COLUMN tagged BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE users SET tagged = TRUE WHERE bio IS NOT NULL;
+
+-- The tags prompt was inserted before the quiescent prompt
+UPDATE prompts SET discriminant = discriminant + 1 WHERE discriminant >= 2;
@@ -0,0 +1,10 @@
+ALTER TABLE responses ADD COLUMN super_like BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS reports (
+  discord_id BIGINT NOT NULL,
+  reported_id BIGINT NOT NULL,
+  created_at REAL NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS REAL)),
+  PRIMARY KEY(discord_id, reported_id),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id),
+  FOREIGN KEY(reported_id) REFERENCES users(discord_id)
+);
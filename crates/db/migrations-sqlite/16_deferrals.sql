@@ -0,0 +1,10 @@
+-- Times are seconds since the Unix epoch
+CREATE TABLE IF NOT EXISTS deferrals (
+  discord_id BIGINT NOT NULL,
+  candidate_id BIGINT NOT NULL,
+  deferred_at REAL NOT NULL,
+  created_at REAL NOT NULL,
+  PRIMARY KEY(discord_id, candidate_id),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id),
+  FOREIGN KEY(candidate_id) REFERENCES users(discord_id)
+);
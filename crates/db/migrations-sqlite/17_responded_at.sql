@@ -0,0 +1,2 @@
+-- Responses made before this was added are recorded as made at the epoch
+ALTER TABLE responses ADD COLUMN responded_at REAL NOT NULL DEFAULT 0;
@@ -0,0 +1,18 @@
+-- Bios set before these were added are recorded as set at the epoch
+ALTER TABLE users ADD COLUMN bio_updated_at REAL;
+UPDATE users SET bio_updated_at = 0 WHERE bio IS NOT NULL;
+
+CREATE TABLE IF NOT EXISTS bio_history (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  discord_id BIGINT NOT NULL,
+  bio TEXT NOT NULL,
+  set_at REAL NOT NULL,
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id)
+);
+
+CREATE INDEX IF NOT EXISTS bio_history_discord_id ON bio_history(discord_id, set_at);
+
+INSERT INTO bio_history (discord_id, bio, set_at)
+SELECT discord_id, bio, 0 FROM users WHERE bio IS NOT NULL;
+
+ALTER TABLE users ADD COLUMN resurface_declined BOOLEAN NOT NULL DEFAULT FALSE;
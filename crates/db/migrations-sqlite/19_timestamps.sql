@@ -0,0 +1,4 @@
+-- Null for rows that existed before these were added
+ALTER TABLE users ADD COLUMN created_at REAL;
+ALTER TABLE users ADD COLUMN updated_at REAL;
+ALTER TABLE prompts ADD COLUMN sent_at REAL;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS users (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  discord_id BIGINT NOT NULL UNIQUE,
+  welcomed BOOLEAN NOT NULL DEFAULT FALSE,
+  bio TEXT DEFAULT NULL
+);
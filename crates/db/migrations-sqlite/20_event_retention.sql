@@ -0,0 +1,6 @@
+-- Events processed before this was added are recorded as processed now
+ALTER TABLE processed_events ADD COLUMN processed_at REAL;
+UPDATE processed_events SET processed_at = CAST(strftime('%s', 'now') AS REAL)
+WHERE processed_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS processed_events_processed_at ON processed_events(processed_at);
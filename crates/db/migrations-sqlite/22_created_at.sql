@@ -0,0 +1,3 @@
+-- Null for rows that existed before these were added
+ALTER TABLE responses ADD COLUMN created_at REAL;
+ALTER TABLE prompts ADD COLUMN created_at REAL;
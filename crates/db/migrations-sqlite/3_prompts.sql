@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS prompts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  discriminant BIGINT NOT NULL,
+  message_id BIGINT NOT NULL,
+  payload BIGINT,
+  recipient_discord_id BIGINT NOT NULL UNIQUE,
+  FOREIGN KEY (recipient_discord_id) references users(discord_id)
+);
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS processed_events (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  key TEXT NOT NULL UNIQUE
+);
@@ -0,0 +1,6 @@
+-- Times are seconds since the Unix epoch
+CREATE TABLE IF NOT EXISTS leases (
+  name TEXT NOT NULL PRIMARY KEY,
+  holder TEXT NOT NULL,
+  expires_at REAL NOT NULL
+);
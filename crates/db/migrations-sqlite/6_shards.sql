@@ -0,0 +1,14 @@
+ALTER TABLE leases ADD COLUMN acquired_at REAL NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS interrupts (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  shard BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  candidate_id BIGINT NOT NULL,
+  channel_id BIGINT,
+  created_at REAL NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS REAL)),
+  FOREIGN KEY (user_id) REFERENCES users(discord_id),
+  FOREIGN KEY (candidate_id) REFERENCES users(discord_id)
+);
+
+CREATE INDEX IF NOT EXISTS interrupts_shard ON interrupts(shard, id);
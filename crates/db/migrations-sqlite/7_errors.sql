@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS errors (
+  id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+  reference TEXT NOT NULL UNIQUE,
+  variant TEXT NOT NULL,
+  message TEXT NOT NULL,
+  discord_id BIGINT,
+  created_at REAL NOT NULL DEFAULT (CAST(strftime('%s', 'now') AS REAL))
+);
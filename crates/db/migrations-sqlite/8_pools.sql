@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN pool TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,5 @@
+-- Times are seconds since the Unix epoch, and `exposure` is decayed as of
+-- `last_shown_at`
+ALTER TABLE users ADD COLUMN IF NOT EXISTS exposure DOUBLE PRECISION NOT NULL DEFAULT 0;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_shown_at DOUBLE PRECISION;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS last_active_at DOUBLE PRECISION;
@@ -0,0 +1,13 @@
+-- Finding a candidate only scans the user's queue, instead of every user
+CREATE TABLE IF NOT EXISTS candidate_queue (
+  discord_id BIGINT NOT NULL,
+  candidate_id BIGINT NOT NULL,
+  created_at DOUBLE PRECISION NOT NULL DEFAULT extract(epoch FROM now()),
+  PRIMARY KEY(discord_id, candidate_id),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id),
+  FOREIGN KEY(candidate_id) REFERENCES users(discord_id)
+);
+
+CREATE INDEX IF NOT EXISTS responses_candidate_id ON responses(candidate_id, discord_id);
+CREATE INDEX IF NOT EXISTS users_pool_exposure ON users(pool, exposure, id);
+CREATE INDEX IF NOT EXISTS users_pool_last_active_at ON users(pool, last_active_at);
@@ -0,0 +1,54 @@
+-- UTC offsets are in minutes, timezone windows in hours, and `connections`
+-- is a `Connection::mask`
+ALTER TABLE users ADD COLUMN IF NOT EXISTS age_bracket BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS min_age_bracket BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS max_age_bracket BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS utc_offset BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS timezone_window BIGINT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS connections BIGINT NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS user_languages (
+  discord_id BIGINT NOT NULL,
+  language TEXT NOT NULL,
+  PRIMARY KEY(discord_id, language),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id)
+);
+
+-- Mirrors `Preferences::compatible`. Users under 18 are in bracket 0, and
+-- dating is 8 in `connections`.
+CREATE OR REPLACE VIEW compatible_users AS
+SELECT
+  a.discord_id AS discord_id,
+  b.discord_id AS candidate_id
+FROM
+  users AS a, users AS b
+WHERE
+  (a.min_age_bracket IS NULL OR b.age_bracket BETWEEN a.min_age_bracket AND a.max_age_bracket)
+  AND
+  (b.min_age_bracket IS NULL OR a.age_bracket BETWEEN b.min_age_bracket AND b.max_age_bracket)
+  AND
+  (a.timezone_window IS NULL OR ABS(a.utc_offset - b.utc_offset) <= a.timezone_window * 60)
+  AND
+  (b.timezone_window IS NULL OR ABS(a.utc_offset - b.utc_offset) <= b.timezone_window * 60)
+  AND
+  (COALESCE(a.age_bracket, -1) = 0) = (COALESCE(b.age_bracket, -1) = 0)
+  AND
+  (
+    NOT EXISTS (SELECT * FROM user_languages WHERE discord_id = a.discord_id)
+    OR
+    NOT EXISTS (SELECT * FROM user_languages WHERE discord_id = b.discord_id)
+    OR
+    EXISTS (
+      SELECT * FROM user_languages AS theirs
+      WHERE
+        theirs.discord_id = b.discord_id
+        AND
+        theirs.language IN (SELECT language FROM user_languages WHERE discord_id = a.discord_id)
+    )
+  )
+  AND
+  (
+    (CASE WHEN a.connections = 0 THEN 15 ELSE a.connections END)
+    & (CASE WHEN b.connections = 0 THEN 15 ELSE b.connections END)
+    & (CASE WHEN a.age_bracket = 0 THEN 7 ELSE 15 END)
+  ) != 0;
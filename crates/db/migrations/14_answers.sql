@@ -0,0 +1,11 @@
+-- `accepted` is an `Answer::accepted_mask`, with no bits set for any choice
+CREATE TABLE IF NOT EXISTS answers (
+  discord_id BIGINT NOT NULL,
+  question BIGINT NOT NULL,
+  choice BIGINT NOT NULL,
+  accepted BIGINT NOT NULL,
+  importance BIGINT NOT NULL,
+  created_at DOUBLE PRECISION NOT NULL DEFAULT extract(epoch FROM now()),
+  PRIMARY KEY(discord_id, question),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id)
+);
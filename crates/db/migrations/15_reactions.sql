@@ -0,0 +1,10 @@
+ALTER TABLE responses ADD COLUMN IF NOT EXISTS super_like BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE IF NOT EXISTS reports (
+  discord_id BIGINT NOT NULL,
+  reported_id BIGINT NOT NULL,
+  created_at DOUBLE PRECISION NOT NULL DEFAULT extract(epoch FROM now()),
+  PRIMARY KEY(discord_id, reported_id),
+  FOREIGN KEY(discord_id) REFERENCES users(discord_id),
+  FOREIGN KEY(reported_id) REFERENCES users(discord_id)
+);
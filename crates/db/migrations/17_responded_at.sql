@@ -0,0 +1,2 @@
+-- Responses made before this was added are recorded as made at the epoch
+ALTER TABLE responses ADD COLUMN IF NOT EXISTS responded_at DOUBLE PRECISION NOT NULL DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Null for rows that existed before these were added
+ALTER TABLE users ADD COLUMN IF NOT EXISTS created_at DOUBLE PRECISION;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS updated_at DOUBLE PRECISION;
+ALTER TABLE prompts ADD COLUMN IF NOT EXISTS sent_at DOUBLE PRECISION;
@@ -0,0 +1,6 @@
+-- Events processed before this was added are recorded as processed now
+ALTER TABLE processed_events ADD COLUMN IF NOT EXISTS processed_at DOUBLE PRECISION;
+UPDATE processed_events SET processed_at = extract(epoch FROM now()) WHERE processed_at IS NULL;
+ALTER TABLE processed_events ALTER COLUMN processed_at SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS processed_events_processed_at ON processed_events(processed_at);
@@ -0,0 +1,7 @@
+-- Exhausted queues are offered new candidates instead of being refilled
+ALTER TABLE users ADD COLUMN IF NOT EXISTS queue_exhausted BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS paused BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS candidate_queue_candidate_id ON candidate_queue(candidate_id);
+CREATE INDEX IF NOT EXISTS prompts_payload ON prompts(payload);
@@ -0,0 +1,3 @@
+-- Null for rows that existed before these were added
+ALTER TABLE responses ADD COLUMN IF NOT EXISTS created_at DOUBLE PRECISION;
+ALTER TABLE prompts ADD COLUMN IF NOT EXISTS created_at DOUBLE PRECISION;
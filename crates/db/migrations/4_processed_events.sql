@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS processed_events (
+  id BIGSERIAL NOT NULL PRIMARY KEY,
+  key TEXT NOT NULL UNIQUE
+);
@@ -0,0 +1,14 @@
+ALTER TABLE leases ADD COLUMN IF NOT EXISTS acquired_at DOUBLE PRECISION NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS interrupts (
+  id BIGSERIAL NOT NULL PRIMARY KEY,
+  shard BIGINT NOT NULL,
+  user_id BIGINT NOT NULL,
+  candidate_id BIGINT NOT NULL,
+  channel_id BIGINT,
+  created_at DOUBLE PRECISION NOT NULL DEFAULT extract(epoch FROM now()),
+  FOREIGN KEY (user_id) REFERENCES users(discord_id),
+  FOREIGN KEY (candidate_id) REFERENCES users(discord_id)
+);
+
+CREATE INDEX IF NOT EXISTS interrupts_shard ON interrupts(shard, id);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS errors (
+  id BIGSERIAL NOT NULL PRIMARY KEY,
+  reference TEXT NOT NULL UNIQUE,
+  variant TEXT NOT NULL,
+  message TEXT NOT NULL,
+  discord_id BIGINT,
+  created_at DOUBLE PRECISION NOT NULL DEFAULT extract(epoch FROM now())
+);
@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN IF NOT EXISTS pool TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS external_ids (
+  id BIGSERIAL NOT NULL PRIMARY KEY,
+  namespace TEXT NOT NULL,
+  external_id TEXT NOT NULL,
+  UNIQUE(namespace, external_id)
+);
@@ -0,0 +1,14 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+  pub discord_id:     UserId,
+  pub accepted:       bool,
+  /// Decayed as of `last_shown_at`
+  pub exposure:       f64,
+  pub last_shown_at:  Option<f64>,
+  pub last_active_at: Option<f64>,
+  pub shared_tags:    u64,
+  /// Computed by `Db`, so backends leave it unset
+  pub compatibility:  Option<u64>,
+}
@@ -0,0 +1,26 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Clock {
+  System,
+  Fixed(f64),
+}
+
+impl Clock {
+  /// Seconds since the Unix epoch
+  pub fn now(self) -> f64 {
+    match self {
+      Self::System => SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64(),
+      Self::Fixed(now) => now,
+    }
+  }
+}
+
+impl Default for Clock {
+  fn default() -> Self {
+    Self::System
+  }
+}
@@ -1,39 +1,64 @@
 // stdlib
 pub(crate) use std::{
-  convert::{Infallible, TryInto},
+  collections::{BTreeMap, BTreeSet},
+  convert::{Infallible, TryFrom, TryInto},
+  fmt::Debug,
+  ops::Range,
   path::PathBuf,
   str::FromStr,
+  sync::Arc,
+  time::{Duration, Instant, SystemTime},
 };
 
 // dependencies
 pub(crate) use {
+  async_trait::async_trait,
   num_enum::TryFromPrimitiveError,
   snafu::{ResultExt, Snafu},
-  sqlx::{migrate::MigrateDatabase, PgPool, Postgres},
-  twilight_model::id::{MessageId, UserId},
+  tokio::sync::Mutex,
 };
 
 // local dependencies
-pub(crate) use model::{Action, Emoji, Prompt, PromptDiscriminant, PromptMessage, Update, User};
+pub(crate) use model::{
+  Action, AgeBracket, Answer, ChannelId, Connection, Emoji, EventId, Importance, Interests,
+  MessageId, Preferences, Prompt, PromptDiscriminant, PromptMessage, Questionnaire, Update, User,
+  UserId,
+};
 
 // modules
 pub(crate) use crate::error;
 
 // traits
-pub(crate) use crate::{unwrap_infallible::UnwrapInfallible, value::Value};
+pub(crate) use crate::{
+  store::Store, store_transaction::StoreTransaction, unwrap_infallible::UnwrapInfallible,
+  value::Value,
+};
 
 // structs and enums
-pub(crate) use crate::{db::Db, error::Error, update_tx::UpdateTx};
+pub(crate) use crate::{
+  candidate::Candidate, clock::Clock, error::Error, interrupt::Interrupt,
+  memory_lease::MemoryLease, memory_operation::MemoryOperation, memory_response::MemoryResponse,
+  memory_state::MemoryState, memory_store::MemoryStore, memory_transaction::MemoryTransaction,
+  pool_options::PoolOptions, ranking::Ranking, update_tx::UpdateTx,
+};
+
+#[cfg(feature = "postgres")]
+pub(crate) use crate::postgres_store::PostgresStore;
+
+#[cfg(feature = "sqlite")]
+pub(crate) use crate::sqlite_store::SqliteStore;
 
 // type aliases
 pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
-pub(crate) type Transaction<'a> = sqlx::Transaction<'a, sqlx::Postgres>;
 
 #[cfg(test)]
 mod test {
   pub(crate) use {
+    crate::{backend_test, db::Db, test_context::TestContext},
     guard::guard_unwrap,
+    model::Response,
     std::sync::atomic::{AtomicUsize, Ordering},
+    tempfile::TempDir,
   };
 }
 
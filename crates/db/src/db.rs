@@ -1,78 +1,95 @@
 use crate::common::*;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Db {
-  pool: PgPool,
+  clock:           Clock,
+  defer_cooldown:  Duration,
+  ranking:         Ranking,
+  resurface_delay: Duration,
+  store:           Arc<dyn Store>,
 }
 
 impl Db {
-  pub async fn connect(name: &str) -> Result<Self> {
-    let url = db_url::db_url(name);
-
-    if !sqlx::Postgres::database_exists(&url).await? {
-      Postgres::create_database(&url).await.unwrap();
+  pub const DEFAULT_DEFER_COOLDOWN: Duration = Duration::from_secs(24 * 60 * 60);
+  pub const DEFAULT_POOL: &'static str = "default";
+  pub const DEFAULT_RESURFACE_DELAY: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+  /// Gateway resumes and catch-up only replay recent events, so older ones
+  /// needn't be remembered
+  pub const EVENT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+  const QUEUE_BATCH: u64 = 32;
+  pub const UNDO_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+  pub fn new(store: impl Store + 'static) -> Self {
+    Self {
+      clock:           Clock::default(),
+      defer_cooldown:  Self::DEFAULT_DEFER_COOLDOWN,
+      ranking:         Ranking::default(),
+      resurface_delay: Self::DEFAULT_RESURFACE_DELAY,
+      store:           Arc::new(store),
     }
+  }
 
-    let options = sqlx::postgres::PgConnectOptions::from_str(&url)?;
-
-    let pool = PgPool::connect_with(options).await?;
+  pub fn with_defer_cooldown(self, defer_cooldown: Duration) -> Self {
+    Self {
+      defer_cooldown,
+      ..self
+    }
+  }
 
-    sqlx::migrate!("./migrations").run(&pool).await?;
+  pub fn with_resurface_delay(self, resurface_delay: Duration) -> Self {
+    Self {
+      resurface_delay,
+      ..self
+    }
+  }
 
-    Ok(Self { pool })
+  pub fn with_ranking(self, ranking: Ranking) -> Self {
+    Self { ranking, ..self }
   }
 
-  async fn load_user<'a>(tx: &mut Transaction<'a>, discord_id: UserId) -> Result<Option<User>> {
-    let discord_id = discord_id.store();
+  pub fn with_clock(self, clock: Clock) -> Self {
+    Self { clock, ..self }
+  }
 
-    let row = sqlx::query!("SELECT * FROM users WHERE discord_id = $1", discord_id)
-      .fetch_optional(&mut *tx)
-      .await?;
+  /// Candidates are ranked with a fixed seed, so that tests are reproducible
+  pub fn memory() -> Self {
+    Self::new(MemoryStore::default()).with_ranking(Ranking::new(0))
+  }
 
-    if let Some(user) = row {
-      let prompt = sqlx::query!(
-        "SELECT * FROM prompts where recipient_discord_id = $1",
-        discord_id,
-      )
-      .fetch_optional(tx)
-      .await?;
+  pub async fn connect(name: &str) -> Result<Self> {
+    Self::connect_url(&db_url::db_url(name), PoolOptions::default()).await
+  }
 
-      let prompt_message = match prompt {
-        Some(row) => Some(PromptMessage {
-          prompt:     Prompt::load((row.discriminant, row.payload))?,
-          message_id: MessageId::load(row.message_id).unwrap_infallible(),
-        }),
-        None => None,
-      };
+  pub async fn connect_url(url: &str, pool_options: PoolOptions) -> Result<Self> {
+    let scheme = url.split(':').next().unwrap_or_default();
 
-      return Ok(Some(User {
-        id: u64::load(user.id).unwrap_infallible(),
-        discord_id: UserId::load(user.discord_id).unwrap_infallible(),
-        welcomed: user.welcomed,
-        bio: user.bio,
-        prompt_message,
-      }));
+    match scheme {
+      #[cfg(feature = "postgres")]
+      "postgres" | "postgresql" => Ok(Self::new(PostgresStore::connect(url, pool_options).await?)),
+      #[cfg(feature = "sqlite")]
+      "sqlite" => Ok(Self::new(SqliteStore::connect(url, pool_options).await?)),
+      _ => Err(Error::UnsupportedScheme {
+        scheme: scheme.to_owned(),
+      }),
     }
-
-    Ok(None)
   }
 
   pub async fn user(&self, discord_id: UserId) -> Result<User> {
-    let mut tx = self.pool.begin().await?;
+    self.user_in_pool(discord_id, Self::DEFAULT_POOL).await
+  }
+
+  /// A user's pool is fixed when they're inserted
+  pub async fn user_in_pool(&self, discord_id: UserId, pool: &str) -> Result<User> {
+    let mut tx = self.store.begin().await?;
 
-    if let Some(user) = Self::load_user(&mut tx, discord_id).await? {
+    if let Some(user) = tx.load_user(discord_id).await? {
       return Ok(user);
     }
 
-    {
-      let discord_id = discord_id.store();
-
-      sqlx::query!("INSERT INTO users(discord_id) VALUES($1)", discord_id)
-        .execute(&mut tx)
-        .await?;
-    };
+    tx.insert_user(discord_id, pool, self.clock.now()).await?;
 
-    let user = Self::load_user(&mut tx, discord_id)
+    let user = tx
+      .load_user(discord_id)
       .await?
       .ok_or_else(|| Error::Internal {
         message: "Load user returned None after insertion.".to_owned(),
@@ -83,157 +100,152 @@ impl Db {
     Ok(user)
   }
 
-  async fn get_candidate<'a>(
-    tx: &mut Transaction<'a>,
-    discord_id: UserId,
-  ) -> Result<Option<UserId>> {
-    let discord_id = discord_id.store();
-
-    let quiescent_discriminant = PromptDiscriminant::Quiescent.store();
-
-    // This should be a single SELECT with an OUTER JOIN, instead of two SELECTS,
-    // but that seems to break the sqlx query parser:
-    // https://github.com/launchbadge/sqlx/issues/1249
-    let candidate = sqlx::query!(
-      "SELECT
-        discord_id
-      FROM
-        users AS potential_candidate
-      WHERE
-        welcomed = TRUE
-        AND
-        bio IS NOT NULL
-        AND
-        discord_id != $1
-        AND
-        NOT EXISTS (
-          SELECT * FROM responses
-          WHERE discord_id = $2 AND candidate_id = potential_candidate.discord_id
-        )
-        AND
-        EXISTS (
-          SELECT * FROM responses
-          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $3 AND response
-        )
-        AND
-        EXISTS (
-          SELECT * FROM prompts
-          WHERE
-            recipient_discord_id = potential_candidate.discord_id AND discriminant = $4
-        )
-      LIMIT 1",
-      discord_id,
-      discord_id,
-      discord_id,
-      quiescent_discriminant,
-    )
-    .fetch_optional(&mut *tx)
-    .await?
-    .map(|row| UserId::load(row.discord_id).unwrap_infallible());
-
-    if let Some(candidate) = candidate {
-      return Ok(Some(candidate));
-    }
-
-    let candidate = sqlx::query!(
-      "SELECT
-        discord_id
-      FROM
-        users AS potential_candidate
-      WHERE
-        welcomed = TRUE
-        AND
-        bio IS NOT NULL
-        AND
-        discord_id != $1
-        AND
-        NOT EXISTS (
-          SELECT * FROM responses
-          WHERE discord_id = $2 AND candidate_id = potential_candidate.discord_id
-        )
-        AND
-        NOT EXISTS (
-          SELECT * FROM responses
-          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $3 AND NOT response
-        )
-        AND
-        EXISTS (
-          SELECT * FROM prompts
-          WHERE
-            recipient_discord_id = potential_candidate.discord_id AND discriminant = $4
-        )
-      LIMIT 1",
-      discord_id,
-      discord_id,
-      discord_id,
-      quiescent_discriminant,
-    )
-    .fetch_optional(tx)
-    .await?
-    .map(|row| UserId::load(row.discord_id).unwrap_infallible());
-
-    Ok(candidate)
-  }
-
-  async fn get_match<'a>(tx: &mut Transaction<'a>, discord_id: UserId) -> Result<Option<UserId>> {
-    let discord_id = discord_id.store();
-
-    let row = sqlx::query!(
-      "SELECT
-        candidate_id
-      FROM
-        responses AS outer_responses
-      WHERE
-        discord_id = $1
-        AND
-        response
-        AND
-        NOT dismissed
-        AND
-        EXISTS (
-          SELECT * FROM responses
-          WHERE
-            discord_id = outer_responses.candidate_id
-            AND
-            candidate_id = outer_responses.discord_id
-            AND
-            response
-        )
-      LIMIT 1",
-      discord_id,
-    )
-    .fetch_optional(tx)
-    .await?;
+  pub async fn prompt_messages(&self) -> Result<Vec<(UserId, PromptMessage)>> {
+    self.store.prompt_messages().await
+  }
+
+  pub async fn intern(&self, namespace: &str, external_id: &str) -> Result<u64> {
+    self.store.intern(namespace, external_id).await
+  }
+
+  pub async fn external_id(&self, namespace: &str, id: u64) -> Result<Option<String>> {
+    self.store.external_id(namespace, id).await
+  }
+
+  pub async fn current_prompt(&self, discord_id: UserId) -> Result<Option<Prompt>> {
+    let mut tx = self.store.begin().await?;
+
+    tx.prompt(discord_id).await
+  }
+
+  pub async fn prepare(&self, user_id: UserId, update: &Update) -> Result<UpdateTx> {
+    let tx = self.store.begin().await?;
+
+    self.prepare_with_transaction(tx, user_id, update).await
+  }
+
+  /// Returns `None` if `event` has already been processed
+  pub async fn prepare_event(
+    &self,
+    event: &EventId,
+    user_id: UserId,
+    update: &Update,
+  ) -> Result<Option<UpdateTx>> {
+    let mut tx = self.store.begin().await?;
+
+    if !tx
+      .record_event(&event.to_string(), self.clock.now())
+      .await?
+    {
+      return Ok(None);
+    }
 
-    Ok(row.map(|row| UserId::load(row.candidate_id).unwrap_infallible()))
+    Ok(Some(
+      self.prepare_with_transaction(tx, user_id, update).await?,
+    ))
   }
 
-  pub async fn prepare<'a>(&'a self, user_id: UserId, update: &Update) -> Result<UpdateTx<'a>> {
-    let mut tx = self.pool.begin().await?;
+  async fn prepare_with_transaction<'a>(
+    &'a self,
+    mut tx: Box<dyn StoreTransaction>,
+    user_id: UserId,
+    update: &'a Update,
+  ) -> Result<UpdateTx> {
+    let now = self.clock.now();
+
+    tx.set_last_active(user_id, now).await?;
+
+    let mut undone = None;
+    let mut retracted = Vec::new();
 
     if let Some(action) = &update.action {
       use Action::*;
       match action {
-        Welcome => Self::welcome(&mut tx, user_id).await?,
-        SetBio { text } => Self::set_bio(&mut tx, user_id, text).await?,
-        AcceptCandidate { id } => Self::respond_to_candidate(&mut tx, user_id, *id, true).await?,
-        DeclineCandidate { id } => Self::respond_to_candidate(&mut tx, user_id, *id, false).await?,
-        DismissMatch { id } => Self::dismiss_match(&mut tx, user_id, *id).await?,
+        Welcome => tx.welcome(user_id).await?,
+        SetBio { text } => tx.set_bio(user_id, text, now).await?,
+        SetTags { tags } => {
+          let onboarding = tx
+            .load_user(user_id)
+            .await?
+            .map_or(false, |user| user.tags.is_none());
+
+          tx.set_tags(user_id, tags).await?;
+
+          if onboarding {
+            tx.offer_candidate(user_id, Self::QUEUE_BATCH).await?;
+          }
+        },
+        AcceptCandidate { id } => Self::accept(&mut *tx, user_id, *id, now).await?,
+        SuperLikeCandidate { id } => {
+          Self::accept(&mut *tx, user_id, *id, now).await?;
+          tx.super_like(user_id, *id).await?;
+        },
+        DeclineCandidate { id } => Self::decline(&mut *tx, user_id, *id, now).await?,
+        DeferCandidate { id } => tx.defer_candidate(user_id, *id, now).await?,
+        ReportCandidate { id } => {
+          Self::decline(&mut *tx, user_id, *id, now).await?;
+          tx.report(user_id, *id).await?;
+        },
+        DismissMatch { id } => tx.dismiss_match(user_id, *id).await?,
+        SetPreferences { preferences } => {
+          tx.set_preferences(user_id, preferences).await?;
+
+          // Users who weren't compatible before may be now
+          tx.set_queue_exhausted(user_id, false).await?;
+
+          if !tx
+            .load_user(user_id)
+            .await?
+            .map_or(true, |user| user.paused)
+          {
+            tx.offer_candidate(user_id, Self::QUEUE_BATCH).await?;
+          }
+        },
+        SetPaused { paused } => {
+          tx.set_paused(user_id, *paused).await?;
+
+          if *paused {
+            tx.withdraw_candidate(user_id).await?;
+          } else {
+            tx.offer_candidate(user_id, Self::QUEUE_BATCH).await?;
+          }
+        },
+        SetResurfaceDeclined { enabled } => tx.set_resurface_declined(user_id, *enabled).await?,
+        DeleteProfile => {
+          retracted = tx.shown_to(user_id).await?;
+          tx.delete_profile(user_id, now).await?;
+        },
+        SetAnswer { answer } => tx.set_answer(user_id, answer).await?,
+        Undo => undone = Self::undo(&mut *tx, user_id, now).await?,
       }
-    }
 
-    let mut next_prompt = update.next_prompt;
-
-    if next_prompt.quiescent() {
-      if let Some(id) = Self::get_match(&mut tx, user_id).await? {
-        next_prompt = Prompt::Match { id };
-      } else if let Some(id) = Self::get_candidate(&mut tx, user_id).await? {
-        next_prompt = Prompt::Candidate { id };
+      if let Welcome
+      | SetBio { .. }
+      | SetTags { .. }
+      | SetPreferences { .. }
+      | SetPaused { .. }
+      | SetResurfaceDeclined { .. }
+      | SetAnswer { .. } = action
+      {
+        tx.set_updated_at(user_id, now).await?;
       }
+    }
+
+    let prompt = match undone {
+      Some((id, _)) => Prompt::Candidate { id },
+      None =>
+        self
+          .resolve(&mut *tx, user_id, update.next_prompt, now)
+          .await?,
     };
 
     let update_tx = UpdateTx {
-      prompt: next_prompt,
+      retracted: match undone {
+        Some((id, true)) => vec![id],
+        _ => retracted,
+      },
+      clock: self.clock,
+      prompt,
       tx,
       user_id,
     };
@@ -241,733 +253,2213 @@ impl Db {
     Ok(update_tx)
   }
 
-  pub async fn prepare_interrupt_for_accept<'a>(
-    &'a self,
+  async fn resolve(
+    &self,
+    tx: &mut dyn StoreTransaction,
     user_id: UserId,
-    candidate_id: UserId,
-  ) -> Result<Option<UpdateTx<'a>>> {
-    let mut tx = self.pool.begin().await?;
-
-    let response = {
-      let user_id = user_id.store();
-      let candidate_id = candidate_id.store();
-
-      sqlx::query!(
-        "SELECT
-          response
-        FROM
-          responses
-        WHERE
-          discord_id = $1 AND candidate_id = $2
-        LIMIT 1",
-        candidate_id,
-        user_id,
-      )
-      .fetch_optional(&mut tx)
-      .await?
-      .map(|row| row.response)
+    mut prompt: Prompt,
+    now: f64,
+  ) -> Result<Prompt> {
+    if let Prompt::Candidate { id } = prompt {
+      if !Self::compatible(tx, user_id, id).await? {
+        prompt = Prompt::Quiescent;
+      }
+    }
+
+    if !prompt.quiescent() {
+      return Ok(prompt);
+    }
+
+    if let Some(id) = tx.get_match(user_id).await? {
+      return Ok(Prompt::Match { id });
+    }
+
+    if let Some(id) = self
+      .ranking
+      .choose(user_id, self.candidates(tx, user_id, now).await?, now)
+    {
+      Self::record_exposure(tx, id, now).await?;
+      return Ok(Prompt::Candidate { id });
+    }
+
+    Ok(prompt)
+  }
+
+  async fn accept(
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    id: UserId,
+    now: f64,
+  ) -> Result<()> {
+    tx.respond_to_candidate(user_id, id, true, now).await?;
+    tx.dequeue_candidate(user_id, id).await?;
+
+    // Users are shown candidates who accepted them first
+    if tx.response(id, user_id).await?.is_none() {
+      tx.enqueue_candidate(id, user_id).await?;
+    }
+
+    Ok(())
+  }
+
+  async fn decline(
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    id: UserId,
+    now: f64,
+  ) -> Result<()> {
+    tx.respond_to_candidate(user_id, id, false, now).await?;
+    tx.dequeue_candidate(user_id, id).await?;
+    tx.dequeue_candidate(id, user_id).await
+  }
+
+  /// Returns the candidate and whether they had been accepted
+  async fn undo(
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    now: f64,
+  ) -> Result<Option<(UserId, bool)>> {
+    let (id, accepted, responded_at) = match tx.last_response(user_id).await? {
+      Some(last_response) => last_response,
+      None => return Ok(None),
+    };
+
+    if now - responded_at >= Self::UNDO_WINDOW.as_secs_f64()
+      || tx.response(id, user_id).await?.is_some()
+      || !Self::compatible(tx, user_id, id).await?
+    {
+      return Ok(None);
+    }
+
+    tx.delete_response(user_id, id).await?;
+    tx.enqueue_candidate(user_id, id).await?;
+    tx.enqueue_candidate(id, user_id).await?;
+
+    Ok(Some((id, accepted)))
+  }
+
+  /// The interrupt stays queued until the update is committed, so that it is
+  /// delivered again if sending the prompt fails
+  pub async fn prepare_interrupt(
+    &self,
+    shards: Range<u64>,
+  ) -> Result<Option<(Interrupt, Option<UpdateTx>)>> {
+    let mut tx = self.store.begin().await?;
+
+    let interrupt = match tx.dequeue_interrupt(shards).await? {
+      Some(interrupt) => interrupt,
+      None => return Ok(None),
+    };
+
+    let now = self.clock.now();
+
+    let prompt = self
+      .interrupt_prompt(&mut *tx, interrupt.user_id, interrupt.candidate_id, now)
+      .await?;
+
+    let update_tx = if let Some(prompt) = prompt {
+      Some(UpdateTx {
+        retracted: Vec::new(),
+        clock: self.clock,
+        user_id: interrupt.candidate_id,
+        prompt,
+        tx,
+      })
+    } else {
+      tx.commit().await?;
+      None
     };
 
-    let prompt = match response {
+    Ok(Some((interrupt, update_tx)))
+  }
+
+  async fn interrupt_prompt(
+    &self,
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    candidate_id: UserId,
+    now: f64,
+  ) -> Result<Option<Prompt>> {
+    if tx.response(user_id, candidate_id).await? != Some(true) {
+      return self.retract(tx, user_id, candidate_id, now).await;
+    }
+
+    let prompt = match tx.response(candidate_id, user_id).await? {
       Some(true) => Prompt::Match { id: user_id },
       Some(false) => return Ok(None),
       None => Prompt::Candidate { id: user_id },
     };
 
+    if let Some(current) = tx.prompt(candidate_id).await? {
+      if prompt.cannot_interrupt(current.discriminant()) {
+        return Ok(None);
+      }
+    }
+
+    if let Prompt::Candidate { id } = prompt {
+      if !Self::compatible(tx, candidate_id, id).await? {
+        return Ok(None);
+      }
+
+      // Users who deferred the accepting user wait out the cooldown
+      if self.deferred(tx, candidate_id, now).await?.contains(&id) {
+        return Ok(None);
+      }
+
+      Self::record_exposure(tx, id, now).await?;
+    }
+
+    Ok(Some(prompt))
+  }
+
+  async fn retract(
+    &self,
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    candidate_id: UserId,
+    now: f64,
+  ) -> Result<Option<Prompt>> {
+    match tx.prompt(candidate_id).await? {
+      Some(Prompt::Candidate { id } | Prompt::Match { id }) if id == user_id => {},
+      _ => return Ok(None),
+    }
+
+    tx.dequeue_candidate(candidate_id, user_id).await?;
+
+    let prompt = self
+      .resolve(tx, candidate_id, Prompt::Quiescent, now)
+      .await?;
+
+    Ok(Some(prompt))
+  }
+
+  /// Refilling the queue scans every user, so it's only done when the queue
+  /// is empty, and not again once a refill has found no one
+  async fn candidates(
+    &self,
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    now: f64,
+  ) -> Result<Vec<Candidate>> {
+    if tx
+      .load_user(user_id)
+      .await?
+      .map_or(false, |user| user.paused)
     {
-      let candidate_id = candidate_id.store();
-
-      let discriminant = sqlx::query!(
-        "SELECT
-          discriminant
-        FROM
-          prompts
-        WHERE
-          recipient_discord_id = $1
-        LIMIT 1",
-        candidate_id,
-      )
-      .fetch_optional(&mut tx)
+      return Ok(Vec::new());
+    }
+
+    self.resurface(tx, user_id, now).await?;
+
+    let deferred = self.deferred(tx, user_id, now).await?;
+
+    let mut candidates = tx.candidates(user_id).await?;
+    candidates.retain(|candidate| !deferred.contains(&candidate.discord_id));
+
+    if candidates.is_empty() && !tx.queue_exhausted(user_id).await? {
+      if tx
+        .refill_candidate_queue(user_id, Self::QUEUE_BATCH)
+        .await?
+        > 0
+      {
+        candidates = tx.candidates(user_id).await?;
+        candidates.retain(|candidate| !deferred.contains(&candidate.discord_id));
+      } else {
+        tx.set_queue_exhausted(user_id, true).await?;
+      }
+    }
+
+    let answers = tx.answers(user_id).await?;
+
+    // Compatibility is unknown for users who haven't answered any questions,
+    // so don't bother loading their candidates' answers
+    if !answers.is_empty() {
+      let queued = tx.queued_answers(user_id).await?;
+
+      for candidate in &mut candidates {
+        candidate.compatibility = Questionnaire::compatibility(
+          &answers,
+          queued.get(&candidate.discord_id).map_or(&[], Vec::as_slice),
+        );
+      }
+    }
+
+    Ok(candidates)
+  }
+
+  async fn resurface(
+    &self,
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    now: f64,
+  ) -> Result<()> {
+    if !tx
+      .load_user(user_id)
       .await?
-      .map(|row| row.discriminant);
+      .map_or(false, |user| user.resurface_declined)
+    {
+      return Ok(());
+    }
 
-      if let Some(discriminant) = discriminant {
-        if prompt.cannot_interrupt(PromptDiscriminant::load(discriminant)?) {
-          return Ok(None);
-        }
+    let delay = self.resurface_delay.as_secs_f64();
+
+    for (id, declined_at) in tx.declined_with_new_bios(user_id).await? {
+      if now - declined_at < delay {
+        continue;
+      }
+
+      let rewritten = match tx.bio_at(id, declined_at).await? {
+        Some(declined_bio) => Self::rewritten(&declined_bio, &tx.bio(id).await?),
+        None => true,
+      };
+
+      if rewritten {
+        tx.delete_response(user_id, id).await?;
+        tx.enqueue_candidate(user_id, id).await?;
+        tx.enqueue_candidate(id, user_id).await?;
       }
     }
 
-    let update_tx = UpdateTx {
-      user_id: candidate_id,
-      prompt,
-      tx,
+    Ok(())
+  }
+
+  /// Whether `new` shares fewer than half of its distinct words with `old`
+  fn rewritten(old: &str, new: &str) -> bool {
+    let words = |text: &str| {
+      text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<BTreeSet<String>>()
     };
 
-    Ok(Some(update_tx))
+    let old = words(old);
+    let new = words(new);
+
+    old.intersection(&new).count() * 2 < old.union(&new).count()
+  }
+
+  async fn deferred(
+    &self,
+    tx: &mut dyn StoreTransaction,
+    user_id: UserId,
+    now: f64,
+  ) -> Result<Vec<UserId>> {
+    let cooldown = self.defer_cooldown.as_secs_f64();
+
+    Ok(
+      tx.deferrals(user_id)
+        .await?
+        .into_iter()
+        .filter(|(_, deferred_at)| now - deferred_at < cooldown)
+        .map(|(candidate_id, _)| candidate_id)
+        .collect(),
+    )
+  }
+
+  async fn compatible(tx: &mut dyn StoreTransaction, a: UserId, b: UserId) -> Result<bool> {
+    Ok(match (tx.load_user(a).await?, tx.load_user(b).await?) {
+      (Some(a), Some(b)) => a.preferences.compatible(&b.preferences),
+      _ => false,
+    })
+  }
+
+  async fn record_exposure(
+    tx: &mut dyn StoreTransaction,
+    discord_id: UserId,
+    now: f64,
+  ) -> Result<()> {
+    let (exposure, last_shown_at) = tx.exposure(discord_id).await?;
+
+    tx.set_exposure(
+      discord_id,
+      Ranking::decayed_exposure(exposure, last_shown_at, now) + 1.0,
+      now,
+    )
+    .await
+  }
+
+  pub async fn prune_events(&self) -> Result<u64> {
+    self
+      .store
+      .prune_events(self.clock.now() - Self::EVENT_RETENTION.as_secs_f64())
+      .await
+  }
+
+  pub async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+    self.store.acquire_lease(name, holder, duration).await
+  }
+
+  pub async fn lease_holder(&self, name: &str) -> Result<Option<String>> {
+    self.store.lease_holder(name).await
+  }
+
+  pub async fn live_lease_holders(&self, prefix: &str) -> Result<Vec<String>> {
+    self.store.live_lease_holders(prefix).await
+  }
+
+  pub async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+    self.store.release_lease(name, holder).await
+  }
+
+  pub async fn release_leases(&self, holder: &str) -> Result<()> {
+    self.store.release_leases(holder).await
+  }
+
+  pub async fn enqueue_interrupt(&self, shard: u64, interrupt: Interrupt) -> Result<()> {
+    self.store.enqueue_interrupt(shard, interrupt).await
+  }
+
+  pub async fn record_error(
+    &self,
+    reference: &str,
+    variant: &str,
+    message: &str,
+    discord_id: Option<UserId>,
+  ) -> Result<()> {
+    self
+      .store
+      .record_error(reference, variant, message, discord_id)
+      .await
+  }
+
+  #[cfg(test)]
+  async fn user_count(&self) -> Result<u64> {
+    self.store.user_count().await
   }
 
-  pub(crate) async fn commit<'a>(
-    mut tx: Transaction<'a>,
-    discord_id: UserId,
-    prompt_message: PromptMessage,
-  ) -> Result<()> {
-    let discord_id = discord_id.store();
-    let (discriminant, payload) = prompt_message.prompt.store();
-    let message_id = prompt_message.message_id.store();
-
-    sqlx::query!(
-      "INSERT INTO prompts
-        (discriminant, payload, message_id, recipient_discord_id)
-      VALUES
-        ($1, $2, $3, $4)
-      ON CONFLICT (recipient_discord_id) DO UPDATE SET
-        discriminant = $1,
-        payload = $2,
-        message_id = $3,
-        recipient_discord_id = $4
-      ",
-      discriminant,
-      payload,
-      message_id,
-      discord_id
-    )
-    .execute(&mut tx)
-    .await?;
+  pub async fn prompt_text(
+    tx: &mut dyn StoreTransaction,
+    recipient_id: UserId,
+    prompt: Prompt,
+  ) -> Result<String> {
+    use Prompt::*;
+
+    let text = match prompt {
+      Welcome => format!(
+        concat!(
+          "Hi!\n",
+          "Quwue is a bot that matches you with other Discord users.\n",
+          "Your Discord tag will only be revealed to matches.\n",
+          "To start, you'll need to set up your profile.\n",
+          "React with {} or type `ok` to continue.",
+        ),
+        Emoji::ThumbsUp.markup()
+      ),
+      Quiescent => {
+        let user = tx.load_user(recipient_id).await?;
+
+        if user.as_ref().map_or(false, |user| user.paused) {
+          format!(
+            concat!(
+              "You're paused, so you won't be shown new people, and nobody will be shown you. ",
+              "Type `{}` to unpause.",
+            ),
+            User::PAUSE_COMMAND,
+          )
+        } else {
+          format!(
+            concat!(
+              "You've seen all available matches. We'll message you when we have new matches to ",
+              "show you! Type `{}` to change who you're matched with, or `{}` to answer some ",
+              "questions that help us find good matches. Changed your mind about someone? Type ",
+              "`{}` within {} minutes to take back your last response. Type `{}` to rewrite ",
+              "your bio, or `{}` to change your interests. Type `{}` to {} people you've ",
+              "declined again once they rewrite their bio. ",
+              "Type `{}` to take a break, or `{}` to delete your profile and start over.",
+            ),
+            Preferences::COMMAND,
+            Questionnaire::COMMAND,
+            User::UNDO_COMMAND,
+            Self::UNDO_WINDOW.as_secs() / 60,
+            User::BIO_COMMAND,
+            Interests::COMMAND,
+            User::RESURFACE_COMMAND,
+            if user.map_or(false, |user| user.resurface_declined) {
+              "stop seeing"
+            } else {
+              "see"
+            },
+            User::PAUSE_COMMAND,
+            User::DELETE_COMMAND,
+          )
+        }
+      },
+      Candidate { id } => {
+        let mut text = format!("New potential match:\n{}", tx.bio(id).await?);
+
+        if tx.super_liked(id, recipient_id).await? {
+          text.push_str(&format!(
+            "\n{} They super-liked you!",
+            Emoji::SuperLike.markup()
+          ));
+        }
+
+        let shared_tags = Self::shared_tags(tx, recipient_id, id).await?;
+
+        if !shared_tags.is_empty() {
+          text.push_str(&format!("\nYou both like: {}", shared_tags.join(", ")));
+        }
+
+        let compatibility =
+          Questionnaire::compatibility(&tx.answers(recipient_id).await?, &tx.answers(id).await?);
+
+        if let Some(compatibility) = compatibility {
+          text.push_str(&format!("\nQuestionnaire match: {}%", compatibility));
+        }
+
+        text
+      },
+      Bio => "Please enter a bio to show to other users.".into(),
+      Tags => format!(
+        concat!(
+          "What are you into? Enter some interests, separated by commas. ",
+          "Pick from this list by name or number, or add your own:{}\n",
+          "Or type `none` to skip.",
+        ),
+        Self::numbered(Interests::CURATED.iter().copied()),
+      ),
+      Match { id } => format!(
+        concat!(
+          "You matched with <@{}>:\n{}\nSend them a message!\n",
+          "React with {} or type `ok` to continue.",
+        ),
+        id,
+        tx.bio(id).await?,
+        Emoji::ThumbsUp.markup()
+      ),
+      Age => format!(
+        concat!(
+          "How old are you? Enter a number from this list:{}\n",
+          "Or type `skip`. Users under 18 are only matched with each other.",
+        ),
+        Self::numbered(AgeBracket::ALL.iter().copied().map(AgeBracket::label)),
+      ),
+      AgeRange => format!(
+        concat!(
+          "Which ages do you want to be matched with? Enter a number from this list, or a ",
+          "range like `2-4`:{}\n",
+          "Or type `any`.",
+        ),
+        Self::numbered(AgeBracket::ALL.iter().copied().map(AgeBracket::label)),
+      ),
+      UtcOffset => concat!(
+        "What's your timezone, as an offset from UTC like `+2`, `-5` or `+5:30`? ",
+        "Or type `skip`.",
+      )
+      .into(),
+      TimezoneWindow =>
+        "How many hours from your timezone can your matches' timezones be? Or type `any`.".into(),
+      Languages => concat!(
+        "Which languages do you speak? Enter them separated by commas, or type `skip`. ",
+        "Users who have both entered languages are only matched if they share one.",
+      )
+      .into(),
+      Connections => format!(
+        concat!(
+          "What are you looking for? Pick one or more from this list by name or number, ",
+          "separated by commas:{}\n",
+          "Or type `any`.",
+        ),
+        Self::numbered(Connection::ALL.iter().copied().map(Connection::name)),
+      ),
+      QuestionChoice { question } => match Questionnaire::question(question) {
+        Some(question) => format!(
+          concat!(
+            "{}{}\n",
+            "React with a number or type it to give your answer, or type `skip` to skip this ",
+            "question.",
+          ),
+          question.text,
+          Self::numbered(question.choices.iter().copied()),
+        ),
+        None => Self::missing_question(),
+      },
+      QuestionAccepted { question: index } => match Questionnaire::question(index) {
+        Some(question) => {
+          let accepted = tx
+            .answers(recipient_id)
+            .await?
+            .into_iter()
+            .find(|answer| answer.question == index)
+            .map(|answer| answer.accepted)
+            .unwrap_or_default();
+
+          let mut text = format!(
+            concat!(
+              "Which answers would you accept from a match?{}\n",
+              "React with numbers, then with {} when you're done, or type them, like `1 3`. ",
+              "Type `any` if you don't mind.",
+            ),
+            Self::numbered(question.choices.iter().copied()),
+            Emoji::ThumbsUp.markup(),
+          );
+
+          if !accepted.is_empty() {
+            text.push_str(&format!(
+              "\nAccepted so far: {}",
+              accepted
+                .iter()
+                .map(|choice| (choice + 1).to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+            ));
+          }
+
+          text
+        },
+        None => Self::missing_question(),
+      },
+      QuestionImportance { .. } => format!(
+        "How important is this question to you? React with a number or type it:{}",
+        Self::numbered(Importance::ALL.iter().copied().map(Importance::label)),
+      ),
+    };
+
+    Ok(text)
+  }
+
+  fn missing_question() -> String {
+    format!(
+      "This question is no longer available. Type `{}` to start again.",
+      Questionnaire::COMMAND
+    )
+  }
+
+  fn numbered<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    items
+      .enumerate()
+      .map(|(i, item)| format!("\n{}. {}", i + 1, item))
+      .collect()
+  }
+
+  async fn shared_tags(tx: &mut dyn StoreTransaction, a: UserId, b: UserId) -> Result<Vec<String>> {
+    let tags = |user: Option<User>| user.and_then(|user| user.tags).unwrap_or_default();
+
+    let theirs = tags(tx.load_user(b).await?);
+
+    Ok(
+      tags(tx.load_user(a).await?)
+        .into_iter()
+        .filter(|tag| theirs.contains(tag))
+        .collect(),
+    )
+  }
+
+  pub async fn prompt_text_outside_update_transaction(
+    &self,
+    recipient_id: UserId,
+    prompt: Prompt,
+  ) -> String {
+    let mut tx = self.store.begin().await.unwrap();
+    Db::prompt_text(tx.as_mut(), recipient_id, prompt)
+      .await
+      .unwrap()
+  }
+
+  #[cfg(test)]
+  async fn create_user(&self, expected_prompt: Prompt) -> UserId {
+    self
+      .create_user_in_pool(Self::DEFAULT_POOL, expected_prompt)
+      .await
+  }
+
+  #[cfg(test)]
+  async fn create_user_in_pool(&self, pool: &str, expected_prompt: Prompt) -> UserId {
+    let id = UserId(self.user_count().await.unwrap());
+
+    self.user_in_pool(id, pool).await.unwrap();
+
+    let update = Update {
+      action:      Some(Action::Welcome),
+      next_prompt: Prompt::Bio,
+    };
+
+    let tx = self.prepare(id, &update).await.unwrap();
+
+    tx.commit(MessageId(200)).await.unwrap();
+
+    let update = Update {
+      action:      Some(Action::SetBio {
+        text: format!("User {}'s bio!", id),
+      }),
+      next_prompt: Prompt::Tags,
+    };
+
+    let tx = self.prepare(id, &update).await.unwrap();
+
+    tx.commit(MessageId(200)).await.unwrap();
+
+    let update = Update {
+      action:      Some(Action::SetTags { tags: Vec::new() }),
+      next_prompt: Prompt::Quiescent,
+    };
+
+    let tx = self.prepare(id, &update).await.unwrap();
+
+    assert_eq!(tx.prompt(), expected_prompt);
+
+    tx.commit(MessageId(200)).await.unwrap();
+
+    id
+  }
+
+  #[cfg(test)]
+  async fn set_prompt(&self, recipient_id: UserId, prompt: Prompt) {
+    let mut tx = self.store.begin().await.unwrap();
+
+    let prompt_message = PromptMessage {
+      message_id: MessageId(0),
+      prompt,
+    };
+
+    tx.set_prompt(recipient_id, prompt_message, self.clock.now())
+      .await
+      .unwrap();
+
+    tx.commit().await.unwrap();
+  }
+
+  #[cfg(test)]
+  async fn response(&self, user: UserId, candidate: UserId) -> bool {
+    let mut tx = self.store.begin().await.unwrap();
+
+    tx.response(user, candidate).await.unwrap().unwrap()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  backend_test! {
+    async fn on_disk_database_is_persistant(context: TestContext) {
+      let url = match context.url {
+        Some(url) => url,
+        None => return,
+      };
+
+      assert_eq!(context.db.user_count().await.unwrap(), 0);
+
+      context.db.create_user(Prompt::Quiescent).await;
+
+      assert_eq!(context.db.user_count().await.unwrap(), 1);
+
+      drop(context.db);
+
+      let db = Db::connect_url(&url, PoolOptions::default())
+        .await
+        .unwrap();
+
+      assert_eq!(db.user_count().await.unwrap(), 1);
+    }
+  }
+
+  backend_test! {
+    async fn create_user(context: TestContext) {
+      let discord_id = UserId(100);
+
+      let db = context.db.clone().with_clock(Clock::Fixed(1000.0));
+
+      assert_eq!(db.user_count().await.unwrap(), 0);
+
+      let have = db.user(discord_id).await.unwrap();
+      let want = User {
+        id: 1,
+        prompt_message: None,
+        prompt_sent_at: None,
+        welcomed: false,
+        bio: None,
+        resurface_declined: false,
+        paused: false,
+        tags: None,
+        preferences: Preferences::default(),
+        answers: Vec::new(),
+        created_at: Some(1000.0),
+        updated_at: Some(1000.0),
+        last_active_at: None,
+        discord_id,
+      };
+      assert_eq!(have, want);
+
+      assert_eq!(db.user_count().await.unwrap(), 1);
+
+      let have = db.user(discord_id).await.unwrap();
+      assert_eq!(have, want);
+    }
+  }
+
+  backend_test! {
+    async fn welcome(context: TestContext) {
+      let discord_id = UserId(100);
+      let message_id = MessageId(200);
+
+      let at = |now: f64| context.db.clone().with_clock(Clock::Fixed(now));
+
+      let have = at(1000.0).user(discord_id).await.unwrap();
+      let want = User {
+        id: 1,
+        prompt_message: None,
+        prompt_sent_at: None,
+        welcomed: false,
+        bio: None,
+        resurface_declined: false,
+        paused: false,
+        tags: None,
+        preferences: Preferences::default(),
+        answers: Vec::new(),
+        created_at: Some(1000.0),
+        updated_at: Some(1000.0),
+        last_active_at: None,
+        discord_id,
+      };
+      assert_eq!(have, want);
+
+      let prompt_message = PromptMessage {
+        prompt: Prompt::Welcome,
+        message_id,
+      };
+
+      let update = Update {
+        action:      Some(Action::Welcome),
+        next_prompt: Prompt::Welcome,
+      };
+
+      let tx = at(2000.0).prepare(have.discord_id, &update).await.unwrap();
+
+      tx.commit(message_id).await.unwrap();
+
+      let have = context.db.user(discord_id).await.unwrap();
+      let want = User {
+        id: 1,
+        welcomed: true,
+        prompt_message: Some(prompt_message),
+        prompt_sent_at: Some(2000.0),
+        bio: None,
+        resurface_declined: false,
+        paused: false,
+        tags: None,
+        preferences: Preferences::default(),
+        answers: Vec::new(),
+        created_at: Some(1000.0),
+        updated_at: Some(2000.0),
+        last_active_at: Some(2000.0),
+        discord_id,
+      };
+      assert_eq!(have, want);
+    }
+  }
+
+  backend_test! {
+    async fn set_bio(context: TestContext) {
+      let discord_id = UserId(100);
+      let message_id = MessageId(200);
+
+      let at = |now: f64| context.db.clone().with_clock(Clock::Fixed(now));
+
+      let have = at(1000.0).user(discord_id).await.unwrap();
+      let want = User {
+        id: 1,
+        prompt_message: None,
+        prompt_sent_at: None,
+        welcomed: false,
+        bio: None,
+        resurface_declined: false,
+        paused: false,
+        tags: None,
+        preferences: Preferences::default(),
+        answers: Vec::new(),
+        created_at: Some(1000.0),
+        updated_at: Some(1000.0),
+        last_active_at: None,
+        discord_id,
+      };
+      assert_eq!(have, want);
+
+      let prompt_message = PromptMessage {
+        prompt: Prompt::Bio,
+        message_id,
+      };
+
+      let update = Update {
+        action:      Some(Action::SetBio {
+          text: "bio!".to_owned(),
+        }),
+        next_prompt: Prompt::Bio,
+      };
+
+      let tx = at(2000.0).prepare(have.discord_id, &update).await.unwrap();
+
+      tx.commit(message_id).await.unwrap();
+
+      let have = context.db.user(discord_id).await.unwrap();
+      let want = User {
+        id: 1,
+        welcomed: false,
+        prompt_message: Some(prompt_message),
+        prompt_sent_at: Some(2000.0),
+        bio: Some("bio!".to_owned()),
+        resurface_declined: false,
+        paused: false,
+        tags: None,
+        preferences: Preferences::default(),
+        answers: Vec::new(),
+        created_at: Some(1000.0),
+        updated_at: Some(2000.0),
+        last_active_at: Some(2000.0),
+        discord_id,
+      };
+      assert_eq!(have, want);
+    }
+  }
+
+  backend_test! {
+    async fn responses_only_update_activity(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let updated_at = context.db.user(b).await.unwrap().updated_at.unwrap();
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      context
+        .db
+        .clone()
+        .with_clock(Clock::Fixed(updated_at + 60.0))
+        .prepare(b, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(201))
+        .await
+        .unwrap();
+
+      let user = context.db.user(b).await.unwrap();
+      assert_eq!(user.updated_at, Some(updated_at));
+      assert_eq!(user.last_active_at, Some(updated_at + 60.0));
+      assert_eq!(user.prompt_sent_at, Some(updated_at + 60.0));
+    }
+  }
+
+  backend_test! {
+    async fn set_tags(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let tags = |tags: &[&str]| {
+        tags
+          .iter()
+          .map(|tag| (*tag).to_owned())
+          .collect::<Vec<String>>()
+      };
+
+      assert_eq!(context.db.user(a).await.unwrap().tags, Some(Vec::new()));
+
+      for (user_id, user_tags) in [(a, ["art", "music"]), (b, ["music", "rust"])] {
+        let update = Update {
+          action:      Some(Action::SetTags {
+            tags: tags(&user_tags),
+          }),
+          next_prompt: Prompt::Quiescent,
+        };
+
+        context
+          .db
+          .prepare(user_id, &update)
+          .await
+          .unwrap()
+          .commit(MessageId(0))
+          .await
+          .unwrap();
+      }
+
+      assert_eq!(
+        context.db.user(a).await.unwrap().tags,
+        Some(tags(&["art", "music"]))
+      );
+      assert_eq!(
+        context.db.user(b).await.unwrap().tags,
+        Some(tags(&["music", "rust"]))
+      );
+
+      assert!(context
+        .db
+        .prompt_text_outside_update_transaction(b, Prompt::Candidate { id: a })
+        .await
+        .ends_with("\nYou both like: music"));
+
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(
+        tx.candidates(a)
+          .await
+          .unwrap()
+          .iter()
+          .map(|candidate| candidate.shared_tags)
+          .collect::<Vec<u64>>(),
+        &[1]
+      );
+    }
+  }
+
+  backend_test! {
+    async fn expect_candidate(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      context.db.create_user(Prompt::Candidate { id: a }).await;
+    }
+  }
+
+  backend_test! {
+    async fn filter_out_accepted_candidates(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+    }
+  }
+
+  backend_test! {
+    async fn set_preferences(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+
+      let preferences = Preferences {
+        age:             Some(AgeBracket::From35To44),
+        ages:            Some((AgeBracket::From25To34, AgeBracket::From45)),
+        connections:     vec![Connection::Friends, Connection::Study],
+        languages:       vec!["english".into(), "portuguese".into()],
+        timezone_window: Some(3),
+        utc_offset:      Some(-210),
+      };
+
+      let update = Update {
+        action:      Some(Action::SetPreferences {
+          preferences: preferences.clone(),
+        }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      context
+        .db
+        .prepare(a, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(201))
+        .await
+        .unwrap();
+
+      assert_eq!(context.db.user(a).await.unwrap().preferences, preferences);
+    }
+  }
+
+  backend_test! {
+    async fn filter_out_candidates_with_incompatible_preferences(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+
+      let update = Update {
+        action:      Some(Action::SetPreferences {
+          preferences: Preferences {
+            age:  Some(AgeBracket::From25To34),
+            ages: Some((AgeBracket::From25To34, AgeBracket::From25To34)),
+            ..Preferences::default()
+          },
+        }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      context
+        .db
+        .prepare(a, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(201))
+        .await
+        .unwrap();
+
+      // `a` would satisfy `b`'s unset preferences, but `b` doesn't satisfy
+      // `a`'s
+      let b = context.db.create_user(Prompt::Quiescent).await;
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+    }
+  }
+
+  backend_test! {
+    async fn candidates_who_become_incompatible_are_replaced(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::SetPreferences {
+          preferences: Preferences {
+            age:  Some(AgeBracket::From25To34),
+            ages: Some((AgeBracket::From25To34, AgeBracket::From25To34)),
+            ..Preferences::default()
+          },
+        }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      context
+        .db
+        .prepare(a, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(201))
+        .await
+        .unwrap();
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Candidate { id: a },
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+    }
+  }
+
+  backend_test! {
+    async fn set_answers(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      let c = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let answer = |choice, accepted: &[u64]| Answer {
+        accepted: accepted.to_vec(),
+        importance: Importance::Very,
+        ..Answer::new(0, choice)
+      };
+
+      // `a`'s second answer replaces their first
+      for (user_id, user_answer) in vec![
+        (a, answer(1, &[])),
+        (b, answer(1, &[])),
+        (c, answer(0, &[])),
+        (a, answer(0, &[0])),
+      ] {
+        let update = Update {
+          action:      Some(Action::SetAnswer {
+            answer: user_answer,
+          }),
+          next_prompt: Prompt::Quiescent,
+        };
+
+        context
+          .db
+          .prepare(user_id, &update)
+          .await
+          .unwrap()
+          .commit(MessageId(0))
+          .await
+          .unwrap();
+      }
+
+      assert_eq!(context.db.user(a).await.unwrap().answers, vec![answer(0, &[0])]);
+
+      assert!(context
+        .db
+        .prompt_text_outside_update_transaction(a, Prompt::Candidate { id: b })
+        .await
+        .ends_with("\nQuestionnaire match: 0%"));
+
+      assert!(context
+        .db
+        .prompt_text_outside_update_transaction(a, Prompt::Candidate { id: c })
+        .await
+        .ends_with("\nQuestionnaire match: 100%"));
+
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+      context.db.set_prompt(c, Prompt::Quiescent).await;
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Candidate { id: c });
+    }
+  }
+
+  backend_test! {
+    async fn filter_out_declined_candidates(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+    }
+  }
+
+  backend_test! {
+    async fn filter_out_candidates_that_have_declined_user(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+    }
+  }
+
+  backend_test! {
+    async fn dont_filter_candidates_that_have_accepted_user(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Candidate { id: b });
+    }
+  }
+
+  backend_test! {
+    async fn allow_multiple_responses(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      assert!(context.db.response(b, a).await);
+
+      let update = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      assert!(!context.db.response(b, a).await);
+    }
+  }
+
+  backend_test! {
+    async fn show_match_prompt_after_mutual_acceptance(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: b }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Match { id: b });
+
+      tx.commit(MessageId(201)).await.unwrap();
+    }
+  }
+
+  backend_test! {
+    async fn super_likes_are_shown_to_candidates(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::SuperLikeCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      context
+        .db
+        .prepare(b, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(201))
+        .await
+        .unwrap();
+
+      assert!(context.db.response(b, a).await);
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Candidate { id: b });
+
+      drop(tx);
+
+      assert!(context
+        .db
+        .prompt_text_outside_update_transaction(a, Prompt::Candidate { id: b })
+        .await
+        .contains("\n:star: They super-liked you!"));
+
+      assert!(!context
+        .db
+        .prompt_text_outside_update_transaction(b, Prompt::Candidate { id: a })
+        .await
+        .contains("super-liked"));
+    }
+  }
+
+  backend_test! {
+    async fn reported_candidates_are_declined(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let update = Update {
+        action:      Some(Action::ReportCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      assert!(!context.db.response(b, a).await);
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = context.db.prepare(a, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+    }
+  }
+
+  backend_test! {
+    async fn deferred_candidates_are_shown_again_after_the_cooldown(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let at = |now: f64| {
+        context
+          .db
+          .clone()
+          .with_defer_cooldown(Duration::from_secs(60))
+          .with_clock(Clock::Fixed(now))
+      };
+
+      let update = Update {
+        action:      Some(Action::DeferCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = at(1000.0).prepare(b, &update).await.unwrap();
+
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+
+      tx.commit(MessageId(201)).await.unwrap();
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    tx.commit().await?;
+      let tx = at(1059.0).prepare(b, &update).await.unwrap();
 
-    Ok(())
-  }
+      assert_eq!(tx.prompt, Prompt::Quiescent);
 
-  async fn welcome(tx: &mut Transaction<'_>, discord_id: UserId) -> Result<()> {
-    let discord_id = discord_id.store();
+      drop(tx);
 
-    sqlx::query!(
-      "UPDATE users SET welcomed = TRUE WHERE discord_id = $1",
-      discord_id
-    )
-    .execute(tx)
-    .await?;
+      let tx = at(1060.0).prepare(b, &update).await.unwrap();
 
-    Ok(())
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+    }
   }
 
-  async fn set_bio(tx: &mut Transaction<'_>, discord_id: UserId, text: &str) -> Result<()> {
-    let discord_id = discord_id.store();
+  backend_test! {
+    async fn declined_candidates_who_rewrite_their_bio_are_resurfaced(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    sqlx::query!(
-      "UPDATE users SET bio = $1 WHERE discord_id = $2",
-      text,
-      discord_id
-    )
-    .execute(tx)
-    .await?;
+      let start = Clock::System.now().ceil();
 
-    Ok(())
-  }
+      let at = |now: f64| {
+        context
+          .db
+          .clone()
+          .with_resurface_delay(Duration::from_secs(60))
+          .with_clock(Clock::Fixed(start + now))
+      };
 
-  #[cfg(test)]
-  async fn user_count(&self) -> Result<u64> {
-    #[allow(clippy::cast_sign_loss)]
-    Ok(
-      sqlx::query!("SELECT COUNT(*) as count FROM users")
-        .fetch_one(&self.pool)
-        .await?
-        .count
-        .unwrap_or(0) as u64,
-    )
-  }
+      let update = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-  pub async fn prompt_text(tx: &mut Transaction<'_>, prompt: Prompt) -> Result<String> {
-    use Prompt::*;
+      let tx = at(0.0).prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(201)).await.unwrap();
+
+      for (i, content) in [User::BIO_COMMAND, "Something else entirely"]
+        .iter()
+        .enumerate()
+      {
+        let update = context
+          .db
+          .user(a)
+          .await
+          .unwrap()
+          .update(&Response::message(*content));
 
-    let text = match prompt {
-      Welcome => format!(
-        concat!(
-          "Hi!\n",
-          "Quwue is a bot that matches you with other Discord users.\n",
-          "Your Discord tag will only be revealed to matches.\n",
-          "To start, you'll need to set up your profile.\n",
-          "React with {} or type `ok` to continue.",
-        ),
-        Emoji::ThumbsUp.markup()
-      ),
-      Quiescent => "You've seen all available matches. We'll message you when we have new matches \
-                    to show you!"
-        .into(),
-      Candidate { id } => {
-        format!("New potential match:\n{}", Self::bio(tx, id).await?)
-      },
-      Bio => "Please enter a bio to show to other users.".into(),
-      Match { id } => format!(
-        concat!(
-          "You matched with <@{}>:\n{}\nSend them a message!\n",
-          "React with {} or type `ok` to continue.",
-        ),
-        id,
-        Self::bio(tx, id).await?,
-        Emoji::ThumbsUp.markup()
-      ),
-    };
+        at(1.0)
+          .prepare(a, &update)
+          .await
+          .unwrap()
+          .commit(MessageId(202 + i as u64))
+          .await
+          .unwrap();
+      }
 
-    Ok(text)
-  }
+      assert_eq!(
+        context.db.user(a).await.unwrap().bio.as_deref(),
+        Some("Something else entirely")
+      );
 
-  async fn bio(tx: &mut Transaction<'_>, id: UserId) -> Result<String> {
-    let id_storage = id.store();
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let row = sqlx::query!("SELECT bio from users where discord_id = $1", id_storage)
-      .fetch_optional(tx)
-      .await?;
+      let tx = at(60.0).prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      drop(tx);
 
-    row
-      .ok_or(Error::UserUnknown { id })?
-      .bio
-      .ok_or(Error::UserMissingBio { id })
-  }
+      let update = Update {
+        action:      Some(Action::SetResurfaceDeclined { enabled: true }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-  async fn respond_to_candidate(
-    tx: &mut Transaction<'_>,
-    user_id: UserId,
-    candidate_id: UserId,
-    response: bool,
-  ) -> Result<()> {
-    let user_id = user_id.store();
-    let candidate_id = candidate_id.store();
-
-    sqlx::query!(
-      "INSERT INTO responses
-        (discord_id, candidate_id, response, dismissed)
-      VALUES
-        ($1, $2, $3, FALSE)
-      ON CONFLICT (discord_id, candidate_id) DO UPDATE SET
-        discord_id = $1,
-        candidate_id = $2,
-        response = $3,
-        dismissed = FALSE
-      ",
-      user_id,
-      candidate_id,
-      response
-    )
-    .execute(tx)
-    .await?;
+      let tx = at(59.0).prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(204)).await.unwrap();
 
-    Ok(())
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+
+      let tx = at(60.0).prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+    }
   }
 
-  async fn dismiss_match(
-    tx: &mut Transaction<'_>,
-    user_id: UserId,
-    match_id: UserId,
-  ) -> Result<()> {
-    let user_id = user_id.store();
-    let match_id = match_id.store();
+  backend_test! {
+    async fn recent_responses_can_be_undone(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    sqlx::query!(
-      "UPDATE responses SET dismissed = TRUE WHERE discord_id = $1 AND candidate_id = $2",
-      user_id,
-      match_id
-    )
-    .execute(tx)
-    .await?;
+      let at = |now: f64| context.db.clone().with_clock(Clock::Fixed(now));
 
-    Ok(())
-  }
+      let decline = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-  pub async fn prompt_text_outside_update_transaction(&self, prompt: Prompt) -> String {
-    let mut tx = self.pool.begin().await.unwrap();
-    Db::prompt_text(&mut tx, prompt).await.unwrap()
-  }
+      let undo = Update {
+        action:      Some(Action::Undo),
+        next_prompt: Prompt::Quiescent,
+      };
 
-  #[cfg(test)]
-  async fn create_user(&self, expected_prompt: Prompt) -> UserId {
-    let id = sqlx::query!(
-      "SELECT
-        discord_id
-      FROM
-        users
-      ORDER BY
-        discord_id DESC
-      "
-    )
-    .fetch_optional(&self.pool)
-    .await
-    .unwrap()
-    .map_or(0, |row| row.discord_id + 1);
+      let tx = at(1000.0).prepare(b, &decline).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(201)).await.unwrap();
 
-    let id = UserId(u64::load(id).unwrap());
+      let tx = at(1599.0).prepare(b, &undo).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+      assert_eq!(tx.retracted(), Vec::new());
+      tx.commit(MessageId(202)).await.unwrap();
 
-    self.user(id).await.unwrap();
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(tx.response(b, a).await.unwrap(), None);
+      drop(tx);
 
-    let update = Update {
-      action:      Some(Action::Welcome),
-      next_prompt: Prompt::Bio,
-    };
+      let tx = at(2000.0).prepare(b, &decline).await.unwrap();
+      tx.commit(MessageId(203)).await.unwrap();
 
-    let tx = self.prepare(id, &update).await.unwrap();
+      let tx = at(2600.0).prepare(b, &undo).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(204)).await.unwrap();
 
-    tx.commit(MessageId(200)).await.unwrap();
+      assert!(!context.db.response(b, a).await);
+    }
+  }
 
-    let update = Update {
-      action:      Some(Action::SetBio {
-        text: format!("User {}'s bio!", id),
-      }),
-      next_prompt: Prompt::Quiescent,
-    };
+  backend_test! {
+    async fn matches_cannot_be_undone(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      context.db.set_prompt(a, Prompt::Candidate { id: b }).await;
 
-    let tx = self.prepare(id, &update).await.unwrap();
+      let at = |now: f64| context.db.clone().with_clock(Clock::Fixed(now));
 
-    assert_eq!(tx.prompt(), expected_prompt);
+      let accept = |id| Update {
+        action:      Some(Action::AcceptCandidate { id }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    tx.commit(MessageId(200)).await.unwrap();
+      let undo = Update {
+        action:      Some(Action::Undo),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    id
-  }
+      let tx = at(1000.0).prepare(a, &accept(b)).await.unwrap();
+      tx.commit(MessageId(201)).await.unwrap();
 
-  #[cfg(test)]
-  async fn set_prompt(&self, recipient_id: UserId, prompt: Prompt) {
-    let (discriminant, payload) = prompt.store();
-    let message_id = MessageId(0).store();
-    let recipient_id = recipient_id.store();
-
-    sqlx::query!(
-      "INSERT INTO prompts
-        (discriminant, payload, message_id, recipient_discord_id)
-      VALUES
-        ($1, $2, $3, $4)
-      ON CONFLICT (recipient_discord_id) DO UPDATE SET
-        discriminant = $1,
-        payload = $2,
-        message_id = $3,
-        recipient_discord_id = $4",
-      discriminant,
-      payload,
-      message_id,
-      recipient_id
-    )
-    .execute(&self.pool)
-    .await
-    .unwrap();
-  }
+      let tx = at(1001.0).prepare(b, &accept(a)).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Match { id: a });
+      tx.commit(MessageId(202)).await.unwrap();
 
-  #[cfg(test)]
-  async fn response(&self, user: UserId, candidate: UserId) -> bool {
-    let user = user.store();
-    let candidate = candidate.store();
-    let row = sqlx::query!(
-      "SELECT response FROM responses WHERE discord_id = $1 AND candidate_id = $2",
-      user,
-      candidate
-    )
-    .fetch_one(&self.pool)
-    .await
-    .unwrap();
+      let tx = at(1002.0).prepare(b, &undo).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Match { id: a });
+      assert_eq!(tx.retracted(), Vec::new());
+      tx.commit(MessageId(203)).await.unwrap();
 
-    row.response
+      assert!(context.db.response(b, a).await);
+    }
   }
-}
 
-#[cfg(test)]
-mod tests {
-  use super::*;
+  backend_test! {
+    async fn undone_accepts_are_retracted(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-  struct TestContext {
-    db:      Db,
-    db_name: String,
-  }
+      let interrupt = Interrupt {
+        channel_id:   None,
+        user_id:      b,
+        candidate_id: a,
+      };
 
-  impl TestContext {
-    async fn new() -> Self {
-      static TEST_DATABASE_NUMBER: AtomicUsize = AtomicUsize::new(0);
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-      let test_database_number = TEST_DATABASE_NUMBER.fetch_add(1, Ordering::Relaxed);
+      let tx = context.db.prepare(b, &update).await.unwrap();
+      tx.commit(MessageId(201)).await.unwrap();
 
-      let db_name = format!(
-        "quwue-test-{}-{}",
-        std::time::SystemTime::now()
-          .duration_since(std::time::SystemTime::UNIX_EPOCH)
-          .unwrap()
-          .as_millis(),
-        test_database_number,
-      );
+      context.db.enqueue_interrupt(0, interrupt).await.unwrap();
+      guard_unwrap!(let Some((_, Some(tx))) = context.db.prepare_interrupt(0..1).await.unwrap());
+      assert_eq!(tx.prompt, Prompt::Candidate { id: b });
+      tx.commit(MessageId(202)).await.unwrap();
 
-      let db = Db::connect(&db_name).await.unwrap();
+      let update = Update {
+        action:      Some(Action::Undo),
+        next_prompt: Prompt::Quiescent,
+      };
 
-      TestContext { db, db_name }
+      let tx = context.db.prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+      assert_eq!(tx.retracted(), vec![a]);
+      tx.commit(MessageId(203)).await.unwrap();
+
+      context.db.enqueue_interrupt(0, interrupt).await.unwrap();
+      guard_unwrap!(let Some((_, Some(tx))) = context.db.prepare_interrupt(0..1).await.unwrap());
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(204)).await.unwrap();
+
+      context.db.enqueue_interrupt(0, interrupt).await.unwrap();
+      assert!(matches!(
+        context.db.prepare_interrupt(0..1).await.unwrap(),
+        Some((_, None))
+      ));
     }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn on_disk_database_is_persistant() {
-    let TestContext { db, db_name } = TestContext::new().await;
+  #[test]
+  fn rewritten() {
+    assert!(!Db::rewritten("I like cats.", "i LIKE cats!!"));
+    assert!(!Db::rewritten(
+      "I like cats and dogs",
+      "I like cats and birds"
+    ));
+    assert!(Db::rewritten("I like cats", "I like dogs and birds"));
+    assert!(Db::rewritten("I like cats", "Gardening is my passion"));
+  }
+
+  backend_test! {
+    async fn prompt_messages(context: TestContext) {
+      assert_eq!(context.db.prompt_messages().await.unwrap(), Vec::new());
 
-    assert_eq!(db.user_count().await.unwrap(), 0);
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    db.create_user(Prompt::Quiescent).await;
+      context.db.user(UserId(100)).await.unwrap();
 
-    assert_eq!(db.user_count().await.unwrap(), 1);
+      assert_eq!(context.db.prompt_messages().await.unwrap(), vec![
+        (a, PromptMessage {
+          prompt:     Prompt::Quiescent,
+          message_id: MessageId(200),
+        }),
+        (b, PromptMessage {
+          prompt:     Prompt::Candidate { id: a },
+          message_id: MessageId(200),
+        }),
+      ]);
+    }
+  }
 
-    drop(db);
+  backend_test! {
+    async fn current_prompt(context: TestContext) {
+      assert_eq!(context.db.current_prompt(UserId(100)).await.unwrap(), None);
 
-    let db = Db::connect(&db_name).await.unwrap();
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    assert_eq!(db.user_count().await.unwrap(), 1);
+      assert_eq!(
+        context.db.current_prompt(a).await.unwrap(),
+        Some(Prompt::Quiescent)
+      );
+      assert_eq!(
+        context.db.current_prompt(b).await.unwrap(),
+        Some(Prompt::Candidate { id: a })
+      );
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn create_user() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn events_are_only_processed_once(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+
+      let event = EventId::Message { id: MessageId(300) };
 
-    let discord_id = UserId(100);
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    assert_eq!(context.db.user_count().await.unwrap(), 0);
+      let tx = context
+        .db
+        .prepare_event(&event, b, &update)
+        .await
+        .unwrap()
+        .unwrap();
 
-    let have = context.db.user(discord_id).await.unwrap();
-    let want = User {
-      id: 1,
-      prompt_message: None,
-      welcomed: false,
-      bio: None,
-      discord_id,
-    };
-    assert_eq!(have, want);
+      assert_eq!(tx.prompt, Prompt::Quiescent);
 
-    assert_eq!(context.db.user_count().await.unwrap(), 1);
+      tx.commit(MessageId(301)).await.unwrap();
 
-    let have = context.db.user(discord_id).await.unwrap();
-    assert_eq!(have, want);
+      assert!(context
+        .db
+        .prepare_event(&event, b, &update)
+        .await
+        .unwrap()
+        .is_none());
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn welcome() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn uncommitted_events_are_not_recorded(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
 
-    let discord_id = UserId(100);
-    let message_id = MessageId(200);
+      let event = EventId::Reaction {
+        message_id: MessageId(200),
+        user_id:    a,
+        emoji:      "👍".into(),
+      };
 
-    let have = context.db.user(discord_id).await.unwrap();
-    let want = User {
-      id: 1,
-      prompt_message: None,
-      welcomed: false,
-      bio: None,
-      discord_id,
-    };
-    assert_eq!(have, want);
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let prompt_message = PromptMessage {
-      prompt: Prompt::Welcome,
-      message_id,
-    };
+      let tx = context.db.prepare_event(&event, a, &update).await.unwrap();
 
-    let update = Update {
-      action:      Some(Action::Welcome),
-      next_prompt: Prompt::Welcome,
-    };
+      assert!(tx.is_some());
 
-    let tx = context.db.prepare(have.discord_id, &update).await.unwrap();
+      drop(tx);
 
-    tx.commit(message_id).await.unwrap();
+      let tx = context.db.prepare_event(&event, a, &update).await.unwrap();
 
-    let have = context.db.user(discord_id).await.unwrap();
-    let want = User {
-      id: 1,
-      welcomed: true,
-      prompt_message: Some(prompt_message),
-      bio: None,
-      discord_id,
-    };
-    assert_eq!(have, want);
+      assert!(tx.is_some());
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn set_bio() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn old_events_are_pruned(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
 
-    let discord_id = UserId(100);
-    let message_id = MessageId(200);
+      let old = EventId::Message { id: MessageId(400) };
+      let new = EventId::Message { id: MessageId(401) };
 
-    let have = context.db.user(discord_id).await.unwrap();
-    let want = User {
-      id: 1,
-      prompt_message: None,
-      welcomed: false,
-      bio: None,
-      discord_id,
-    };
-    assert_eq!(have, want);
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let prompt_message = PromptMessage {
-      prompt: Prompt::Bio,
-      message_id,
-    };
+      let retention = Db::EVENT_RETENTION.as_secs_f64();
 
-    let update = Update {
-      action:      Some(Action::SetBio {
-        text: "bio!".to_owned(),
-      }),
-      next_prompt: Prompt::Bio,
-    };
+      for (event, at) in [(&old, 0.0), (&new, retention)] {
+        let db = context.db.clone().with_clock(Clock::Fixed(at));
+        let tx = db.prepare_event(event, a, &update).await.unwrap().unwrap();
+        tx.commit(MessageId(500)).await.unwrap();
+      }
 
-    let tx = context.db.prepare(have.discord_id, &update).await.unwrap();
+      let db = context
+        .db
+        .clone()
+        .with_clock(Clock::Fixed(retention + 1.0));
 
-    tx.commit(message_id).await.unwrap();
+      assert_eq!(db.prune_events().await.unwrap(), 1);
 
-    let have = context.db.user(discord_id).await.unwrap();
-    let want = User {
-      id: 1,
-      welcomed: false,
-      prompt_message: Some(prompt_message),
-      bio: Some("bio!".to_owned()),
-      discord_id,
-    };
-    assert_eq!(have, want);
+      assert!(db.prepare_event(&old, a, &update).await.unwrap().is_some());
+      assert!(db.prepare_event(&new, a, &update).await.unwrap().is_none());
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn expect_candidate() {
-    let context = TestContext::new().await;
-
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    context.db.create_user(Prompt::Candidate { id: a }).await;
-  }
+  backend_test! {
+    async fn leases_are_exclusive(context: TestContext) {
+      let duration = Duration::from_secs(60);
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn filter_out_accepted_candidates() {
-    let context = TestContext::new().await;
+      assert_eq!(context.db.lease_holder("bot").await.unwrap(), None);
 
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      assert!(context.db.acquire_lease("bot", "a", duration).await.unwrap());
+      assert!(!context.db.acquire_lease("bot", "b", duration).await.unwrap());
+      assert!(context.db.acquire_lease("bot", "a", duration).await.unwrap());
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      assert_eq!(
+        context.db.lease_holder("bot").await.unwrap(),
+        Some("a".into())
+      );
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      context.db.release_lease("bot", "b").await.unwrap();
+      assert!(!context.db.acquire_lease("bot", "b", duration).await.unwrap());
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      context.db.release_lease("bot", "a").await.unwrap();
+      assert!(context.db.acquire_lease("bot", "b", duration).await.unwrap());
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn filter_out_declined_candidates() {
-    let context = TestContext::new().await;
-
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+  backend_test! {
+    async fn expired_leases_can_be_acquired(context: TestContext) {
+      assert!(context
+        .db
+        .acquire_lease("bot", "a", Duration::from_millis(0))
+        .await
+        .unwrap());
+
+      tokio::time::sleep(Duration::from_millis(10)).await;
+
+      assert!(context
+        .db
+        .acquire_lease("bot", "b", Duration::from_secs(60))
+        .await
+        .unwrap());
+
+      assert_eq!(
+        context.db.lease_holder("bot").await.unwrap(),
+        Some("b".into())
+      );
+    }
+  }
 
-    let update = Update {
-      action:      Some(Action::DeclineCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+  backend_test! {
+    async fn live_lease_holders_are_ordered_by_seniority(context: TestContext) {
+      let duration = Duration::from_secs(60);
+
+      context
+        .db
+        .acquire_lease("instance:b", "b", duration)
+        .await
+        .unwrap();
+      context
+        .db
+        .acquire_lease("instance:a", "a", duration)
+        .await
+        .unwrap();
+      context
+        .db
+        .acquire_lease("instance:c", "c", Duration::from_millis(0))
+        .await
+        .unwrap();
+      context.db.acquire_lease("shard:0", "a", duration).await.unwrap();
+
+      tokio::time::sleep(Duration::from_millis(10)).await;
+
+      context
+        .db
+        .acquire_lease("instance:b", "b", duration)
+        .await
+        .unwrap();
+
+      assert_eq!(
+        context.db.live_lease_holders("instance:").await.unwrap(),
+        &["b", "a"]
+      );
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      context.db.release_leases("a").await.unwrap();
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      assert_eq!(
+        context.db.live_lease_holders("instance:").await.unwrap(),
+        &["b"]
+      );
+      assert_eq!(context.db.lease_holder("shard:0").await.unwrap(), None);
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn filter_out_candidates_that_have_declined_user() {
-    let context = TestContext::new().await;
-
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+  backend_test! {
+    async fn interrupts_are_dequeued_by_shard(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    let update = Update {
-      action:      Some(Action::DeclineCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      let first = Interrupt {
+        user_id:      b,
+        candidate_id: a,
+        channel_id:   None,
+      };
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      let second = Interrupt {
+        user_id:      a,
+        candidate_id: b,
+        channel_id:   Some(ChannelId(5)),
+      };
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      context.db.enqueue_interrupt(0, first).await.unwrap();
+      context.db.enqueue_interrupt(1, second).await.unwrap();
 
-    tx.commit(MessageId(201)).await.unwrap();
+      let (interrupt, tx) = context.db.prepare_interrupt(1..2).await.unwrap().unwrap();
+      assert_eq!(interrupt, second);
+      tx.unwrap().commit(MessageId(1)).await.unwrap();
 
-    let update = Update {
-      action:      None,
-      next_prompt: Prompt::Quiescent,
-    };
+      assert!(context.db.prepare_interrupt(1..2).await.unwrap().is_none());
 
-    let tx = context.db.prepare(a, &update).await.unwrap();
+      let (interrupt, tx) = context.db.prepare_interrupt(0..2).await.unwrap().unwrap();
+      assert_eq!(interrupt, first);
+      assert!(tx.is_none());
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      assert!(context.db.prepare_interrupt(0..2).await.unwrap().is_none());
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn dont_filter_candidates_that_have_accepted_user() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn interrupts_stay_queued_until_delivered(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      let interrupt = Interrupt {
+        user_id:      a,
+        candidate_id: b,
+        channel_id:   None,
+      };
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      context.db.enqueue_interrupt(0, interrupt).await.unwrap();
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      let (_, tx) = context.db.prepare_interrupt(0..1).await.unwrap().unwrap();
+      drop(tx);
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      let (dequeued, tx) = context.db.prepare_interrupt(0..1).await.unwrap().unwrap();
+      assert_eq!(dequeued, interrupt);
+      tx.unwrap().commit(MessageId(1)).await.unwrap();
 
-    tx.commit(MessageId(201)).await.unwrap();
+      assert!(context.db.prepare_interrupt(0..1).await.unwrap().is_none());
+    }
+  }
 
-    let update = Update {
-      action:      None,
-      next_prompt: Prompt::Quiescent,
-    };
+  backend_test! {
+    async fn candidates_who_have_accepted_are_chosen_first(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      let c = context.db.create_user(Prompt::Candidate { id: a }).await;
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+      context.db.set_prompt(c, Prompt::Quiescent).await;
+
+      let accepted = |candidates: Vec<Candidate>| {
+        candidates
+          .iter()
+          .map(|candidate| (candidate.discord_id, candidate.accepted))
+          .collect::<Vec<(UserId, bool)>>()
+      };
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(
+        accepted(tx.candidates(a).await.unwrap()),
+        &[(b, false), (c, false)]
+      );
 
-    let tx = context.db.prepare(a, &update).await.unwrap();
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+      context
+        .db
+        .prepare(c, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(0))
+        .await
+        .unwrap();
+      context.db.set_prompt(c, Prompt::Quiescent).await;
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(
+        accepted(tx.candidates(a).await.unwrap()),
+        &[(b, false), (c, true)]
+      );
 
-    assert_eq!(tx.prompt, Prompt::Candidate { id: b });
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
+      let tx = context.db.prepare(a, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: c });
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn allow_multiple_responses() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn showing_candidates_records_exposure(context: TestContext) {
+      let db = context.db.clone().with_clock(Clock::Fixed(1000.0));
 
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      let a = db.create_user(Prompt::Quiescent).await;
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      let mut tx = db.store.begin().await.unwrap();
+      assert_eq!(tx.exposure(a).await.unwrap(), (0.0, None));
+      drop(tx);
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      let b = db.create_user(Prompt::Candidate { id: a }).await;
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      let mut tx = db.store.begin().await.unwrap();
+      assert_eq!(tx.exposure(a).await.unwrap(), (1.0, Some(1000.0)));
+      assert_eq!(tx.exposure(b).await.unwrap(), (0.0, None));
+      assert!(matches!(
+        tx.exposure(UserId(100)).await,
+        Err(Error::UserUnknown { .. })
+      ));
+    }
+  }
 
-    tx.commit(MessageId(201)).await.unwrap();
+  backend_test! {
+    async fn candidate_queues_are_maintained_incrementally(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+
+      let queued = |candidates: Vec<Candidate>| {
+        candidates
+          .iter()
+          .map(|candidate| candidate.discord_id)
+          .collect::<Vec<UserId>>()
+      };
 
-    assert!(context.db.response(b, a).await);
+      // `b` was added to `a`'s queue when they onboarded
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(queued(tx.candidates(a).await.unwrap()), &[b]);
+      assert_eq!(queued(tx.candidates(b).await.unwrap()), &[a]);
+      drop(tx);
 
-    let update = Update {
-      action:      Some(Action::DeclineCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      let update = Update {
+        action:      Some(Action::DeclineCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
+      context
+        .db
+        .prepare(b, &update)
+        .await
+        .unwrap()
+        .commit(MessageId(0))
+        .await
+        .unwrap();
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert!(tx.candidates(a).await.unwrap().is_empty());
+      assert!(tx.candidates(b).await.unwrap().is_empty());
+      assert_eq!(tx.refill_candidate_queue(a, 10).await.unwrap(), 0);
+    }
+  }
+
+  backend_test! {
+    async fn exhausted_candidate_queues_are_not_rescanned(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert!(tx.queue_exhausted(a).await.unwrap());
+      assert!(!tx.queue_exhausted(b).await.unwrap());
+      tx.dequeue_candidate(a, b).await.unwrap();
+      assert!(tx.candidates(a).await.unwrap().is_empty());
+      tx.commit().await.unwrap();
+
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      let tx = context.db.prepare(a, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(0)).await.unwrap();
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      let mut tx = context.db.store.begin().await.unwrap();
+      tx.set_queue_exhausted(a, false).await.unwrap();
+      tx.commit().await.unwrap();
 
-    tx.commit(MessageId(201)).await.unwrap();
+      let tx = context.db.prepare(a, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: b });
+      tx.commit(MessageId(0)).await.unwrap();
 
-    assert!(!context.db.response(b, a).await);
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert!(!tx.queue_exhausted(a).await.unwrap());
+      tx.dequeue_candidate(a, b).await.unwrap();
+      assert_eq!(tx.refill_candidate_queue(a, 0).await.unwrap(), 0);
+      assert_eq!(tx.refill_candidate_queue(a, 10).await.unwrap(), 1);
+      assert_eq!(tx.refill_candidate_queue(a, 10).await.unwrap(), 0);
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn show_match_prompt_after_mutual_acceptance() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn exhausted_candidate_queues_are_offered_new_users(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+      let c = context.db.create_user(Prompt::Candidate { id: a }).await;
+      context.db.set_prompt(b, Prompt::Quiescent).await;
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      tx.dequeue_candidate(a, b).await.unwrap();
+      tx.dequeue_candidate(c, b).await.unwrap();
+      tx.offer_candidate(b, 0).await.unwrap();
+      assert_eq!(
+        tx.candidates(a)
+          .await
+          .unwrap()
+          .iter()
+          .map(|candidate| candidate.discord_id)
+          .collect::<Vec<UserId>>(),
+        &[b]
+      );
+      assert_eq!(
+        tx.candidates(c)
+          .await
+          .unwrap()
+          .iter()
+          .map(|candidate| candidate.discord_id)
+          .collect::<Vec<UserId>>(),
+        &[a]
+      );
+    }
+  }
 
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
+  backend_test! {
+    async fn paused_users_are_not_candidates(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
+      let update = Update {
+        action:      Some(Action::SetPaused { paused: true }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let tx = context.db.prepare(b, &update).await.unwrap();
+      let tx = context.db.prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(201)).await.unwrap();
 
-    assert_eq!(tx.prompt, Prompt::Quiescent);
+      assert!(context.db.user(b).await.unwrap().paused);
 
-    tx.commit(MessageId(201)).await.unwrap();
+      let update = Update {
+        action:      None,
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: b }),
-      next_prompt: Prompt::Quiescent,
-    };
+      let tx = context.db.prepare(a, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(202)).await.unwrap();
+
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(tx.refill_candidate_queue(a, 10).await.unwrap(), 0);
+      assert!(Db::prompt_text(&mut *tx, b, Prompt::Quiescent)
+        .await
+        .unwrap()
+        .starts_with("You're paused"));
+      drop(tx);
+
+      let update = Update {
+        action:      Some(Action::SetPaused { paused: false }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    let tx = context.db.prepare(a, &update).await.unwrap();
+      let tx = context.db.prepare(b, &update).await.unwrap();
+      assert_eq!(tx.prompt, Prompt::Candidate { id: a });
+      tx.commit(MessageId(203)).await.unwrap();
 
-    assert_eq!(tx.prompt, Prompt::Match { id: b });
+      context.db.set_prompt(b, Prompt::Quiescent).await;
 
-    tx.commit(MessageId(201)).await.unwrap();
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(
+        tx.candidates(a)
+          .await
+          .unwrap()
+          .iter()
+          .map(|candidate| candidate.discord_id)
+          .collect::<Vec<UserId>>(),
+        &[b]
+      );
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn inserting_responses_from_non_existant_users_is_an_error() {
-    let context = TestContext::new().await;
-
-    context.db.create_user(Prompt::Quiescent).await;
-
-    let mut tx = context.db.pool.begin().await.unwrap();
+  backend_test! {
+    async fn deleted_profiles_are_forgotten(context: TestContext) {
+      let a = context.db.create_user(Prompt::Quiescent).await;
+      let b = context.db.create_user(Prompt::Candidate { id: a }).await;
 
-    let error = sqlx::query!(
-      "INSERT INTO responses
-        (discord_id, candidate_id, response, dismissed)
-      VALUES
-        (1, 100, TRUE, FALSE)",
-    )
-    .execute(&mut tx)
-    .await
-    .unwrap_err();
+      let update = Update {
+        action:      Some(Action::AcceptCandidate { id: a }),
+        next_prompt: Prompt::Quiescent,
+      };
 
-    guard_unwrap!(let sqlx::Error::Database(error) = error);
+      let tx = context.db.prepare(b, &update).await.unwrap();
+      tx.commit(MessageId(201)).await.unwrap();
+      context.db.set_prompt(a, Prompt::Candidate { id: b }).await;
 
-    assert_eq!(
-      error.message(),
-      r#"insert or update on table "responses" violates foreign key constraint "responses_discord_id_fkey""#
-    );
-  }
+      let update = Update {
+        action:      Some(Action::DeleteProfile),
+        next_prompt: Prompt::Welcome,
+      };
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn inserting_responses_to_non_existant_users_is_an_error() {
-    let context = TestContext::new().await;
+      let tx = context
+        .db
+        .clone()
+        .with_clock(Clock::Fixed(1000.0))
+        .prepare(b, &update)
+        .await
+        .unwrap();
+      assert_eq!(tx.prompt, Prompt::Welcome);
+      assert_eq!(tx.retracted(), vec![a]);
+      tx.commit(MessageId(202)).await.unwrap();
+
+      let user = context.db.user(b).await.unwrap();
+      assert!(!user.welcomed);
+      assert_eq!(user.bio, None);
+      assert_eq!(user.tags, None);
+      assert!(user.answers.is_empty());
+      assert_eq!(user.updated_at, Some(1000.0));
+
+      let interrupt = Interrupt {
+        user_id:      b,
+        candidate_id: a,
+        channel_id:   None,
+      };
 
-    context.db.create_user(Prompt::Quiescent).await;
+      context.db.enqueue_interrupt(0, interrupt).await.unwrap();
 
-    let mut tx = context.db.pool.begin().await.unwrap();
+      let (_, tx) = context.db.prepare_interrupt(0..1).await.unwrap().unwrap();
+      let tx = tx.unwrap();
+      assert_eq!(tx.prompt, Prompt::Quiescent);
+      tx.commit(MessageId(203)).await.unwrap();
 
-    let error = sqlx::query!(
-      "INSERT INTO responses
-        (discord_id, candidate_id, response, dismissed)
-      VALUES
-        (100, 1, TRUE, FALSE)",
-    )
-    .execute(&mut tx)
-    .await
-    .unwrap_err();
+      let mut tx = context.db.store.begin().await.unwrap();
+      assert_eq!(tx.response(b, a).await.unwrap(), None);
+      assert_eq!(tx.refill_candidate_queue(a, 10).await.unwrap(), 0);
+      assert_eq!(tx.bio_at(b, 0.0).await.unwrap(), None);
+    }
+  }
 
-    guard_unwrap!(let sqlx::Error::Database(error) = error);
+  backend_test! {
+    async fn candidates_come_from_the_same_pool(context: TestContext) {
+      let db = &context.db;
 
-    assert_eq!(
-      error.message(),
-      r#"insert or update on table "responses" violates foreign key constraint "responses_discord_id_fkey""#
-    );
+      let a = db.create_user(Prompt::Quiescent).await;
+      let b = db.create_user_in_pool("matrix", Prompt::Quiescent).await;
+      db.create_user(Prompt::Candidate { id: a }).await;
+      db.create_user_in_pool("matrix", Prompt::Candidate { id: b }).await;
+    }
   }
 
-  #[tokio::test(flavor = "multi_thread")]
-  async fn candidate_function_prioritizes_users_who_have_accepted() {
-    let context = TestContext::new().await;
+  backend_test! {
+    async fn external_ids_are_interned(context: TestContext) {
+      let db = &context.db;
 
-    let a = context.db.create_user(Prompt::Quiescent).await;
-    let b = context.db.create_user(Prompt::Candidate { id: a }).await;
-    let c = context.db.create_user(Prompt::Candidate { id: a }).await;
-    context.db.set_prompt(b, Prompt::Quiescent).await;
-    context.db.set_prompt(c, Prompt::Quiescent).await;
+      let alice = db.intern("matrix-user", "@alice:example.org").await.unwrap();
+      assert!(alice > 0);
+      assert_eq!(db.intern("matrix-user", "@alice:example.org").await.unwrap(), alice);
 
-    let mut tx = context.db.pool.begin().await.unwrap();
-    assert_eq!(Db::get_candidate(&mut tx, a).await.unwrap(), Some(b));
+      let bob = db.intern("matrix-user", "@bob:example.org").await.unwrap();
+      assert_ne!(bob, alice);
 
-    let update = Update {
-      action:      Some(Action::AcceptCandidate { id: a }),
-      next_prompt: Prompt::Quiescent,
-    };
-    context
-      .db
-      .prepare(c, &update)
-      .await
-      .unwrap()
-      .commit(MessageId(0))
-      .await
-      .unwrap();
-    context.db.set_prompt(c, Prompt::Quiescent).await;
+      let room = db.intern("matrix-room", "@alice:example.org").await.unwrap();
+      assert_ne!(room, alice);
+      assert_ne!(room, bob);
 
-    let mut tx = context.db.pool.begin().await.unwrap();
-    assert_eq!(Db::get_candidate(&mut tx, a).await.unwrap(), Some(c));
+      assert_eq!(
+        db.external_id("matrix-user", alice).await.unwrap().as_deref(),
+        Some("@alice:example.org")
+      );
+      assert_eq!(db.external_id("matrix-room", alice).await.unwrap(), None);
+      assert_eq!(db.external_id("matrix-user", 1000).await.unwrap(), None);
+    }
   }
 }
@@ -3,9 +3,17 @@ use crate::common::*;
 #[derive(Debug, Snafu)]
 #[snafu(visibility(pub(crate)))]
 pub enum Error {
+  AgeBracketLoad {
+    storage: u64,
+    source:  TryFromPrimitiveError<AgeBracket>,
+  },
   Bool {
     storage: i64,
   },
+  ImportanceLoad {
+    storage: u64,
+    source:  TryFromPrimitiveError<Importance>,
+  },
   Internal {
     message: String,
   },
@@ -27,10 +35,17 @@ pub enum Error {
   Sqlx {
     source: sqlx::Error,
   },
+  #[snafu(display("Unsupported database URL scheme `{}`", scheme))]
+  UnsupportedScheme {
+    scheme: String,
+  },
   UrlLoad {
     source: url::ParseError,
     text:   String,
   },
+  UserExists {
+    id: UserId,
+  },
   UserMissingBio {
     id: UserId,
   },
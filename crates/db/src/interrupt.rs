@@ -0,0 +1,10 @@
+use crate::common::*;
+
+/// Queued so that it can be delivered by the instance responsible for the
+/// candidate's shard
+#[derive(Debug, Eq, PartialEq, Copy, Clone)]
+pub struct Interrupt {
+  pub user_id:      UserId,
+  pub candidate_id: UserId,
+  pub channel_id:   Option<ChannelId>,
+}
@@ -1,8 +1,33 @@
-pub use crate::{db::Db, error::Error, update_tx::UpdateTx};
+pub use crate::{
+  candidate::Candidate, clock::Clock, db::Db, error::Error, interrupt::Interrupt,
+  pool_options::PoolOptions, ranking::Ranking, store::Store, store_transaction::StoreTransaction,
+  update_tx::UpdateTx,
+};
 
+mod candidate;
+mod clock;
 mod common;
 mod db;
 mod error;
+mod interrupt;
+mod memory_lease;
+mod memory_operation;
+mod memory_response;
+mod memory_state;
+mod memory_store;
+mod memory_transaction;
+mod pool_options;
+#[cfg(feature = "postgres")]
+mod postgres_store;
+#[cfg(test)]
+mod prompt_flow;
+mod ranking;
+#[cfg(feature = "sqlite")]
+mod sqlite_store;
+mod store;
+mod store_transaction;
+#[cfg(test)]
+mod test_context;
 mod unwrap_infallible;
 mod update_tx;
 mod value;
@@ -0,0 +1,8 @@
+use crate::common::*;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MemoryLease {
+  pub(crate) acquired_at: Instant,
+  pub(crate) expires_at:  Instant,
+  pub(crate) holder:      String,
+}
@@ -0,0 +1,119 @@
+use crate::common::*;
+
+/// Replayed against the shared state when the transaction is committed
+#[derive(Debug, Clone)]
+pub(crate) enum MemoryOperation {
+  DeferCandidate {
+    user_id:      UserId,
+    candidate_id: UserId,
+    deferred_at:  f64,
+  },
+  DeleteProfile {
+    discord_id: UserId,
+    updated_at: f64,
+  },
+  DeleteResponse {
+    user_id:      UserId,
+    candidate_id: UserId,
+  },
+  DequeueCandidate {
+    discord_id:   UserId,
+    candidate_id: UserId,
+  },
+  DequeueInterrupt {
+    shard:     u64,
+    interrupt: Interrupt,
+  },
+  DismissMatch {
+    user_id:  UserId,
+    match_id: UserId,
+  },
+  EnqueueCandidate {
+    discord_id:   UserId,
+    candidate_id: UserId,
+  },
+  InsertUser {
+    discord_id: UserId,
+    pool:       String,
+    created_at: f64,
+  },
+  OfferCandidate {
+    candidate_id: UserId,
+    limit:        u64,
+  },
+  RecordEvent {
+    key:          String,
+    processed_at: f64,
+  },
+  RefillCandidateQueue {
+    discord_id: UserId,
+    limit:      u64,
+  },
+  Report {
+    user_id:     UserId,
+    reported_id: UserId,
+  },
+  RespondToCandidate {
+    user_id:      UserId,
+    candidate_id: UserId,
+    response:     bool,
+    responded_at: f64,
+  },
+  SetAnswer {
+    discord_id: UserId,
+    answer:     Answer,
+  },
+  SetBio {
+    discord_id: UserId,
+    text:       String,
+    updated_at: f64,
+  },
+  SetExposure {
+    discord_id: UserId,
+    exposure:   f64,
+    shown_at:   f64,
+  },
+  SetLastActive {
+    discord_id: UserId,
+    active_at:  f64,
+  },
+  SetPaused {
+    discord_id: UserId,
+    paused:     bool,
+  },
+  SetPreferences {
+    discord_id:  UserId,
+    preferences: Preferences,
+  },
+  SetPrompt {
+    discord_id:     UserId,
+    prompt_message: PromptMessage,
+    sent_at:        f64,
+  },
+  SetQueueExhausted {
+    discord_id: UserId,
+    exhausted:  bool,
+  },
+  SetResurfaceDeclined {
+    discord_id: UserId,
+    enabled:    bool,
+  },
+  SetTags {
+    discord_id: UserId,
+    tags:       Vec<String>,
+  },
+  SetUpdatedAt {
+    discord_id: UserId,
+    updated_at: f64,
+  },
+  SuperLike {
+    user_id:      UserId,
+    candidate_id: UserId,
+  },
+  Welcome {
+    discord_id: UserId,
+  },
+  WithdrawCandidate {
+    candidate_id: UserId,
+  },
+}
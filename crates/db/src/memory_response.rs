@@ -0,0 +1,11 @@
+use crate::common::*;
+
+#[derive(Debug, Clone)]
+pub(crate) struct MemoryResponse {
+  pub(crate) candidate_id: UserId,
+  pub(crate) dismissed:    bool,
+  pub(crate) responded_at: f64,
+  pub(crate) response:     bool,
+  pub(crate) super_like:   bool,
+  pub(crate) user_id:      UserId,
+}
@@ -0,0 +1,599 @@
+use crate::common::*;
+
+use std::cmp::Ordering;
+
+/// Users and responses are kept in insertion order, which is the order the
+/// SQL backends break ties in
+#[derive(Debug, Clone, Default)]
+pub(crate) struct MemoryState {
+  pub(crate) bio_history:      Vec<(UserId, String, f64)>,
+  pub(crate) candidate_queue:  BTreeSet<(UserId, UserId)>,
+  pub(crate) deferrals:        BTreeMap<(UserId, UserId), f64>,
+  pub(crate) errors:           BTreeMap<String, (String, String, Option<UserId>)>,
+  pub(crate) events:           BTreeMap<String, f64>,
+  pub(crate) exhausted_queues: BTreeSet<UserId>,
+  pub(crate) exposure:         BTreeMap<UserId, (f64, f64)>,
+  /// Indexed by ID minus one
+  pub(crate) external_ids:     Vec<(String, String)>,
+  pub(crate) interrupts:       Vec<(u64, Interrupt)>,
+  pub(crate) leases:           BTreeMap<String, MemoryLease>,
+  pub(crate) pools:            BTreeMap<UserId, String>,
+  pub(crate) reports:          BTreeSet<(UserId, UserId)>,
+  pub(crate) responses:        Vec<MemoryResponse>,
+  pub(crate) users:            Vec<User>,
+}
+
+impl MemoryState {
+  pub(crate) fn perform(&mut self, operation: &MemoryOperation) -> Result<()> {
+    use MemoryOperation::*;
+
+    match operation {
+      DeferCandidate {
+        user_id,
+        candidate_id,
+        deferred_at,
+      } => {
+        self.user_mut(*user_id)?;
+        self.user_mut(*candidate_id)?;
+        self
+          .deferrals
+          .insert((*user_id, *candidate_id), *deferred_at);
+      },
+      DeleteProfile {
+        discord_id,
+        updated_at,
+      } => {
+        let discord_id = *discord_id;
+        let user = self.user_mut(discord_id)?;
+
+        *user = User {
+          id: user.id,
+          prompt_message: user.prompt_message,
+          prompt_sent_at: user.prompt_sent_at,
+          created_at: user.created_at,
+          last_active_at: user.last_active_at,
+          welcomed: false,
+          bio: None,
+          resurface_declined: false,
+          paused: false,
+          tags: None,
+          preferences: Preferences::default(),
+          answers: Vec::new(),
+          updated_at: Some(*updated_at),
+          discord_id,
+        };
+
+        let unrelated = |&(a, b): &(UserId, UserId)| a != discord_id && b != discord_id;
+
+        self.bio_history.retain(|(id, _, _)| *id != discord_id);
+        self.candidate_queue.retain(unrelated);
+        self.deferrals.retain(|pair, _| unrelated(pair));
+        self.exhausted_queues.remove(&discord_id);
+        self.exposure.remove(&discord_id);
+        self.reports.retain(unrelated);
+        self
+          .responses
+          .retain(|response| response.user_id != discord_id && response.candidate_id != discord_id);
+      },
+      DeleteResponse {
+        user_id,
+        candidate_id,
+      } => {
+        self.responses.retain(|response| {
+          !(response.user_id == *user_id && response.candidate_id == *candidate_id)
+        });
+        self.reports.remove(&(*user_id, *candidate_id));
+      },
+      DequeueCandidate {
+        discord_id,
+        candidate_id,
+      } => {
+        self.candidate_queue.remove(&(*discord_id, *candidate_id));
+      },
+      DequeueInterrupt { shard, interrupt } => {
+        let index = self
+          .interrupts
+          .iter()
+          .position(|queued| queued == &(*shard, *interrupt));
+
+        if let Some(index) = index {
+          self.interrupts.remove(index);
+        }
+      },
+      DismissMatch { user_id, match_id } =>
+        if let Some(response) = self.response_mut(*user_id, *match_id) {
+          response.dismissed = true;
+        },
+      EnqueueCandidate {
+        discord_id,
+        candidate_id,
+      } => {
+        self.user_mut(*discord_id)?;
+        self.user_mut(*candidate_id)?;
+        self.candidate_queue.insert((*discord_id, *candidate_id));
+      },
+      InsertUser {
+        discord_id,
+        pool,
+        created_at,
+      } => {
+        if self.user(*discord_id).is_some() {
+          return Err(Error::UserExists { id: *discord_id });
+        }
+
+        self.pools.insert(*discord_id, pool.clone());
+
+        self.users.push(User {
+          id:                 self.users.len() as u64 + 1,
+          discord_id:         *discord_id,
+          welcomed:           false,
+          bio:                None,
+          resurface_declined: false,
+          paused:             false,
+          prompt_message:     None,
+          prompt_sent_at:     None,
+          tags:               None,
+          preferences:        Preferences::default(),
+          answers:            Vec::new(),
+          created_at:         Some(*created_at),
+          updated_at:         Some(*created_at),
+          last_active_at:     None,
+        });
+      },
+      OfferCandidate {
+        candidate_id,
+        limit,
+      } => {
+        let (exhausted, mut others) = self
+          .users
+          .iter()
+          .filter(|user| {
+            user.welcomed
+              && user.bio.is_some()
+              && user.discord_id != *candidate_id
+              && self.response(user.discord_id, *candidate_id).is_none()
+              && self.response(*candidate_id, user.discord_id) != Some(false)
+              && self.pools.get(&user.discord_id) == self.pools.get(candidate_id)
+              && self.compatible(user.discord_id, *candidate_id)
+          })
+          .partition::<Vec<&User>, _>(|user| self.exhausted_queues.contains(&user.discord_id));
+
+        others.sort_by(|a, b| {
+          self
+            .last_active(b.discord_id)
+            .partial_cmp(&self.last_active(a.discord_id))
+            .unwrap_or(Ordering::Equal)
+            .then(a.id.cmp(&b.id))
+        });
+
+        let queued = exhausted
+          .iter()
+          .chain(others.iter().take(Self::limit(*limit)))
+          .map(|user| (user.discord_id, *candidate_id))
+          .collect::<Vec<(UserId, UserId)>>();
+
+        self.candidate_queue.extend(queued);
+      },
+      RecordEvent { key, processed_at } => {
+        self.events.insert(key.clone(), *processed_at);
+      },
+      RefillCandidateQueue { discord_id, limit } => {
+        let accepted = self
+          .users
+          .iter()
+          .filter(|user| self.refillable(*discord_id, user, true))
+          .take(Self::limit(*limit))
+          .map(|user| (*discord_id, user.discord_id))
+          .collect::<Vec<(UserId, UserId)>>();
+
+        self.candidate_queue.extend(accepted);
+
+        let mut others = self
+          .users
+          .iter()
+          .filter(|user| self.refillable(*discord_id, user, false))
+          .collect::<Vec<&User>>();
+
+        others.sort_by(|a, b| {
+          self
+            .exposure(a.discord_id)
+            .0
+            .partial_cmp(&self.exposure(b.discord_id).0)
+            .unwrap_or(Ordering::Equal)
+            .then(a.id.cmp(&b.id))
+        });
+
+        let others = others
+          .iter()
+          .take(Self::limit(*limit))
+          .map(|user| (*discord_id, user.discord_id))
+          .collect::<Vec<(UserId, UserId)>>();
+
+        self.candidate_queue.extend(others);
+      },
+      Report {
+        user_id,
+        reported_id,
+      } => {
+        self.user_mut(*user_id)?;
+        self.user_mut(*reported_id)?;
+        self.reports.insert((*user_id, *reported_id));
+      },
+      RespondToCandidate {
+        user_id,
+        candidate_id,
+        response,
+        responded_at,
+      } => {
+        self.user_mut(*user_id)?;
+        self.user_mut(*candidate_id)?;
+
+        if let Some(existing) = self.response_mut(*user_id, *candidate_id) {
+          existing.response = *response;
+          existing.dismissed = false;
+          existing.super_like = false;
+          existing.responded_at = *responded_at;
+        } else {
+          self.responses.push(MemoryResponse {
+            user_id:      *user_id,
+            candidate_id: *candidate_id,
+            response:     *response,
+            dismissed:    false,
+            super_like:   false,
+            responded_at: *responded_at,
+          });
+        }
+      },
+      SetBio {
+        discord_id,
+        text,
+        updated_at,
+      } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.bio = Some(text.clone());
+          self
+            .bio_history
+            .push((*discord_id, text.clone(), *updated_at));
+        },
+      SetExposure {
+        discord_id,
+        exposure,
+        shown_at,
+      } =>
+        if self.user(*discord_id).is_some() {
+          self.exposure.insert(*discord_id, (*exposure, *shown_at));
+        },
+      SetLastActive {
+        discord_id,
+        active_at,
+      } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.last_active_at = Some(*active_at);
+        },
+      SetAnswer { discord_id, answer } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user
+            .answers
+            .retain(|existing| existing.question != answer.question);
+          user.answers.push(answer.clone());
+          user.answers.sort_by_key(|existing| existing.question);
+        },
+      SetPaused { discord_id, paused } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.paused = *paused;
+        },
+      SetPreferences {
+        discord_id,
+        preferences,
+      } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.preferences = preferences.clone();
+        },
+      SetPrompt {
+        discord_id,
+        prompt_message,
+        sent_at,
+      } => {
+        let user = self.user_mut(*discord_id)?;
+        user.prompt_message = Some(*prompt_message);
+        user.prompt_sent_at = Some(*sent_at);
+      },
+      SetQueueExhausted {
+        discord_id,
+        exhausted,
+      } =>
+        if *exhausted {
+          self.exhausted_queues.insert(*discord_id);
+        } else {
+          self.exhausted_queues.remove(discord_id);
+        },
+      SetResurfaceDeclined {
+        discord_id,
+        enabled,
+      } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.resurface_declined = *enabled;
+        },
+      SetTags { discord_id, tags } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.tags = Some(tags.clone());
+        },
+      SetUpdatedAt {
+        discord_id,
+        updated_at,
+      } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.updated_at = Some(*updated_at);
+        },
+      SuperLike {
+        user_id,
+        candidate_id,
+      } =>
+        if let Some(response) = self.response_mut(*user_id, *candidate_id) {
+          response.super_like = true;
+        },
+      Welcome { discord_id } =>
+        if let Ok(user) = self.user_mut(*discord_id) {
+          user.welcomed = true;
+        },
+      WithdrawCandidate { candidate_id } => self
+        .candidate_queue
+        .retain(|(_, queued_id)| queued_id != candidate_id),
+    }
+
+    Ok(())
+  }
+
+  pub(crate) fn user(&self, discord_id: UserId) -> Option<&User> {
+    self.users.iter().find(|user| user.discord_id == discord_id)
+  }
+
+  fn user_mut(&mut self, discord_id: UserId) -> Result<&mut User> {
+    self
+      .users
+      .iter_mut()
+      .find(|user| user.discord_id == discord_id)
+      .ok_or(Error::UserUnknown { id: discord_id })
+  }
+
+  pub(crate) fn response(&self, user_id: UserId, candidate_id: UserId) -> Option<bool> {
+    self
+      .responses
+      .iter()
+      .find(|response| response.user_id == user_id && response.candidate_id == candidate_id)
+      .map(|response| response.response)
+  }
+
+  /// Ties go to the latest inserted, like the SQL backends' ordering by ID
+  pub(crate) fn last_response(&self, user_id: UserId) -> Option<(UserId, bool, f64)> {
+    self
+      .responses
+      .iter()
+      .filter(|response| response.user_id == user_id)
+      .fold(None, |last: Option<&MemoryResponse>, response| match last {
+        Some(last) if last.responded_at > response.responded_at => Some(last),
+        _ => Some(response),
+      })
+      .map(|response| {
+        (
+          response.candidate_id,
+          response.response,
+          response.responded_at,
+        )
+      })
+  }
+
+  pub(crate) fn super_liked(&self, user_id: UserId, candidate_id: UserId) -> bool {
+    self.responses.iter().any(|response| {
+      response.user_id == user_id && response.candidate_id == candidate_id && response.super_like
+    })
+  }
+
+  fn response_mut(&mut self, user_id: UserId, candidate_id: UserId) -> Option<&mut MemoryResponse> {
+    self
+      .responses
+      .iter_mut()
+      .find(|response| response.user_id == user_id && response.candidate_id == candidate_id)
+  }
+
+  pub(crate) fn bio(&self, id: UserId) -> Result<String> {
+    self
+      .user(id)
+      .ok_or(Error::UserUnknown { id })?
+      .bio
+      .clone()
+      .ok_or(Error::UserMissingBio { id })
+  }
+
+  /// Ties go to the latest set, like the SQL backends' ordering by ID
+  pub(crate) fn bio_at(&self, discord_id: UserId, at: f64) -> Option<String> {
+    self
+      .bio_history
+      .iter()
+      .filter(|(id, _, set_at)| *id == discord_id && *set_at <= at)
+      .fold(
+        None,
+        |latest: Option<&(UserId, String, f64)>, entry| match latest {
+          Some(latest) if latest.2 > entry.2 => Some(latest),
+          _ => Some(entry),
+        },
+      )
+      .map(|(_, bio, _)| bio.clone())
+  }
+
+  fn bio_updated_at(&self, discord_id: UserId) -> Option<f64> {
+    self
+      .bio_history
+      .iter()
+      .rev()
+      .find(|(id, _, _)| *id == discord_id)
+      .map(|(_, _, set_at)| *set_at)
+  }
+
+  pub(crate) fn declined_with_new_bios(&self, user_id: UserId) -> Vec<(UserId, f64)> {
+    self
+      .responses
+      .iter()
+      .filter(|response| {
+        response.user_id == user_id
+          && !response.response
+          && self
+            .bio_updated_at(response.candidate_id)
+            .map_or(false, |updated_at| updated_at > response.responded_at)
+          && !self.reports.contains(&(user_id, response.candidate_id))
+      })
+      .map(|response| (response.candidate_id, response.responded_at))
+      .collect()
+  }
+
+  pub(crate) fn prompt(&self, discord_id: UserId) -> Option<Prompt> {
+    self
+      .user(discord_id)
+      .and_then(|user| user.prompt_message)
+      .map(|prompt_message| prompt_message.prompt)
+  }
+
+  pub(crate) fn prompt_messages(&self) -> Vec<(UserId, PromptMessage)> {
+    let mut prompt_messages = self
+      .users
+      .iter()
+      .filter_map(|user| Some((user.discord_id, user.prompt_message?)))
+      .collect::<Vec<(UserId, PromptMessage)>>();
+
+    prompt_messages
+      .sort_by_key(|(discord_id, prompt_message)| (prompt_message.message_id, *discord_id));
+
+    prompt_messages
+  }
+
+  pub(crate) fn shown_to(&self, discord_id: UserId) -> Vec<UserId> {
+    let mut shown_to = self
+      .users
+      .iter()
+      .filter(
+        |user| match user.prompt_message.map(|message| message.prompt) {
+          Some(Prompt::Candidate { id } | Prompt::Match { id }) => id == discord_id,
+          _ => false,
+        },
+      )
+      .map(|user| user.discord_id)
+      .collect::<Vec<UserId>>();
+
+    shown_to.sort();
+
+    shown_to
+  }
+
+  pub(crate) fn get_match(&self, discord_id: UserId) -> Option<UserId> {
+    self
+      .responses
+      .iter()
+      .find(|response| {
+        response.user_id == discord_id
+          && response.response
+          && !response.dismissed
+          && self.response(response.candidate_id, discord_id) == Some(true)
+      })
+      .map(|response| response.candidate_id)
+  }
+
+  fn queueable(&self, discord_id: UserId, user: &User) -> bool {
+    user.welcomed
+      && user.bio.is_some()
+      && !user.paused
+      && user.discord_id != discord_id
+      && self.response(discord_id, user.discord_id).is_none()
+      && self.response(user.discord_id, discord_id) != Some(false)
+      && self.pools.get(&user.discord_id) == self.pools.get(&discord_id)
+      && self.compatible(discord_id, user.discord_id)
+  }
+
+  /// Whether or not they are queued
+  fn eligible(&self, discord_id: UserId, user: &User) -> bool {
+    self.queueable(discord_id, user)
+      && user
+        .prompt_message
+        .map(|prompt_message| prompt_message.prompt.discriminant())
+        == Some(PromptDiscriminant::Quiescent)
+  }
+
+  fn compatible(&self, a: UserId, b: UserId) -> bool {
+    match (self.user(a), self.user(b)) {
+      (Some(a), Some(b)) => a.preferences.compatible(&b.preferences),
+      _ => false,
+    }
+  }
+
+  fn refillable(&self, discord_id: UserId, user: &User, accepted: bool) -> bool {
+    self.queueable(discord_id, user)
+      && (!accepted || self.response(user.discord_id, discord_id) == Some(true))
+      && !self
+        .candidate_queue
+        .contains(&(discord_id, user.discord_id))
+  }
+
+  fn limit(limit: u64) -> usize {
+    limit.try_into().unwrap_or(usize::MAX)
+  }
+
+  pub(crate) fn queued(&self, discord_id: UserId) -> impl Iterator<Item = UserId> + '_ {
+    self
+      .candidate_queue
+      .range((discord_id, UserId(0))..=(discord_id, UserId(u64::MAX)))
+      .map(|&(_, candidate_id)| candidate_id)
+  }
+
+  pub(crate) fn candidates(&self, discord_id: UserId) -> Vec<Candidate> {
+    let mut queued = self
+      .queued(discord_id)
+      .filter_map(|candidate_id| self.user(candidate_id))
+      .filter(|user| self.eligible(discord_id, user))
+      .collect::<Vec<&User>>();
+
+    queued.sort_by_key(|user| user.id);
+
+    queued
+      .into_iter()
+      .map(|user| {
+        let (exposure, last_shown_at) = self.exposure(user.discord_id);
+
+        Candidate {
+          discord_id: user.discord_id,
+          accepted: self.response(user.discord_id, discord_id) == Some(true),
+          last_active_at: self.last_active(user.discord_id),
+          shared_tags: self.shared_tags(discord_id, user.discord_id).len() as u64,
+          compatibility: None,
+          exposure,
+          last_shown_at,
+        }
+      })
+      .collect()
+  }
+
+  fn shared_tags(&self, a: UserId, b: UserId) -> Vec<String> {
+    let tags = |discord_id| {
+      self
+        .user(discord_id)
+        .and_then(|user| user.tags.clone())
+        .unwrap_or_default()
+    };
+
+    let theirs = tags(b);
+
+    tags(a)
+      .into_iter()
+      .filter(|tag| theirs.contains(tag))
+      .collect()
+  }
+
+  fn last_active(&self, discord_id: UserId) -> Option<f64> {
+    self.user(discord_id).and_then(|user| user.last_active_at)
+  }
+
+  pub(crate) fn exposure(&self, discord_id: UserId) -> (f64, Option<f64>) {
+    match self.exposure.get(&discord_id) {
+      Some((exposure, shown_at)) => (*exposure, Some(*shown_at)),
+      None => (0.0, None),
+    }
+  }
+}
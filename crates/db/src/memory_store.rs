@@ -0,0 +1,344 @@
+use crate::common::*;
+
+#[derive(Debug, Default)]
+pub(crate) struct MemoryStore {
+  state: Arc<Mutex<MemoryState>>,
+}
+
+#[async_trait]
+impl Store for MemoryStore {
+  async fn begin(&self) -> Result<Box<dyn StoreTransaction>> {
+    Ok(Box::new(MemoryTransaction {
+      operations: Vec::new(),
+      snapshot:   self.state.lock().await.clone(),
+      state:      self.state.clone(),
+    }))
+  }
+
+  async fn prompt_messages(&self) -> Result<Vec<(UserId, PromptMessage)>> {
+    Ok(self.state.lock().await.prompt_messages())
+  }
+
+  async fn user_count(&self) -> Result<u64> {
+    Ok(self.state.lock().await.users.len() as u64)
+  }
+
+  async fn intern(&self, namespace: &str, external_id: &str) -> Result<u64> {
+    let mut state = self.state.lock().await;
+
+    let index = if let Some(index) = state
+      .external_ids
+      .iter()
+      .position(|(n, e)| n == namespace && e == external_id)
+    {
+      index
+    } else {
+      state
+        .external_ids
+        .push((namespace.to_owned(), external_id.to_owned()));
+      state.external_ids.len() - 1
+    };
+
+    Ok(index as u64 + 1)
+  }
+
+  async fn external_id(&self, namespace: &str, id: u64) -> Result<Option<String>> {
+    let state = self.state.lock().await;
+
+    let index = match id
+      .checked_sub(1)
+      .and_then(|index| usize::try_from(index).ok())
+    {
+      Some(index) => index,
+      None => return Ok(None),
+    };
+
+    Ok(
+      state
+        .external_ids
+        .get(index)
+        .filter(|(n, _)| n == namespace)
+        .map(|(_, external_id)| external_id.clone()),
+    )
+  }
+
+  async fn prune_events(&self, before: f64) -> Result<u64> {
+    let mut state = self.state.lock().await;
+
+    let count = state.events.len();
+
+    state
+      .events
+      .retain(|_, processed_at| *processed_at >= before);
+
+    Ok((count - state.events.len()) as u64)
+  }
+
+  async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+    let mut state = self.state.lock().await;
+
+    let now = Instant::now();
+
+    let acquired_at = match state.leases.get(name) {
+      None => now,
+      Some(lease) if lease.holder == holder => lease.acquired_at,
+      Some(lease) if lease.expires_at < now => now,
+      Some(_) => return Ok(false),
+    };
+
+    state.leases.insert(name.to_owned(), MemoryLease {
+      expires_at: now + duration,
+      holder: holder.to_owned(),
+      acquired_at,
+    });
+
+    Ok(true)
+  }
+
+  async fn lease_holder(&self, name: &str) -> Result<Option<String>> {
+    Ok(
+      self
+        .state
+        .lock()
+        .await
+        .leases
+        .get(name)
+        .map(|lease| lease.holder.clone()),
+    )
+  }
+
+  async fn live_lease_holders(&self, prefix: &str) -> Result<Vec<String>> {
+    let state = self.state.lock().await;
+
+    let now = Instant::now();
+
+    let mut leases = state
+      .leases
+      .iter()
+      .filter(|(name, lease)| name.starts_with(prefix) && lease.expires_at > now)
+      .map(|(_, lease)| lease)
+      .collect::<Vec<&MemoryLease>>();
+
+    leases.sort_by_key(|lease| (lease.acquired_at, &lease.holder));
+
+    Ok(
+      leases
+        .into_iter()
+        .map(|lease| lease.holder.clone())
+        .collect(),
+    )
+  }
+
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+    let mut state = self.state.lock().await;
+
+    if state.leases.get(name).map(|lease| lease.holder.as_str()) == Some(holder) {
+      state.leases.remove(name);
+    }
+
+    Ok(())
+  }
+
+  async fn release_leases(&self, holder: &str) -> Result<()> {
+    self
+      .state
+      .lock()
+      .await
+      .leases
+      .retain(|_, lease| lease.holder != holder);
+
+    Ok(())
+  }
+
+  async fn enqueue_interrupt(&self, shard: u64, interrupt: Interrupt) -> Result<()> {
+    let mut state = self.state.lock().await;
+
+    for id in [interrupt.user_id, interrupt.candidate_id] {
+      if state.user(id).is_none() {
+        return Err(Error::UserUnknown { id });
+      }
+    }
+
+    state.interrupts.push((shard, interrupt));
+
+    Ok(())
+  }
+
+  async fn record_error(
+    &self,
+    reference: &str,
+    variant: &str,
+    message: &str,
+    discord_id: Option<UserId>,
+  ) -> Result<()> {
+    self.state.lock().await.errors.insert(
+      reference.to_owned(),
+      (variant.to_owned(), message.to_owned(), discord_id),
+    );
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// The clock advances each step, so that candidates are ranked by decayed
+  /// exposure
+  async fn check_equivalence(db: &Db) {
+    let memory = Db::memory();
+
+    let responses = [
+      Response::message("ok"),
+      Response::message("Hello!"),
+      Response::message("yes"),
+      Response::message("no"),
+      Response::message(User::UNDO_COMMAND),
+      Response::message(User::RESURFACE_COMMAND),
+      Response::message(User::PAUSE_COMMAND),
+      Response::message(User::DELETE_COMMAND),
+      Response::Reaction(Emoji::ThumbsUp),
+      Response::Reaction(Emoji::ThumbsDown),
+      Response::Reaction(Emoji::Skip),
+      Response::Reaction(Emoji::SuperLike),
+      Response::Reaction(Emoji::Report),
+    ];
+
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+
+    let mut random = |n: usize| {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      #[allow(clippy::cast_possible_truncation)]
+      let value = (state % n as u64) as usize;
+      value
+    };
+
+    for step in 0..500 {
+      #[allow(clippy::cast_precision_loss)]
+      let clock = Clock::Fixed(step as f64 * 300.0);
+      let memory = memory
+        .clone()
+        .with_ranking(Ranking::new(0))
+        .with_clock(clock);
+      let db = db.clone().with_ranking(Ranking::new(0)).with_clock(clock);
+
+      let user_id = UserId(random(8) as u64);
+      let response = &responses[random(responses.len())];
+      let pool = if user_id.0 < 6 {
+        Db::DEFAULT_POOL
+      } else {
+        "other"
+      };
+
+      let user = memory.user_in_pool(user_id, pool).await.unwrap();
+      assert_eq!(
+        db.user_in_pool(user_id, pool).await.unwrap(),
+        user,
+        "step {}",
+        step
+      );
+
+      let update = user.update(response);
+
+      let memory_tx = memory.prepare(user_id, &update).await.unwrap();
+      let tx = db.prepare(user_id, &update).await.unwrap();
+      assert_eq!(tx.prompt(), memory_tx.prompt(), "step {}", step);
+      assert_eq!(tx.retracted(), memory_tx.retracted(), "step {}", step);
+
+      let retracted = tx.retracted();
+
+      memory_tx.commit(MessageId(step)).await.unwrap();
+      tx.commit(MessageId(step)).await.unwrap();
+
+      let interrupted = match update.action {
+        Some(Action::AcceptCandidate { id } | Action::SuperLikeCandidate { id }) => vec![id],
+        _ => retracted,
+      };
+
+      for candidate_id in interrupted {
+        let interrupt = Interrupt {
+          channel_id: None,
+          user_id,
+          candidate_id,
+        };
+
+        memory.enqueue_interrupt(0, interrupt).await.unwrap();
+        db.enqueue_interrupt(0, interrupt).await.unwrap();
+      }
+
+      loop {
+        let memory_prepared = memory.prepare_interrupt(0..1).await.unwrap();
+        let prepared = db.prepare_interrupt(0..1).await.unwrap();
+
+        assert_eq!(
+          prepared
+            .as_ref()
+            .map(|(interrupt, tx)| (*interrupt, tx.as_ref().map(UpdateTx::prompt))),
+          memory_prepared
+            .as_ref()
+            .map(|(interrupt, tx)| (*interrupt, tx.as_ref().map(UpdateTx::prompt))),
+          "step {}",
+          step
+        );
+
+        match (memory_prepared, prepared) {
+          (Some((_, Some(memory_tx))), Some((_, Some(tx)))) => {
+            memory_tx.commit(MessageId(step)).await.unwrap();
+            tx.commit(MessageId(step)).await.unwrap();
+          },
+          (Some(_), Some(_)) => {},
+          _ => break,
+        }
+      }
+    }
+
+    assert_eq!(
+      db.prompt_messages().await.unwrap(),
+      memory.prompt_messages().await.unwrap()
+    );
+  }
+
+  #[cfg(feature = "postgres")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn postgres_equivalence() {
+    check_equivalence(&TestContext::postgres().await.db).await;
+  }
+
+  #[cfg(feature = "sqlite")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn sqlite_equivalence() {
+    let context = TestContext::sqlite().await;
+
+    check_equivalence(&context.db).await;
+  }
+
+  #[tokio::test]
+  async fn failed_commits_are_rolled_back() {
+    let store = MemoryStore::default();
+
+    let mut tx = store.begin().await.unwrap();
+    tx.insert_user(UserId(1), Db::DEFAULT_POOL, 0.0)
+      .await
+      .unwrap();
+    tx.insert_user(UserId(2), Db::DEFAULT_POOL, 0.0)
+      .await
+      .unwrap();
+
+    let mut other = store.begin().await.unwrap();
+    other
+      .insert_user(UserId(2), Db::DEFAULT_POOL, 0.0)
+      .await
+      .unwrap();
+    other.commit().await.unwrap();
+
+    assert!(matches!(
+      tx.commit().await,
+      Err(Error::UserExists { id: UserId(2) })
+    ));
+
+    assert_eq!(store.user_count().await.unwrap(), 1);
+  }
+}
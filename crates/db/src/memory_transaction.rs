@@ -0,0 +1,343 @@
+use crate::common::*;
+
+/// Reads see a snapshot taken when the transaction began, plus its own
+/// writes
+#[derive(Debug)]
+pub(crate) struct MemoryTransaction {
+  pub(crate) operations: Vec<MemoryOperation>,
+  pub(crate) snapshot:   MemoryState,
+  pub(crate) state:      Arc<Mutex<MemoryState>>,
+}
+
+impl MemoryTransaction {
+  fn perform(&mut self, operation: MemoryOperation) -> Result<()> {
+    self.snapshot.perform(&operation)?;
+    self.operations.push(operation);
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl StoreTransaction for MemoryTransaction {
+  async fn load_user(&mut self, discord_id: UserId) -> Result<Option<User>> {
+    Ok(self.snapshot.user(discord_id).cloned())
+  }
+
+  async fn insert_user(&mut self, discord_id: UserId, pool: &str, created_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::InsertUser {
+      pool: pool.to_owned(),
+      discord_id,
+      created_at,
+    })
+  }
+
+  async fn record_event(&mut self, key: &str, processed_at: f64) -> Result<bool> {
+    if self.snapshot.events.contains_key(key) {
+      return Ok(false);
+    }
+
+    self.perform(MemoryOperation::RecordEvent {
+      key: key.to_owned(),
+      processed_at,
+    })?;
+
+    Ok(true)
+  }
+
+  async fn welcome(&mut self, discord_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::Welcome { discord_id })
+  }
+
+  async fn set_bio(&mut self, discord_id: UserId, text: &str, updated_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::SetBio {
+      text: text.to_owned(),
+      discord_id,
+      updated_at,
+    })
+  }
+
+  async fn set_resurface_declined(&mut self, discord_id: UserId, enabled: bool) -> Result<()> {
+    self.perform(MemoryOperation::SetResurfaceDeclined {
+      discord_id,
+      enabled,
+    })
+  }
+
+  async fn set_paused(&mut self, discord_id: UserId, paused: bool) -> Result<()> {
+    self.perform(MemoryOperation::SetPaused { discord_id, paused })
+  }
+
+  async fn delete_profile(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::DeleteProfile {
+      discord_id,
+      updated_at,
+    })
+  }
+
+  async fn set_tags(&mut self, discord_id: UserId, tags: &[String]) -> Result<()> {
+    self.perform(MemoryOperation::SetTags {
+      tags: tags.to_vec(),
+      discord_id,
+    })
+  }
+
+  async fn set_preferences(&mut self, discord_id: UserId, preferences: &Preferences) -> Result<()> {
+    self.perform(MemoryOperation::SetPreferences {
+      preferences: preferences.clone(),
+      discord_id,
+    })
+  }
+
+  async fn set_answer(&mut self, discord_id: UserId, answer: &Answer) -> Result<()> {
+    self.perform(MemoryOperation::SetAnswer {
+      answer: answer.clone(),
+      discord_id,
+    })
+  }
+
+  async fn answers(&mut self, discord_id: UserId) -> Result<Vec<Answer>> {
+    Ok(
+      self
+        .snapshot
+        .user(discord_id)
+        .map(|user| user.answers.clone())
+        .unwrap_or_default(),
+    )
+  }
+
+  async fn queued_answers(&mut self, discord_id: UserId) -> Result<BTreeMap<UserId, Vec<Answer>>> {
+    Ok(
+      self
+        .snapshot
+        .queued(discord_id)
+        .filter_map(|candidate_id| self.snapshot.user(candidate_id))
+        .filter(|user| !user.answers.is_empty())
+        .map(|user| (user.discord_id, user.answers.clone()))
+        .collect(),
+    )
+  }
+
+  async fn bio(&mut self, discord_id: UserId) -> Result<String> {
+    self.snapshot.bio(discord_id)
+  }
+
+  async fn bio_at(&mut self, discord_id: UserId, at: f64) -> Result<Option<String>> {
+    Ok(self.snapshot.bio_at(discord_id, at))
+  }
+
+  async fn respond_to_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    response: bool,
+    responded_at: f64,
+  ) -> Result<()> {
+    self.perform(MemoryOperation::RespondToCandidate {
+      user_id,
+      candidate_id,
+      response,
+      responded_at,
+    })
+  }
+
+  async fn last_response(&mut self, user_id: UserId) -> Result<Option<(UserId, bool, f64)>> {
+    Ok(self.snapshot.last_response(user_id))
+  }
+
+  async fn delete_response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::DeleteResponse {
+      user_id,
+      candidate_id,
+    })
+  }
+
+  async fn declined_with_new_bios(&mut self, user_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    Ok(self.snapshot.declined_with_new_bios(user_id))
+  }
+
+  async fn dismiss_match(&mut self, user_id: UserId, match_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::DismissMatch { user_id, match_id })
+  }
+
+  async fn response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<Option<bool>> {
+    Ok(self.snapshot.response(user_id, candidate_id))
+  }
+
+  async fn super_like(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::SuperLike {
+      user_id,
+      candidate_id,
+    })
+  }
+
+  async fn super_liked(&mut self, user_id: UserId, candidate_id: UserId) -> Result<bool> {
+    Ok(self.snapshot.super_liked(user_id, candidate_id))
+  }
+
+  async fn report(&mut self, user_id: UserId, reported_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::Report {
+      user_id,
+      reported_id,
+    })
+  }
+
+  async fn defer_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    deferred_at: f64,
+  ) -> Result<()> {
+    self.perform(MemoryOperation::DeferCandidate {
+      user_id,
+      candidate_id,
+      deferred_at,
+    })
+  }
+
+  async fn deferrals(&mut self, discord_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    Ok(
+      self
+        .snapshot
+        .deferrals
+        .iter()
+        .filter(|((user_id, _), _)| *user_id == discord_id)
+        .map(|(&(_, candidate_id), &deferred_at)| (candidate_id, deferred_at))
+        .collect(),
+    )
+  }
+
+  async fn get_match(&mut self, discord_id: UserId) -> Result<Option<UserId>> {
+    Ok(self.snapshot.get_match(discord_id))
+  }
+
+  async fn candidates(&mut self, discord_id: UserId) -> Result<Vec<Candidate>> {
+    Ok(self.snapshot.candidates(discord_id))
+  }
+
+  async fn refill_candidate_queue(&mut self, discord_id: UserId, limit: u64) -> Result<u64> {
+    let before = self.snapshot.candidate_queue.len();
+
+    self.perform(MemoryOperation::RefillCandidateQueue { discord_id, limit })?;
+
+    Ok((self.snapshot.candidate_queue.len() - before) as u64)
+  }
+
+  async fn queue_exhausted(&mut self, discord_id: UserId) -> Result<bool> {
+    Ok(self.snapshot.exhausted_queues.contains(&discord_id))
+  }
+
+  async fn set_queue_exhausted(&mut self, discord_id: UserId, exhausted: bool) -> Result<()> {
+    self.perform(MemoryOperation::SetQueueExhausted {
+      discord_id,
+      exhausted,
+    })
+  }
+
+  async fn enqueue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::EnqueueCandidate {
+      discord_id,
+      candidate_id,
+    })
+  }
+
+  async fn dequeue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::DequeueCandidate {
+      discord_id,
+      candidate_id,
+    })
+  }
+
+  async fn offer_candidate(&mut self, candidate_id: UserId, limit: u64) -> Result<()> {
+    self.perform(MemoryOperation::OfferCandidate {
+      candidate_id,
+      limit,
+    })
+  }
+
+  async fn withdraw_candidate(&mut self, candidate_id: UserId) -> Result<()> {
+    self.perform(MemoryOperation::WithdrawCandidate { candidate_id })
+  }
+
+  async fn exposure(&mut self, discord_id: UserId) -> Result<(f64, Option<f64>)> {
+    if self.snapshot.user(discord_id).is_none() {
+      return Err(Error::UserUnknown { id: discord_id });
+    }
+
+    Ok(self.snapshot.exposure(discord_id))
+  }
+
+  async fn set_exposure(&mut self, discord_id: UserId, exposure: f64, shown_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::SetExposure {
+      discord_id,
+      exposure,
+      shown_at,
+    })
+  }
+
+  async fn set_last_active(&mut self, discord_id: UserId, active_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::SetLastActive {
+      discord_id,
+      active_at,
+    })
+  }
+
+  async fn set_updated_at(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    self.perform(MemoryOperation::SetUpdatedAt {
+      discord_id,
+      updated_at,
+    })
+  }
+
+  async fn prompt(&mut self, discord_id: UserId) -> Result<Option<Prompt>> {
+    Ok(self.snapshot.prompt(discord_id))
+  }
+
+  async fn shown_to(&mut self, discord_id: UserId) -> Result<Vec<UserId>> {
+    Ok(self.snapshot.shown_to(discord_id))
+  }
+
+  async fn set_prompt(
+    &mut self,
+    discord_id: UserId,
+    prompt_message: PromptMessage,
+    sent_at: f64,
+  ) -> Result<()> {
+    self.perform(MemoryOperation::SetPrompt {
+      discord_id,
+      prompt_message,
+      sent_at,
+    })
+  }
+
+  async fn dequeue_interrupt(&mut self, shards: Range<u64>) -> Result<Option<Interrupt>> {
+    let queued = self
+      .snapshot
+      .interrupts
+      .iter()
+      .find(|(shard, _)| shards.contains(shard))
+      .copied();
+
+    match queued {
+      Some((shard, interrupt)) => {
+        self.perform(MemoryOperation::DequeueInterrupt { shard, interrupt })?;
+        Ok(Some(interrupt))
+      },
+      None => Ok(None),
+    }
+  }
+
+  async fn commit(self: Box<Self>) -> Result<()> {
+    let mut state = self.state.lock().await;
+
+    // A failed operation must leave the shared state untouched
+    let mut next = state.clone();
+
+    for operation in &self.operations {
+      next.perform(operation)?;
+    }
+
+    *state = next;
+
+    Ok(())
+  }
+}
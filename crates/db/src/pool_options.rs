@@ -0,0 +1,16 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolOptions {
+  pub connect_timeout: Duration,
+  pub max_connections: u32,
+}
+
+impl Default for PoolOptions {
+  fn default() -> Self {
+    Self {
+      connect_timeout: Duration::from_secs(30),
+      max_connections: 10,
+    }
+  }
+}
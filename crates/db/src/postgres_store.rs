@@ -0,0 +1,1624 @@
+use crate::common::*;
+
+use sqlx::{
+  migrate::MigrateDatabase,
+  postgres::{PgConnectOptions, PgPoolOptions},
+  PgPool, Postgres,
+};
+
+type Transaction = sqlx::Transaction<'static, Postgres>;
+
+/// Queries are checked against `sqlx-data.json`, or a database created by
+/// `build.rs` if `QUWUE_CHECK_QUERIES` is set
+#[derive(Debug)]
+pub(crate) struct PostgresStore {
+  pool: PgPool,
+}
+
+impl PostgresStore {
+  pub(crate) async fn connect(url: &str, pool_options: PoolOptions) -> Result<Self> {
+    if !Postgres::database_exists(url).await? {
+      Postgres::create_database(url).await?;
+    }
+
+    let options = PgConnectOptions::from_str(url)?;
+
+    let pool = PgPoolOptions::new()
+      .connect_timeout(pool_options.connect_timeout)
+      .max_connections(pool_options.max_connections)
+      .connect_with(options)
+      .await?;
+
+    sqlx::migrate!("./migrations").run(&pool).await?;
+
+    Ok(Self { pool })
+  }
+}
+
+#[async_trait]
+impl Store for PostgresStore {
+  async fn begin(&self) -> Result<Box<dyn StoreTransaction>> {
+    Ok(Box::new(self.pool.begin().await?))
+  }
+
+  async fn prompt_messages(&self) -> Result<Vec<(UserId, PromptMessage)>> {
+    sqlx::query!(
+      "SELECT
+        recipient_discord_id, discriminant, payload, message_id
+      FROM
+        prompts
+      ORDER BY
+        message_id, recipient_discord_id"
+    )
+    .fetch_all(&self.pool)
+    .await?
+    .into_iter()
+    .map(|row| {
+      Ok((
+        UserId::load(row.recipient_discord_id).unwrap_infallible(),
+        PromptMessage {
+          prompt:     Prompt::load((row.discriminant, row.payload))?,
+          message_id: MessageId::load(row.message_id).unwrap_infallible(),
+        },
+      ))
+    })
+    .collect()
+  }
+
+  async fn user_count(&self) -> Result<u64> {
+    #[allow(clippy::cast_sign_loss)]
+    Ok(
+      sqlx::query!("SELECT COUNT(*) as count FROM users")
+        .fetch_one(&self.pool)
+        .await?
+        .count
+        .unwrap_or(0) as u64,
+    )
+  }
+
+  async fn intern(&self, namespace: &str, external_id: &str) -> Result<u64> {
+    sqlx::query!(
+      "INSERT INTO external_ids(namespace, external_id) VALUES($1, $2)
+      ON CONFLICT (namespace, external_id) DO NOTHING",
+      namespace,
+      external_id,
+    )
+    .execute(&self.pool)
+    .await?;
+
+    let row = sqlx::query!(
+      "SELECT id FROM external_ids WHERE namespace = $1 AND external_id = $2",
+      namespace,
+      external_id,
+    )
+    .fetch_one(&self.pool)
+    .await?;
+
+    Ok(u64::load(row.id).unwrap_infallible())
+  }
+
+  async fn external_id(&self, namespace: &str, id: u64) -> Result<Option<String>> {
+    let id = id.store();
+
+    let row = sqlx::query!(
+      "SELECT external_id FROM external_ids WHERE namespace = $1 AND id = $2",
+      namespace,
+      id,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(row.map(|row| row.external_id))
+  }
+
+  async fn prune_events(&self, before: f64) -> Result<u64> {
+    Ok(
+      sqlx::query!(
+        "DELETE FROM processed_events WHERE processed_at < $1",
+        before
+      )
+      .execute(&self.pool)
+      .await?
+      .rows_affected(),
+    )
+  }
+
+  async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+    let seconds = duration.as_secs_f64();
+
+    let row = sqlx::query!(
+      "INSERT INTO leases
+        (name, holder, expires_at, acquired_at)
+      VALUES
+        ($1, $2, extract(epoch FROM now())::DOUBLE PRECISION + $3, extract(epoch FROM now()))
+      ON CONFLICT (name) DO UPDATE SET
+        holder = EXCLUDED.holder,
+        expires_at = EXCLUDED.expires_at,
+        acquired_at = CASE
+          WHEN leases.holder = EXCLUDED.holder THEN leases.acquired_at
+          ELSE EXCLUDED.acquired_at
+        END
+      WHERE
+        leases.holder = EXCLUDED.holder OR leases.expires_at < extract(epoch FROM now())
+      RETURNING holder",
+      name,
+      holder,
+      seconds,
+    )
+    .fetch_optional(&self.pool)
+    .await?;
+
+    Ok(row.is_some())
+  }
+
+  async fn lease_holder(&self, name: &str) -> Result<Option<String>> {
+    let row = sqlx::query!("SELECT holder FROM leases WHERE name = $1", name)
+      .fetch_optional(&self.pool)
+      .await?;
+
+    Ok(row.map(|row| row.holder))
+  }
+
+  async fn live_lease_holders(&self, prefix: &str) -> Result<Vec<String>> {
+    let pattern = format!("{}%", prefix);
+
+    let rows = sqlx::query!(
+      "SELECT
+        holder
+      FROM
+        leases
+      WHERE
+        name LIKE $1 AND expires_at > extract(epoch FROM now())
+      ORDER BY
+        acquired_at, holder",
+      pattern
+    )
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.holder).collect())
+  }
+
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+    sqlx::query!(
+      "DELETE FROM leases WHERE name = $1 AND holder = $2",
+      name,
+      holder
+    )
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn release_leases(&self, holder: &str) -> Result<()> {
+    sqlx::query!("DELETE FROM leases WHERE holder = $1", holder)
+      .execute(&self.pool)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn enqueue_interrupt(&self, shard: u64, interrupt: Interrupt) -> Result<()> {
+    let shard = shard.store();
+    let user_id = interrupt.user_id.store();
+    let candidate_id = interrupt.candidate_id.store();
+    let channel_id = interrupt.channel_id.map(ChannelId::store);
+
+    sqlx::query!(
+      "INSERT INTO interrupts
+        (shard, user_id, candidate_id, channel_id)
+      VALUES
+        ($1, $2, $3, $4)",
+      shard,
+      user_id,
+      candidate_id,
+      channel_id,
+    )
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn record_error(
+    &self,
+    reference: &str,
+    variant: &str,
+    message: &str,
+    discord_id: Option<UserId>,
+  ) -> Result<()> {
+    let discord_id = discord_id.map(UserId::store);
+
+    sqlx::query!(
+      "INSERT INTO errors
+        (reference, variant, message, discord_id)
+      VALUES
+        ($1, $2, $3, $4)",
+      reference,
+      variant,
+      message,
+      discord_id,
+    )
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl StoreTransaction for Transaction {
+  async fn load_user(&mut self, discord_id: UserId) -> Result<Option<User>> {
+    let discord_id = discord_id.store();
+
+    let row = sqlx::query!("SELECT * FROM users WHERE discord_id = $1", discord_id)
+      .fetch_optional(&mut *self)
+      .await?;
+
+    if let Some(user) = row {
+      let prompt = sqlx::query!(
+        "SELECT * FROM prompts where recipient_discord_id = $1",
+        discord_id,
+      )
+      .fetch_optional(&mut *self)
+      .await?;
+
+      let (prompt_message, prompt_sent_at) = match prompt {
+        Some(row) => (
+          Some(PromptMessage {
+            prompt:     Prompt::load((row.discriminant, row.payload))?,
+            message_id: MessageId::load(row.message_id).unwrap_infallible(),
+          }),
+          row.sent_at,
+        ),
+        None => (None, None),
+      };
+
+      let tags = if user.tagged {
+        Some(
+          sqlx::query!(
+            r#"SELECT name FROM tags
+            WHERE id IN (SELECT tag_id FROM user_tags WHERE discord_id = $1)
+            ORDER BY name COLLATE "C""#,
+            discord_id,
+          )
+          .fetch_all(&mut *self)
+          .await?
+          .into_iter()
+          .map(|row| row.name)
+          .collect(),
+        )
+      } else {
+        None
+      };
+
+      let languages = sqlx::query!(
+        r#"SELECT language FROM user_languages
+        WHERE discord_id = $1
+        ORDER BY language COLLATE "C""#,
+        discord_id,
+      )
+      .fetch_all(&mut *self)
+      .await?
+      .into_iter()
+      .map(|row| row.language)
+      .collect();
+
+      let ages = match (user.min_age_bracket, user.max_age_bracket) {
+        (Some(min), Some(max)) => Some((AgeBracket::load(min)?, AgeBracket::load(max)?)),
+        _ => None,
+      };
+
+      let preferences = Preferences {
+        age: user.age_bracket.map(AgeBracket::load).transpose()?,
+        connections: Connection::from_mask(u64::load(user.connections).unwrap_infallible()),
+        timezone_window: user
+          .timezone_window
+          .map(|hours| u64::load(hours).unwrap_infallible()),
+        utc_offset: user.utc_offset,
+        ages,
+        languages,
+      };
+
+      let answers = self
+        .answers(UserId::load(discord_id).unwrap_infallible())
+        .await?;
+
+      return Ok(Some(User {
+        id: u64::load(user.id).unwrap_infallible(),
+        discord_id: UserId::load(user.discord_id).unwrap_infallible(),
+        welcomed: user.welcomed,
+        bio: user.bio,
+        resurface_declined: user.resurface_declined,
+        paused: user.paused,
+        created_at: user.created_at,
+        updated_at: user.updated_at,
+        last_active_at: user.last_active_at,
+        prompt_message,
+        prompt_sent_at,
+        tags,
+        preferences,
+        answers,
+      }));
+    }
+
+    Ok(None)
+  }
+
+  async fn insert_user(&mut self, discord_id: UserId, pool: &str, created_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "INSERT INTO users(discord_id, pool, created_at, updated_at) VALUES($1, $2, $3, $4)",
+      discord_id,
+      pool,
+      created_at,
+      created_at
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn record_event(&mut self, key: &str, processed_at: f64) -> Result<bool> {
+    let rows_affected = sqlx::query!(
+      "INSERT INTO processed_events(key, processed_at) VALUES($1, $2)
+      ON CONFLICT (key) DO NOTHING",
+      key,
+      processed_at,
+    )
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+  }
+
+  async fn welcome(&mut self, discord_id: UserId) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET welcomed = TRUE WHERE discord_id = $1",
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_bio(&mut self, discord_id: UserId, text: &str, updated_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET bio = $1, bio_updated_at = $2 WHERE discord_id = $3",
+      text,
+      updated_at,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "INSERT INTO bio_history(discord_id, bio, set_at) VALUES($1, $2, $3)",
+      discord_id,
+      text,
+      updated_at
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_resurface_declined(&mut self, discord_id: UserId, enabled: bool) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET resurface_declined = $1 WHERE discord_id = $2",
+      enabled,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_paused(&mut self, discord_id: UserId, paused: bool) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET paused = $1 WHERE discord_id = $2",
+      paused,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn delete_profile(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "DELETE FROM responses WHERE discord_id = $1 OR candidate_id = $2",
+      discord_id,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "DELETE FROM reports WHERE discord_id = $1 OR reported_id = $2",
+      discord_id,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "DELETE FROM deferrals WHERE discord_id = $1 OR candidate_id = $2",
+      discord_id,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "DELETE FROM candidate_queue WHERE discord_id = $1 OR candidate_id = $2",
+      discord_id,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!("DELETE FROM answers WHERE discord_id = $1", discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    sqlx::query!("DELETE FROM user_tags WHERE discord_id = $1", discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    sqlx::query!(
+      "DELETE FROM user_languages WHERE discord_id = $1",
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!("DELETE FROM bio_history WHERE discord_id = $1", discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    sqlx::query!(
+      "UPDATE users SET
+        welcomed = FALSE,
+        bio = NULL,
+        bio_updated_at = NULL,
+        resurface_declined = FALSE,
+        paused = FALSE,
+        tagged = FALSE,
+        age_bracket = NULL,
+        min_age_bracket = NULL,
+        max_age_bracket = NULL,
+        utc_offset = NULL,
+        timezone_window = NULL,
+        connections = 0,
+        exposure = 0,
+        last_shown_at = NULL,
+        queue_exhausted = FALSE,
+        updated_at = $1
+      WHERE
+        discord_id = $2",
+      updated_at,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_tags(&mut self, discord_id: UserId, tags: &[String]) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!("DELETE FROM user_tags WHERE discord_id = $1", discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    for tag in tags {
+      sqlx::query!(
+        "INSERT INTO tags(name) VALUES($1) ON CONFLICT (name) DO NOTHING",
+        tag
+      )
+      .execute(&mut *self)
+      .await?;
+
+      sqlx::query!(
+        "INSERT INTO user_tags(discord_id, tag_id) SELECT $1, id FROM tags WHERE name = $2",
+        discord_id,
+        tag
+      )
+      .execute(&mut *self)
+      .await?;
+    }
+
+    sqlx::query!(
+      "UPDATE users SET tagged = TRUE WHERE discord_id = $1",
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_preferences(&mut self, discord_id: UserId, preferences: &Preferences) -> Result<()> {
+    let discord_id = discord_id.store();
+    let age_bracket = preferences.age.map(AgeBracket::store);
+    let min_age_bracket = preferences.ages.map(|(min, _)| min.store());
+    let max_age_bracket = preferences.ages.map(|(_, max)| max.store());
+    let timezone_window = preferences.timezone_window.map(u64::store);
+    let connections = Connection::mask(&preferences.connections).store();
+
+    sqlx::query!(
+      "UPDATE users
+      SET
+        age_bracket = $1,
+        min_age_bracket = $2,
+        max_age_bracket = $3,
+        utc_offset = $4,
+        timezone_window = $5,
+        connections = $6
+      WHERE
+        discord_id = $7",
+      age_bracket,
+      min_age_bracket,
+      max_age_bracket,
+      preferences.utc_offset,
+      timezone_window,
+      connections,
+      discord_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "DELETE FROM user_languages WHERE discord_id = $1",
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    for language in &preferences.languages {
+      sqlx::query!(
+        "INSERT INTO user_languages(discord_id, language) VALUES($1, $2)",
+        discord_id,
+        language
+      )
+      .execute(&mut *self)
+      .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn set_answer(&mut self, discord_id: UserId, answer: &Answer) -> Result<()> {
+    sqlx::query!(
+      "INSERT INTO answers(discord_id, question, choice, accepted, importance)
+      VALUES($1, $2, $3, $4, $5)
+      ON CONFLICT (discord_id, question) DO UPDATE SET
+        choice = excluded.choice,
+        accepted = excluded.accepted,
+        importance = excluded.importance",
+      discord_id.store(),
+      answer.question.store(),
+      answer.choice.store(),
+      answer.accepted_mask().store(),
+      answer.importance.store(),
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn answers(&mut self, discord_id: UserId) -> Result<Vec<Answer>> {
+    sqlx::query!(
+      "SELECT question, choice, accepted, importance FROM answers
+      WHERE discord_id = $1
+      ORDER BY question",
+      discord_id.store(),
+    )
+    .fetch_all(&mut *self)
+    .await?
+    .into_iter()
+    .map(|row| {
+      Ok(Answer {
+        question:   u64::load(row.question).unwrap_infallible(),
+        choice:     u64::load(row.choice).unwrap_infallible(),
+        accepted:   Answer::accepted_from_mask(u64::load(row.accepted).unwrap_infallible()),
+        importance: Importance::load(row.importance)?,
+      })
+    })
+    .collect()
+  }
+
+  async fn queued_answers(&mut self, discord_id: UserId) -> Result<BTreeMap<UserId, Vec<Answer>>> {
+    let rows = sqlx::query!(
+      "SELECT answers.discord_id, question, choice, accepted, importance
+      FROM candidate_queue
+      JOIN answers ON answers.discord_id = candidate_queue.candidate_id
+      WHERE candidate_queue.discord_id = $1
+      ORDER BY answers.discord_id, question",
+      discord_id.store(),
+    )
+    .fetch_all(&mut *self)
+    .await?;
+
+    let mut answers = BTreeMap::<UserId, Vec<Answer>>::new();
+
+    for row in rows {
+      answers
+        .entry(UserId::load(row.discord_id).unwrap_infallible())
+        .or_default()
+        .push(Answer {
+          question:   u64::load(row.question).unwrap_infallible(),
+          choice:     u64::load(row.choice).unwrap_infallible(),
+          accepted:   Answer::accepted_from_mask(u64::load(row.accepted).unwrap_infallible()),
+          importance: Importance::load(row.importance)?,
+        });
+    }
+
+    Ok(answers)
+  }
+
+  async fn bio(&mut self, id: UserId) -> Result<String> {
+    let id_storage = id.store();
+
+    let row = sqlx::query!("SELECT bio from users where discord_id = $1", id_storage)
+      .fetch_optional(&mut *self)
+      .await?;
+
+    row
+      .ok_or(Error::UserUnknown { id })?
+      .bio
+      .ok_or(Error::UserMissingBio { id })
+  }
+
+  async fn bio_at(&mut self, discord_id: UserId, at: f64) -> Result<Option<String>> {
+    let row = sqlx::query!(
+      "SELECT bio FROM bio_history
+      WHERE discord_id = $1 AND set_at <= $2
+      ORDER BY set_at DESC, id DESC
+      LIMIT 1",
+      discord_id.store(),
+      at,
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| row.bio))
+  }
+
+  async fn respond_to_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    response: bool,
+    responded_at: f64,
+  ) -> Result<()> {
+    let user_id = user_id.store();
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "INSERT INTO responses
+        (discord_id, candidate_id, response, dismissed, responded_at, created_at)
+      VALUES
+        ($1, $2, $3, FALSE, $4, $4)
+      ON CONFLICT (discord_id, candidate_id) DO UPDATE SET
+        response = excluded.response,
+        dismissed = FALSE,
+        super_like = FALSE,
+        responded_at = excluded.responded_at
+      ",
+      user_id,
+      candidate_id,
+      response,
+      responded_at
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn last_response(&mut self, user_id: UserId) -> Result<Option<(UserId, bool, f64)>> {
+    let row = sqlx::query!(
+      "SELECT
+        candidate_id, response, responded_at
+      FROM
+        responses
+      WHERE
+        discord_id = $1
+      ORDER BY
+        responded_at DESC, id DESC
+      LIMIT 1",
+      user_id.store(),
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| {
+      (
+        UserId::load(row.candidate_id).unwrap_infallible(),
+        row.response,
+        row.responded_at,
+      )
+    }))
+  }
+
+  async fn delete_response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    let user_id = user_id.store();
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "DELETE FROM responses WHERE discord_id = $1 AND candidate_id = $2",
+      user_id,
+      candidate_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "DELETE FROM reports WHERE discord_id = $1 AND reported_id = $2",
+      user_id,
+      candidate_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn declined_with_new_bios(&mut self, user_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    Ok(
+      sqlx::query!(
+        "SELECT
+          responses.candidate_id, responses.responded_at
+        FROM
+          responses
+          JOIN users ON users.discord_id = responses.candidate_id
+        WHERE
+          responses.discord_id = $1
+          AND
+          NOT responses.response
+          AND
+          users.bio_updated_at > responses.responded_at
+          AND
+          NOT EXISTS (
+            SELECT * FROM reports
+            WHERE discord_id = responses.discord_id AND reported_id = responses.candidate_id
+          )
+        ORDER BY
+          responses.id",
+        user_id.store(),
+      )
+      .fetch_all(&mut *self)
+      .await?
+      .into_iter()
+      .map(|row| {
+        (
+          UserId::load(row.candidate_id).unwrap_infallible(),
+          row.responded_at,
+        )
+      })
+      .collect(),
+    )
+  }
+
+  async fn dismiss_match(&mut self, user_id: UserId, match_id: UserId) -> Result<()> {
+    let user_id = user_id.store();
+    let match_id = match_id.store();
+
+    sqlx::query!(
+      "UPDATE responses SET dismissed = TRUE WHERE discord_id = $1 AND candidate_id = $2",
+      user_id,
+      match_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<Option<bool>> {
+    let user_id = user_id.store();
+    let candidate_id = candidate_id.store();
+
+    let row = sqlx::query!(
+      "SELECT
+        response
+      FROM
+        responses
+      WHERE
+        discord_id = $1 AND candidate_id = $2
+      LIMIT 1",
+      user_id,
+      candidate_id,
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| row.response))
+  }
+
+  async fn super_like(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    let user_id = user_id.store();
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "UPDATE responses SET super_like = TRUE WHERE discord_id = $1 AND candidate_id = $2",
+      user_id,
+      candidate_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn super_liked(&mut self, user_id: UserId, candidate_id: UserId) -> Result<bool> {
+    let user_id = user_id.store();
+    let candidate_id = candidate_id.store();
+
+    let row = sqlx::query!(
+      "SELECT
+        super_like
+      FROM
+        responses
+      WHERE
+        discord_id = $1 AND candidate_id = $2
+      LIMIT 1",
+      user_id,
+      candidate_id,
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map_or(false, |row| row.super_like))
+  }
+
+  async fn report(&mut self, user_id: UserId, reported_id: UserId) -> Result<()> {
+    let user_id = user_id.store();
+    let reported_id = reported_id.store();
+
+    sqlx::query!(
+      "INSERT INTO reports (discord_id, reported_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+      user_id,
+      reported_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn defer_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    deferred_at: f64,
+  ) -> Result<()> {
+    sqlx::query!(
+      "INSERT INTO deferrals(discord_id, candidate_id, deferred_at, created_at)
+      VALUES($1, $2, $3, $3)
+      ON CONFLICT (discord_id, candidate_id) DO UPDATE SET
+        deferred_at = excluded.deferred_at",
+      user_id.store(),
+      candidate_id.store(),
+      deferred_at,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn deferrals(&mut self, discord_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    Ok(
+      sqlx::query!(
+        "SELECT candidate_id, deferred_at FROM deferrals
+        WHERE discord_id = $1
+        ORDER BY candidate_id",
+        discord_id.store(),
+      )
+      .fetch_all(&mut *self)
+      .await?
+      .into_iter()
+      .map(|row| {
+        (
+          UserId::load(row.candidate_id).unwrap_infallible(),
+          row.deferred_at,
+        )
+      })
+      .collect(),
+    )
+  }
+
+  async fn get_match(&mut self, discord_id: UserId) -> Result<Option<UserId>> {
+    let discord_id = discord_id.store();
+
+    let row = sqlx::query!(
+      "SELECT
+        candidate_id
+      FROM
+        responses AS outer_responses
+      WHERE
+        discord_id = $1
+        AND
+        response
+        AND
+        NOT dismissed
+        AND
+        EXISTS (
+          SELECT * FROM responses
+          WHERE
+            discord_id = outer_responses.candidate_id
+            AND
+            candidate_id = outer_responses.discord_id
+            AND
+            response
+        )
+      ORDER BY
+        id
+      LIMIT 1",
+      discord_id,
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| UserId::load(row.candidate_id).unwrap_infallible()))
+  }
+
+  async fn candidates(&mut self, discord_id: UserId) -> Result<Vec<Candidate>> {
+    let discord_id = discord_id.store();
+
+    let quiescent_discriminant = PromptDiscriminant::Quiescent.store();
+
+    let rows = sqlx::query!(
+      r#"SELECT
+        potential_candidate.discord_id,
+        potential_candidate.exposure,
+        potential_candidate.last_shown_at,
+        potential_candidate.last_active_at,
+        EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $1 AND response
+        ) AS "accepted!",
+        (
+          SELECT COUNT(*) FROM user_tags
+          WHERE
+            discord_id = potential_candidate.discord_id
+            AND
+            tag_id IN (SELECT tag_id FROM user_tags WHERE discord_id = $1)
+        ) AS "shared_tags!"
+      FROM
+        candidate_queue
+        JOIN users AS potential_candidate
+          ON potential_candidate.discord_id = candidate_queue.candidate_id
+      WHERE
+        candidate_queue.discord_id = $1
+        AND
+        potential_candidate.welcomed = TRUE
+        AND
+        potential_candidate.bio IS NOT NULL
+        AND
+        NOT potential_candidate.paused
+        AND
+        potential_candidate.discord_id != $1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = $1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $1 AND NOT response
+        )
+        AND
+        EXISTS (
+          SELECT * FROM prompts
+          WHERE
+            recipient_discord_id = potential_candidate.discord_id AND discriminant = $2
+        )
+        AND
+        potential_candidate.pool = (SELECT pool FROM users WHERE discord_id = $1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = $1 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        potential_candidate.id"#,
+      discord_id,
+      quiescent_discriminant,
+    )
+    .fetch_all(&mut *self)
+    .await?;
+
+    Ok(
+      rows
+        .into_iter()
+        .map(|row| Candidate {
+          discord_id:     UserId::load(row.discord_id).unwrap_infallible(),
+          accepted:       row.accepted,
+          exposure:       row.exposure,
+          last_shown_at:  row.last_shown_at,
+          last_active_at: row.last_active_at,
+          shared_tags:    u64::load(row.shared_tags).unwrap_infallible(),
+          compatibility:  None,
+        })
+        .collect(),
+    )
+  }
+
+  async fn refill_candidate_queue(&mut self, discord_id: UserId, limit: u64) -> Result<u64> {
+    let discord_id = discord_id.store();
+    let limit = limit.store();
+
+    let accepted = sqlx::query!(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        $1, discord_id
+      FROM
+        users AS potential_candidate
+      WHERE
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        NOT paused
+        AND
+        discord_id != $2
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = $3 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $4 AND response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = $5)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = $6 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM candidate_queue
+          WHERE discord_id = $7 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        id
+      LIMIT $8",
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      limit,
+    )
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    let others = sqlx::query!(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        $1, discord_id
+      FROM
+        users AS potential_candidate
+      WHERE
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        NOT paused
+        AND
+        discord_id != $2
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = $3 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = $4 AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = $5)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = $6 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM candidate_queue
+          WHERE discord_id = $7 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        exposure, id
+      LIMIT $8",
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      discord_id,
+      limit,
+    )
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    Ok(accepted + others)
+  }
+
+  async fn queue_exhausted(&mut self, discord_id: UserId) -> Result<bool> {
+    let discord_id = discord_id.store();
+
+    Ok(
+      sqlx::query!(
+        "SELECT queue_exhausted FROM users WHERE discord_id = $1",
+        discord_id
+      )
+      .fetch_optional(&mut *self)
+      .await?
+      .map_or(false, |row| row.queue_exhausted),
+    )
+  }
+
+  async fn set_queue_exhausted(&mut self, discord_id: UserId, exhausted: bool) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET queue_exhausted = $1 WHERE discord_id = $2",
+      exhausted,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn enqueue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    let discord_id = discord_id.store();
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "INSERT INTO candidate_queue (discord_id, candidate_id) VALUES ($1, $2)
+      ON CONFLICT DO NOTHING",
+      discord_id,
+      candidate_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn dequeue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    let discord_id = discord_id.store();
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "DELETE FROM candidate_queue WHERE discord_id = $1 AND candidate_id = $2",
+      discord_id,
+      candidate_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn offer_candidate(&mut self, candidate_id: UserId, limit: u64) -> Result<()> {
+    let candidate_id = candidate_id.store();
+    let limit = limit.store();
+
+    sqlx::query!(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        discord_id, $1
+      FROM
+        users
+      WHERE
+        queue_exhausted
+        AND
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        discord_id != $2
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = users.discord_id AND candidate_id = $3
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = $4 AND candidate_id = users.discord_id AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = $5)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = users.discord_id AND candidate_id = $6
+        )
+      ON CONFLICT DO NOTHING",
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query!(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        discord_id, $1
+      FROM
+        users
+      WHERE
+        NOT queue_exhausted
+        AND
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        discord_id != $2
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = users.discord_id AND candidate_id = $3
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = $4 AND candidate_id = users.discord_id AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = $5)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = users.discord_id AND candidate_id = $6
+        )
+      ORDER BY
+        last_active_at DESC NULLS LAST, id
+      LIMIT $7
+      ON CONFLICT DO NOTHING",
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      candidate_id,
+      limit,
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn withdraw_candidate(&mut self, candidate_id: UserId) -> Result<()> {
+    let candidate_id = candidate_id.store();
+
+    sqlx::query!(
+      "DELETE FROM candidate_queue WHERE candidate_id = $1",
+      candidate_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn exposure(&mut self, id: UserId) -> Result<(f64, Option<f64>)> {
+    let id_storage = id.store();
+
+    let row = sqlx::query!(
+      "SELECT exposure, last_shown_at FROM users WHERE discord_id = $1",
+      id_storage
+    )
+    .fetch_optional(&mut *self)
+    .await?
+    .ok_or(Error::UserUnknown { id })?;
+
+    Ok((row.exposure, row.last_shown_at))
+  }
+
+  async fn set_exposure(&mut self, discord_id: UserId, exposure: f64, shown_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET exposure = $1, last_shown_at = $2 WHERE discord_id = $3",
+      exposure,
+      shown_at,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_last_active(&mut self, discord_id: UserId, active_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET last_active_at = $1 WHERE discord_id = $2",
+      active_at,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_updated_at(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "UPDATE users SET updated_at = $1 WHERE discord_id = $2",
+      updated_at,
+      discord_id
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn prompt(&mut self, discord_id: UserId) -> Result<Option<Prompt>> {
+    let discord_id = discord_id.store();
+
+    sqlx::query!(
+      "SELECT discriminant, payload FROM prompts WHERE recipient_discord_id = $1",
+      discord_id,
+    )
+    .fetch_optional(&mut *self)
+    .await?
+    .map(|row| Prompt::load((row.discriminant, row.payload)))
+    .transpose()
+  }
+
+  async fn shown_to(&mut self, discord_id: UserId) -> Result<Vec<UserId>> {
+    let discord_id = discord_id.store();
+
+    let candidate_discriminant = PromptDiscriminant::Candidate.store();
+    let match_discriminant = PromptDiscriminant::Match.store();
+
+    Ok(
+      sqlx::query!(
+        "SELECT recipient_discord_id FROM prompts
+        WHERE payload = $1 AND discriminant IN ($2, $3)
+        ORDER BY recipient_discord_id",
+        discord_id,
+        candidate_discriminant,
+        match_discriminant,
+      )
+      .fetch_all(&mut *self)
+      .await?
+      .into_iter()
+      .map(|row| UserId::load(row.recipient_discord_id).unwrap_infallible())
+      .collect(),
+    )
+  }
+
+  async fn set_prompt(
+    &mut self,
+    discord_id: UserId,
+    prompt_message: PromptMessage,
+    sent_at: f64,
+  ) -> Result<()> {
+    let discord_id = discord_id.store();
+    let (discriminant, payload) = prompt_message.prompt.store();
+    let message_id = prompt_message.message_id.store();
+
+    sqlx::query!(
+      "INSERT INTO prompts
+        (discriminant, payload, message_id, recipient_discord_id, sent_at, created_at)
+      VALUES
+        ($1, $2, $3, $4, $5, $5)
+      ON CONFLICT (recipient_discord_id) DO UPDATE SET
+        discriminant = $1,
+        payload = $2,
+        message_id = $3,
+        recipient_discord_id = $4,
+        sent_at = $5
+      ",
+      discriminant,
+      payload,
+      message_id,
+      discord_id,
+      sent_at
+    )
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn dequeue_interrupt(&mut self, shards: Range<u64>) -> Result<Option<Interrupt>> {
+    let start = shards.start.store();
+    let end = shards.end.store();
+
+    let row = sqlx::query!(
+      "DELETE FROM interrupts
+      WHERE id = (
+        SELECT id FROM interrupts
+        WHERE shard >= $1 AND shard < $2
+        ORDER BY id
+        LIMIT 1
+        FOR UPDATE
+      )
+      RETURNING user_id, candidate_id, channel_id",
+      start,
+      end,
+    )
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| {
+      Interrupt {
+        user_id:      UserId::load(row.user_id).unwrap_infallible(),
+        candidate_id: UserId::load(row.candidate_id).unwrap_infallible(),
+        channel_id:   row
+          .channel_id
+          .map(|id| ChannelId::load(id).unwrap_infallible()),
+      }
+    }))
+  }
+
+  async fn commit(self: Box<Self>) -> Result<()> {
+    sqlx::Transaction::commit(*self).await?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn store() -> PostgresStore {
+    PostgresStore::connect(&TestContext::postgres_url(), PoolOptions::default())
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn inserting_responses_from_non_existant_users_is_an_error() {
+    let store = store().await;
+
+    let mut tx = store.pool.begin().await.unwrap();
+
+    let error = sqlx::query!(
+      "INSERT INTO responses
+        (discord_id, candidate_id, response, dismissed)
+      VALUES
+        (1, 100, TRUE, FALSE)",
+    )
+    .execute(&mut tx)
+    .await
+    .unwrap_err();
+
+    guard_unwrap!(let sqlx::Error::Database(error) = error);
+
+    assert_eq!(
+      error.message(),
+      r#"insert or update on table "responses" violates foreign key constraint "responses_discord_id_fkey""#
+    );
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn inserting_responses_to_non_existant_users_is_an_error() {
+    let store = store().await;
+
+    let mut tx = store.pool.begin().await.unwrap();
+
+    tx.insert_user(UserId(100), Db::DEFAULT_POOL, 0.0)
+      .await
+      .unwrap();
+
+    let error = sqlx::query!(
+      "INSERT INTO responses
+        (discord_id, candidate_id, response, dismissed)
+      VALUES
+        (100, 1, TRUE, FALSE)",
+    )
+    .execute(&mut tx)
+    .await
+    .unwrap_err();
+
+    guard_unwrap!(let sqlx::Error::Database(error) = error);
+
+    assert_eq!(
+      error.message(),
+      r#"insert or update on table "responses" violates foreign key constraint "responses_candidate_id_fkey""#
+    );
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn record_error() {
+    let store = store().await;
+
+    store
+      .record_error("ABCD2345", "Http", "Http error: 500", Some(UserId(100)))
+      .await
+      .unwrap();
+
+    let row = sqlx::query!(
+      "SELECT variant, message, discord_id FROM errors WHERE reference = $1",
+      "ABCD2345"
+    )
+    .fetch_one(&store.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(row.variant, "Http");
+    assert_eq!(row.message, "Http error: 500");
+    assert_eq!(row.discord_id, Some(100));
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn responses_keep_their_creation_time() {
+    let store = store().await;
+
+    let mut tx = store.pool.begin().await.unwrap();
+
+    tx.insert_user(UserId(1), "", 0.0).await.unwrap();
+    tx.insert_user(UserId(2), "", 0.0).await.unwrap();
+
+    tx.respond_to_candidate(UserId(1), UserId(2), false, 10.0)
+      .await
+      .unwrap();
+    tx.respond_to_candidate(UserId(1), UserId(2), true, 20.0)
+      .await
+      .unwrap();
+
+    let row = sqlx::query!(
+      "SELECT created_at, responded_at FROM responses WHERE discord_id = 1 AND candidate_id = 2"
+    )
+    .fetch_one(&mut tx)
+    .await
+    .unwrap();
+
+    assert_eq!((row.created_at, row.responded_at), (Some(10.0), 20.0));
+  }
+}
@@ -0,0 +1,321 @@
+use crate::common::*;
+
+/// Drives responses through a `Db` the way the bot does, and checks
+/// invariants against every prompt that would be sent
+pub(crate) struct PromptFlow {
+  accepted:   BTreeSet<(UserId, UserId)>,
+  db:         Db,
+  declined:   BTreeSet<(UserId, UserId)>,
+  matched:    BTreeSet<(UserId, UserId)>,
+  message_id: u64,
+  users:      BTreeSet<UserId>,
+}
+
+impl PromptFlow {
+  pub(crate) fn new(db: Db) -> Self {
+    Self {
+      accepted: BTreeSet::new(),
+      declined: BTreeSet::new(),
+      matched: BTreeSet::new(),
+      message_id: 0,
+      users: BTreeSet::new(),
+      db,
+    }
+  }
+
+  pub(crate) async fn respond(&mut self, user_id: UserId, response: &Response) {
+    self.users.insert(user_id);
+
+    let user = self.db.user(user_id).await.unwrap();
+
+    let update = user.update(response);
+
+    match update.action {
+      Some(Action::AcceptCandidate { id } | Action::SuperLikeCandidate { id }) => {
+        self.accepted.insert((user_id, id));
+      },
+      Some(Action::DeclineCandidate { id } | Action::ReportCandidate { id }) => {
+        self.declined.insert((user_id, id));
+      },
+      Some(Action::DeleteProfile) => {
+        let unrelated = |&(a, b): &(UserId, UserId)| a != user_id && b != user_id;
+        self.accepted.retain(unrelated);
+        self.declined.retain(unrelated);
+        self.matched.retain(unrelated);
+      },
+      _ => {},
+    }
+
+    let tx = self.db.prepare(user_id, &update).await.unwrap();
+
+    // An undone response restores the candidate it was made to
+    if let (Some(Action::Undo), Prompt::Candidate { id }) = (&update.action, tx.prompt()) {
+      self.accepted.remove(&(user_id, id));
+      self.declined.remove(&(user_id, id));
+    }
+
+    let retracted = tx.retracted();
+
+    // Onboarded users can go back to rewrite their bio and tags
+    let onboarded = Self::onboarded(&user)
+      && update.action != Some(Action::DeleteProfile)
+      && !matches!(update.next_prompt, Prompt::Bio | Prompt::Tags);
+
+    self.deliver(tx, onboarded).await;
+
+    let interrupted = match update.action {
+      Some(Action::AcceptCandidate { id } | Action::SuperLikeCandidate { id }) => vec![id],
+      _ => retracted,
+    };
+
+    for candidate_id in interrupted {
+      let interrupt = Interrupt {
+        channel_id: None,
+        user_id,
+        candidate_id,
+      };
+
+      self.db.enqueue_interrupt(0, interrupt).await.unwrap();
+    }
+
+    while let Some((interrupt, tx)) = self.db.prepare_interrupt(0..1).await.unwrap() {
+      if let Some(tx) = tx {
+        let onboarded = Self::onboarded(&self.db.user(interrupt.candidate_id).await.unwrap());
+        self.deliver(tx, onboarded).await;
+      }
+    }
+  }
+
+  /// Declines every outstanding candidate, so that no new matches are made,
+  /// and checks that every mutual accept was shown to both users
+  pub(crate) async fn settle(&mut self) {
+    let users = self.users.iter().copied().collect::<Vec<UserId>>();
+
+    let rounds = users.len() * users.len() * 2 + Questionnaire::QUESTIONS.len() * 3 + 6;
+
+    for _ in 0..=rounds {
+      let mut responded = false;
+
+      for &user_id in &users {
+        let response = match self.db.current_prompt(user_id).await.unwrap() {
+          Some(Prompt::Match { .. }) => Response::Reaction(Emoji::ThumbsUp),
+          Some(Prompt::Candidate { .. }) => Response::Reaction(Emoji::ThumbsDown),
+          None | Some(Prompt::Welcome | Prompt::Quiescent) => continue,
+          Some(prompt) => {
+            if !Self::onboarded(&self.db.user(user_id).await.unwrap()) {
+              continue;
+            }
+
+            match prompt {
+              Prompt::Bio => Response::message("Bio"),
+              Prompt::QuestionAccepted { .. } => Response::Reaction(Emoji::ThumbsUp),
+              Prompt::QuestionImportance { .. } => Response::Reaction(Emoji::KEYCAPS[0]),
+              _ => Response::Reaction(Emoji::Skip),
+            }
+          },
+        };
+
+        self.respond(user_id, &response).await;
+
+        responded = true;
+      }
+
+      if !responded {
+        break;
+      }
+    }
+
+    for &(a, b) in &self.accepted {
+      if self.accepted.contains(&(b, a)) {
+        assert!(
+          self.matched.contains(&(a, b)),
+          "{} and {} accepted each other, but {} was never shown the match",
+          a,
+          b,
+          a
+        );
+      }
+    }
+  }
+
+  fn onboarded(user: &User) -> bool {
+    user.welcomed && user.bio.is_some() && user.tags.is_some()
+  }
+
+  async fn deliver(&mut self, tx: UpdateTx, onboarded: bool) {
+    let user_id = tx.user_id;
+    let prompt = tx.prompt();
+
+    if onboarded {
+      assert!(
+        !matches!(prompt, Prompt::Welcome | Prompt::Bio | Prompt::Tags),
+        "{} was sent back to {:?} after onboarding",
+        user_id,
+        prompt
+      );
+    }
+
+    match prompt {
+      Prompt::Candidate { id } => {
+        assert_ne!(id, user_id, "{} was shown themselves", user_id);
+        assert!(
+          !self.declined.contains(&(user_id, id)),
+          "{} was shown {}, whom they declined",
+          user_id,
+          id
+        );
+        assert!(
+          !self.declined.contains(&(id, user_id)),
+          "{} was shown {}, who declined them",
+          user_id,
+          id
+        );
+      },
+      Prompt::Match { id } => {
+        assert_ne!(id, user_id, "{} was matched with themselves", user_id);
+        assert!(
+          self.accepted.contains(&(user_id, id)) && self.accepted.contains(&(id, user_id)),
+          "{} was matched with {} without mutual acceptance",
+          user_id,
+          id
+        );
+        self.matched.insert((user_id, id));
+      },
+      Prompt::Welcome
+      | Prompt::Bio
+      | Prompt::Tags
+      | Prompt::Quiescent
+      | Prompt::Age
+      | Prompt::AgeRange
+      | Prompt::UtcOffset
+      | Prompt::TimezoneWindow
+      | Prompt::Languages
+      | Prompt::Connections => {},
+      Prompt::QuestionChoice { question }
+      | Prompt::QuestionAccepted { question }
+      | Prompt::QuestionImportance { question } => assert!(
+        Questionnaire::question(question).is_some(),
+        "{} was asked question {}, which doesn't exist",
+        user_id,
+        question
+      ),
+    }
+
+    self.message_id += 1;
+
+    tx.commit(MessageId(self.message_id)).await.unwrap();
+
+    if let Prompt::Candidate { id } = prompt {
+      let user = self.db.user(user_id).await.unwrap();
+      let candidate = self.db.user(id).await.unwrap();
+      assert!(
+        user.preferences.compatible(&candidate.preferences),
+        "{} was shown {}, whose preferences are incompatible",
+        user_id,
+        id
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use proptest::{
+    collection::vec,
+    prelude::*,
+    test_runner::{TestRng, TestRunner},
+  };
+
+  const NOW: f64 = 1_000_000_000.0;
+
+  fn response() -> impl Strategy<Value = Response> {
+    prop_oneof![
+      Just(Response::message("ok")),
+      Just(Response::message("yes")),
+      Just(Response::message("no")),
+      Just(Response::message("none")),
+      Just(Response::message(Interests::COMMAND)),
+      Just(Response::message(Preferences::COMMAND)),
+      Just(Response::message("any")),
+      Just(Response::message("1")),
+      Just(Response::message("2-3")),
+      Just(Response::message("+2")),
+      Just(Response::message("english")),
+      Just(Response::message("gaming, study")),
+      Just(Response::message(Questionnaire::COMMAND)),
+      Just(Response::message("skip")),
+      Just(Response::message("1, 3")),
+      Just(Response::message(User::UNDO_COMMAND)),
+      Just(Response::message(User::RESURFACE_COMMAND)),
+      Just(Response::message(User::PAUSE_COMMAND)),
+      Just(Response::message(User::DELETE_COMMAND)),
+      "[a-z ]{0,12}".prop_map(Response::message),
+      Just(Response::Reaction(Emoji::ThumbsUp)),
+      Just(Response::Reaction(Emoji::ThumbsDown)),
+      Just(Response::Reaction(Emoji::One)),
+      Just(Response::Reaction(Emoji::Two)),
+      Just(Response::Reaction(Emoji::Skip)),
+      Just(Response::Reaction(Emoji::SuperLike)),
+      Just(Response::Reaction(Emoji::Report)),
+      Just(Response::UnrecognizedReaction("🦀".into())),
+      Just(Response::custom_reaction("1234")),
+    ]
+  }
+
+  fn run(steps: &[(u64, Response)]) -> PromptFlow {
+    tokio::runtime::Builder::new_current_thread()
+      .build()
+      .unwrap()
+      .block_on(async {
+        let mut flow = PromptFlow::new(Db::memory().with_clock(Clock::Fixed(NOW)));
+
+        for (user_id, response) in steps {
+          flow.respond(UserId(*user_id), response).await;
+        }
+
+        flow.settle().await;
+
+        flow
+      })
+  }
+
+  #[test]
+  fn invariants_hold() {
+    let config = ProptestConfig {
+      cases: 128,
+      source_file: Some(file!()),
+      ..ProptestConfig::default()
+    };
+
+    let rng = TestRng::deterministic_rng(config.rng_algorithm);
+
+    TestRunner::new_with_rng(config, rng)
+      .run(&vec((0..6_u64, response()), 0..200), |steps| {
+        run(&steps);
+        Ok(())
+      })
+      .unwrap();
+  }
+
+  #[test]
+  fn mutual_accept() {
+    let mut steps = Vec::new();
+
+    for user_id in 0..2 {
+      steps.push((user_id, Response::message("hi")));
+      steps.push((user_id, Response::message("ok")));
+      steps.push((user_id, Response::message("My bio")));
+      steps.push((user_id, Response::message("none")));
+    }
+
+    steps.push((1, Response::message("yes")));
+    steps.push((0, Response::message("yes")));
+
+    let flow = run(&steps);
+
+    assert!(flow.matched.contains(&(UserId(0), UserId(1))));
+    assert!(flow.matched.contains(&(UserId(1), UserId(0))));
+  }
+}
@@ -0,0 +1,257 @@
+use crate::common::*;
+
+use std::cmp::Ordering;
+
+/// Candidates who have already accepted the user come first, so that mutual
+/// matches are found quickly. A small seeded random term keeps candidates
+/// with similar scores from always being shown in the same order.
+#[derive(Debug, Clone, Copy)]
+pub struct Ranking {
+  seed: u64,
+}
+
+impl Ranking {
+  const ACTIVITY_HALF_LIFE: f64 = 3.0 * Self::DAY;
+  const ACTIVITY_WEIGHT: f64 = 0.5;
+  /// Relative to unknown compatibility, full compatibility gains half of
+  /// this and full incompatibility loses half
+  const COMPATIBILITY_WEIGHT: f64 = 2.0;
+  const DAY: f64 = 24.0 * 60.0 * 60.0;
+  const EXPOSURE_HALF_LIFE: f64 = 7.0 * Self::DAY;
+  const EXPOSURE_WEIGHT: f64 = 1.0;
+  const RANDOM_WEIGHT: f64 = 0.05;
+  const SHOWN_INTERVAL: f64 = Self::DAY;
+  const SHOWN_WEIGHT: f64 = 0.5;
+  const TAG_WEIGHT: f64 = 1.0;
+
+  pub fn new(seed: u64) -> Self {
+    Self { seed }
+  }
+
+  pub fn decayed_exposure(exposure: f64, last_shown_at: Option<f64>, now: f64) -> f64 {
+    match last_shown_at {
+      Some(last_shown_at) => exposure * Self::decay(now - last_shown_at, Self::EXPOSURE_HALF_LIFE),
+      None => exposure,
+    }
+  }
+
+  fn decay(elapsed: f64, half_life: f64) -> f64 {
+    0.5_f64.powf(elapsed.max(0.0) / half_life)
+  }
+
+  /// Higher is better
+  #[allow(clippy::cast_precision_loss)]
+  pub fn score(&self, user_id: UserId, candidate: &Candidate, now: f64) -> f64 {
+    let exposure = Self::decayed_exposure(candidate.exposure, candidate.last_shown_at, now);
+
+    let shown = candidate.last_shown_at.map_or(1.0, |last_shown_at| {
+      ((now - last_shown_at) / Self::SHOWN_INTERVAL).clamp(0.0, 1.0)
+    });
+
+    let activity = candidate.last_active_at.map_or(0.0, |last_active_at| {
+      Self::decay(now - last_active_at, Self::ACTIVITY_HALF_LIFE)
+    });
+
+    let compatibility = candidate
+      .compatibility
+      .map_or(0.0, |percentage| percentage as f64 / 100.0 - 0.5);
+
+    Self::TAG_WEIGHT * (candidate.shared_tags as f64).ln_1p()
+      + Self::COMPATIBILITY_WEIGHT * compatibility
+      - Self::EXPOSURE_WEIGHT * exposure.ln_1p()
+      + Self::SHOWN_WEIGHT * shown
+      + Self::ACTIVITY_WEIGHT * activity
+      + Self::RANDOM_WEIGHT * self.random(user_id, candidate.discord_id)
+  }
+
+  /// In `[0, 1)`
+  #[allow(clippy::cast_precision_loss)]
+  fn random(self, user_id: UserId, candidate_id: UserId) -> f64 {
+    let hash = Self::mix(self.seed ^ Self::mix(user_id.0 ^ Self::mix(candidate_id.0)));
+    (hash >> 11) as f64 / (1_u64 << 53) as f64
+  }
+
+  /// The `SplitMix64` finalizer
+  fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+  }
+
+  pub fn rank(&self, user_id: UserId, candidates: &mut [Candidate], now: f64) {
+    candidates.sort_by(|a, b| {
+      b.accepted
+        .cmp(&a.accepted)
+        .then_with(|| {
+          self
+            .score(user_id, b, now)
+            .partial_cmp(&self.score(user_id, a, now))
+            .unwrap_or(Ordering::Equal)
+        })
+        .then_with(|| a.discord_id.cmp(&b.discord_id))
+    });
+  }
+
+  pub fn choose(
+    &self,
+    user_id: UserId,
+    mut candidates: Vec<Candidate>,
+    now: f64,
+  ) -> Option<UserId> {
+    self.rank(user_id, &mut candidates, now);
+    candidates.first().map(|candidate| candidate.discord_id)
+  }
+}
+
+impl Default for Ranking {
+  /// Ties are broken differently each time the bot starts
+  fn default() -> Self {
+    let now = SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap_or_default();
+
+    Self::new(now.as_secs() ^ u64::from(now.subsec_nanos()))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const NOW: f64 = 1_000_000_000.0;
+
+  const USER: UserId = UserId(1);
+
+  fn candidate(discord_id: u64) -> Candidate {
+    Candidate {
+      discord_id:     UserId(discord_id),
+      accepted:       false,
+      exposure:       0.0,
+      last_shown_at:  None,
+      last_active_at: Some(NOW),
+      shared_tags:    0,
+      compatibility:  None,
+    }
+  }
+
+  fn ranked(seed: u64, mut candidates: Vec<Candidate>) -> Vec<u64> {
+    Ranking::new(seed).rank(USER, &mut candidates, NOW);
+    candidates
+      .iter()
+      .map(|candidate| candidate.discord_id.0)
+      .collect()
+  }
+
+  #[test]
+  fn accepted_candidates_come_first() {
+    let overexposed = Candidate {
+      accepted: true,
+      exposure: 100.0,
+      last_shown_at: Some(NOW),
+      last_active_at: None,
+      ..candidate(2)
+    };
+
+    assert_eq!(ranked(0, vec![candidate(3), overexposed]), &[2, 3]);
+  }
+
+  #[test]
+  fn less_exposed_candidates_come_first() {
+    let exposed = Candidate {
+      exposure: 5.0,
+      last_shown_at: Some(NOW - 2.0 * Ranking::DAY),
+      ..candidate(2)
+    };
+
+    assert_eq!(ranked(0, vec![exposed, candidate(3)]), &[3, 2]);
+  }
+
+  #[test]
+  fn recently_shown_candidates_come_last() {
+    let recent = Candidate {
+      exposure: 1.0,
+      last_shown_at: Some(NOW - 60.0),
+      ..candidate(2)
+    };
+
+    let earlier = Candidate {
+      exposure: 1.0,
+      last_shown_at: Some(NOW - 2.0 * Ranking::DAY),
+      ..candidate(3)
+    };
+
+    assert_eq!(ranked(0, vec![recent, earlier]), &[3, 2]);
+  }
+
+  #[test]
+  fn recently_active_candidates_come_first() {
+    let inactive = Candidate {
+      last_active_at: Some(NOW - 30.0 * Ranking::DAY),
+      ..candidate(2)
+    };
+
+    assert_eq!(ranked(0, vec![inactive, candidate(3)]), &[3, 2]);
+  }
+
+  #[test]
+  fn candidates_with_more_shared_tags_come_first() {
+    let one = Candidate {
+      shared_tags: 1,
+      ..candidate(2)
+    };
+
+    let three = Candidate {
+      shared_tags: 3,
+      ..candidate(3)
+    };
+
+    assert_eq!(ranked(0, vec![candidate(4), one, three]), &[3, 2, 4]);
+  }
+
+  #[test]
+  fn more_compatible_candidates_come_first() {
+    let compatible = Candidate {
+      compatibility: Some(90),
+      ..candidate(2)
+    };
+
+    let incompatible = Candidate {
+      compatibility: Some(10),
+      ..candidate(3)
+    };
+
+    assert_eq!(ranked(0, vec![incompatible, candidate(4), compatible]), &[
+      2, 4, 3
+    ]);
+  }
+
+  #[test]
+  fn exposure_decays() {
+    let decayed = |exposure, last_shown_at| Ranking::decayed_exposure(exposure, last_shown_at, NOW);
+
+    assert!((decayed(4.0, Some(NOW - 7.0 * Ranking::DAY)) - 2.0).abs() < 1e-9);
+    assert!((decayed(4.0, Some(NOW)) - 4.0).abs() < 1e-9);
+    assert!(decayed(0.0, None).abs() < 1e-9);
+  }
+
+  #[test]
+  fn ties_are_broken_by_seed() {
+    let candidates = (2..12).map(candidate).collect::<Vec<Candidate>>();
+
+    assert_eq!(ranked(0, candidates.clone()), &[
+      10, 5, 2, 7, 3, 6, 11, 4, 8, 9
+    ]);
+    assert_eq!(ranked(0, candidates.clone()), ranked(0, candidates.clone()));
+    assert_ne!(ranked(0, candidates.clone()), ranked(1, candidates));
+  }
+
+  #[test]
+  fn choose() {
+    assert_eq!(Ranking::new(0).choose(USER, Vec::new(), NOW), None);
+    assert_eq!(
+      Ranking::new(0).choose(USER, vec![candidate(2)], NOW),
+      Some(UserId(2))
+    );
+  }
+}
@@ -0,0 +1,1414 @@
+use crate::common::*;
+
+use sqlx::{
+  sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow},
+  Row, Sqlite, SqlitePool,
+};
+
+type Transaction = sqlx::Transaction<'static, Sqlite>;
+
+/// Queries mirror those of `PostgresStore`, but aren't checked at compile
+/// time, since `sqlx::query!` can only check against one database
+#[derive(Debug)]
+pub(crate) struct SqliteStore {
+  pool: SqlitePool,
+}
+
+impl SqliteStore {
+  pub(crate) async fn connect(url: &str, pool_options: PoolOptions) -> Result<Self> {
+    let options = SqliteConnectOptions::from_str(url)?
+      .create_if_missing(true)
+      .foreign_keys(true)
+      .journal_mode(SqliteJournalMode::Wal);
+
+    let mut sqlite_pool_options = SqlitePoolOptions::new()
+      .connect_timeout(pool_options.connect_timeout)
+      .max_connections(pool_options.max_connections);
+
+    // Every connection to an in-memory database gets its own database
+    if url.contains(":memory:") {
+      sqlite_pool_options = sqlite_pool_options
+        .max_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None);
+    }
+
+    let pool = sqlite_pool_options.connect_with(options).await?;
+
+    sqlx::migrate!("./migrations-sqlite").run(&pool).await?;
+
+    Ok(Self { pool })
+  }
+
+  fn now() -> f64 {
+    SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_secs_f64()
+  }
+
+  fn prompt_message(row: &SqliteRow) -> Result<PromptMessage> {
+    Ok(PromptMessage {
+      prompt:     Prompt::load((row.try_get("discriminant")?, row.try_get("payload")?))?,
+      message_id: MessageId::load(row.try_get("message_id")?).unwrap_infallible(),
+    })
+  }
+}
+
+#[async_trait]
+impl Store for SqliteStore {
+  async fn begin(&self) -> Result<Box<dyn StoreTransaction>> {
+    Ok(Box::new(self.pool.begin().await?))
+  }
+
+  async fn prompt_messages(&self) -> Result<Vec<(UserId, PromptMessage)>> {
+    sqlx::query(
+      "SELECT
+        recipient_discord_id, discriminant, payload, message_id
+      FROM
+        prompts
+      ORDER BY
+        message_id, recipient_discord_id",
+    )
+    .fetch_all(&self.pool)
+    .await?
+    .iter()
+    .map(|row| {
+      Ok((
+        UserId::load(row.try_get("recipient_discord_id")?).unwrap_infallible(),
+        Self::prompt_message(row)?,
+      ))
+    })
+    .collect()
+  }
+
+  async fn user_count(&self) -> Result<u64> {
+    let count: i64 = sqlx::query("SELECT COUNT(*) AS count FROM users")
+      .fetch_one(&self.pool)
+      .await?
+      .try_get("count")?;
+
+    Ok(u64::load(count).unwrap_infallible())
+  }
+
+  async fn intern(&self, namespace: &str, external_id: &str) -> Result<u64> {
+    sqlx::query(
+      "INSERT INTO external_ids(namespace, external_id) VALUES(?1, ?2)
+      ON CONFLICT (namespace, external_id) DO NOTHING",
+    )
+    .bind(namespace)
+    .bind(external_id)
+    .execute(&self.pool)
+    .await?;
+
+    let id: i64 =
+      sqlx::query("SELECT id FROM external_ids WHERE namespace = ?1 AND external_id = ?2")
+        .bind(namespace)
+        .bind(external_id)
+        .fetch_one(&self.pool)
+        .await?
+        .try_get("id")?;
+
+    Ok(u64::load(id).unwrap_infallible())
+  }
+
+  async fn external_id(&self, namespace: &str, id: u64) -> Result<Option<String>> {
+    let row = sqlx::query("SELECT external_id FROM external_ids WHERE namespace = ?1 AND id = ?2")
+      .bind(namespace)
+      .bind(id.store())
+      .fetch_optional(&self.pool)
+      .await?;
+
+    Ok(row.map(|row| row.try_get("external_id")).transpose()?)
+  }
+
+  async fn prune_events(&self, before: f64) -> Result<u64> {
+    Ok(
+      sqlx::query("DELETE FROM processed_events WHERE processed_at < ?1")
+        .bind(before)
+        .execute(&self.pool)
+        .await?
+        .rows_affected(),
+    )
+  }
+
+  async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool> {
+    let mut tx = self.pool.begin().await?;
+
+    let now = Self::now();
+
+    let current = sqlx::query("SELECT holder, expires_at, acquired_at FROM leases WHERE name = ?1")
+      .bind(name)
+      .fetch_optional(&mut tx)
+      .await?;
+
+    let acquired_at = match current {
+      None => now,
+      Some(lease) => {
+        let current_holder: String = lease.try_get("holder")?;
+
+        if current_holder == holder {
+          lease.try_get("acquired_at")?
+        } else if lease.try_get::<f64, _>("expires_at")? < now {
+          now
+        } else {
+          return Ok(false);
+        }
+      },
+    };
+
+    sqlx::query(
+      "INSERT INTO leases
+        (name, holder, expires_at, acquired_at)
+      VALUES
+        (?1, ?2, ?3, ?4)
+      ON CONFLICT (name) DO UPDATE SET
+        holder = ?2,
+        expires_at = ?3,
+        acquired_at = ?4",
+    )
+    .bind(name)
+    .bind(holder)
+    .bind(now + duration.as_secs_f64())
+    .bind(acquired_at)
+    .execute(&mut tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+  }
+
+  async fn lease_holder(&self, name: &str) -> Result<Option<String>> {
+    let row = sqlx::query("SELECT holder FROM leases WHERE name = ?1")
+      .bind(name)
+      .fetch_optional(&self.pool)
+      .await?;
+
+    Ok(row.map(|row| row.try_get("holder")).transpose()?)
+  }
+
+  async fn live_lease_holders(&self, prefix: &str) -> Result<Vec<String>> {
+    let pattern = format!("{}%", prefix);
+
+    let rows = sqlx::query(
+      "SELECT
+        holder
+      FROM
+        leases
+      WHERE
+        name LIKE ?1 AND expires_at > ?2
+      ORDER BY
+        acquired_at, holder",
+    )
+    .bind(pattern)
+    .bind(Self::now())
+    .fetch_all(&self.pool)
+    .await?;
+
+    Ok(
+      rows
+        .iter()
+        .map(|row| row.try_get("holder"))
+        .collect::<Result<Vec<String>, sqlx::Error>>()?,
+    )
+  }
+
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()> {
+    sqlx::query("DELETE FROM leases WHERE name = ?1 AND holder = ?2")
+      .bind(name)
+      .bind(holder)
+      .execute(&self.pool)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn release_leases(&self, holder: &str) -> Result<()> {
+    sqlx::query("DELETE FROM leases WHERE holder = ?1")
+      .bind(holder)
+      .execute(&self.pool)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn enqueue_interrupt(&self, shard: u64, interrupt: Interrupt) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO interrupts
+        (shard, user_id, candidate_id, channel_id)
+      VALUES
+        (?1, ?2, ?3, ?4)",
+    )
+    .bind(shard.store())
+    .bind(interrupt.user_id.store())
+    .bind(interrupt.candidate_id.store())
+    .bind(interrupt.channel_id.map(ChannelId::store))
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn record_error(
+    &self,
+    reference: &str,
+    variant: &str,
+    message: &str,
+    discord_id: Option<UserId>,
+  ) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO errors
+        (reference, variant, message, discord_id)
+      VALUES
+        (?1, ?2, ?3, ?4)",
+    )
+    .bind(reference)
+    .bind(variant)
+    .bind(message)
+    .bind(discord_id.map(UserId::store))
+    .execute(&self.pool)
+    .await?;
+
+    Ok(())
+  }
+}
+
+#[async_trait]
+impl StoreTransaction for Transaction {
+  async fn load_user(&mut self, discord_id: UserId) -> Result<Option<User>> {
+    let discord_id = discord_id.store();
+
+    let user = match sqlx::query("SELECT * FROM users WHERE discord_id = ?1")
+      .bind(discord_id)
+      .fetch_optional(&mut *self)
+      .await?
+    {
+      Some(user) => user,
+      None => return Ok(None),
+    };
+
+    let prompt = sqlx::query("SELECT * FROM prompts WHERE recipient_discord_id = ?1")
+      .bind(discord_id)
+      .fetch_optional(&mut *self)
+      .await?;
+
+    let prompt_message = prompt
+      .as_ref()
+      .map(SqliteStore::prompt_message)
+      .transpose()?;
+
+    let prompt_sent_at = prompt
+      .map(|row| row.try_get("sent_at"))
+      .transpose()?
+      .flatten();
+
+    let tags = if user.try_get("tagged")? {
+      Some(
+        sqlx::query(
+          "SELECT name FROM tags
+          WHERE id IN (SELECT tag_id FROM user_tags WHERE discord_id = ?1)
+          ORDER BY name",
+        )
+        .bind(discord_id)
+        .fetch_all(&mut *self)
+        .await?
+        .iter()
+        .map(|row| row.try_get("name"))
+        .collect::<Result<Vec<String>, sqlx::Error>>()?,
+      )
+    } else {
+      None
+    };
+
+    let age_bracket = |column: &str| {
+      user
+        .try_get::<Option<i64>, _>(column)?
+        .map(AgeBracket::load)
+        .transpose()
+    };
+
+    let ages = match (
+      age_bracket("min_age_bracket")?,
+      age_bracket("max_age_bracket")?,
+    ) {
+      (Some(min), Some(max)) => Some((min, max)),
+      _ => None,
+    };
+
+    let languages =
+      sqlx::query("SELECT language FROM user_languages WHERE discord_id = ?1 ORDER BY language")
+        .bind(discord_id)
+        .fetch_all(&mut *self)
+        .await?
+        .iter()
+        .map(|row| row.try_get("language"))
+        .collect::<Result<Vec<String>, sqlx::Error>>()?;
+
+    let connections =
+      Connection::from_mask(u64::load(user.try_get("connections")?).unwrap_infallible());
+
+    let preferences = Preferences {
+      age: age_bracket("age_bracket")?,
+      timezone_window: user
+        .try_get::<Option<i64>, _>("timezone_window")?
+        .map(|hours| u64::load(hours).unwrap_infallible()),
+      utc_offset: user.try_get("utc_offset")?,
+      ages,
+      connections,
+      languages,
+    };
+
+    let answers = self
+      .answers(UserId::load(discord_id).unwrap_infallible())
+      .await?;
+
+    Ok(Some(User {
+      id: u64::load(user.try_get("id")?).unwrap_infallible(),
+      discord_id: UserId::load(user.try_get("discord_id")?).unwrap_infallible(),
+      welcomed: user.try_get("welcomed")?,
+      bio: user.try_get("bio")?,
+      resurface_declined: user.try_get("resurface_declined")?,
+      paused: user.try_get("paused")?,
+      created_at: user.try_get("created_at")?,
+      updated_at: user.try_get("updated_at")?,
+      last_active_at: user.try_get("last_active_at")?,
+      prompt_message,
+      prompt_sent_at,
+      tags,
+      preferences,
+      answers,
+    }))
+  }
+
+  async fn insert_user(&mut self, discord_id: UserId, pool: &str, created_at: f64) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO users(discord_id, pool, created_at, updated_at) VALUES(?1, ?2, ?3, ?3)",
+    )
+    .bind(discord_id.store())
+    .bind(pool)
+    .bind(created_at)
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn record_event(&mut self, key: &str, processed_at: f64) -> Result<bool> {
+    let rows_affected = sqlx::query(
+      "INSERT INTO processed_events(key, processed_at) VALUES(?1, ?2)
+      ON CONFLICT (key) DO NOTHING",
+    )
+    .bind(key)
+    .bind(processed_at)
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    Ok(rows_affected == 1)
+  }
+
+  async fn welcome(&mut self, discord_id: UserId) -> Result<()> {
+    sqlx::query("UPDATE users SET welcomed = TRUE WHERE discord_id = ?1")
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_bio(&mut self, discord_id: UserId, text: &str, updated_at: f64) -> Result<()> {
+    sqlx::query("UPDATE users SET bio = ?1, bio_updated_at = ?2 WHERE discord_id = ?3")
+      .bind(text)
+      .bind(updated_at)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    sqlx::query("INSERT INTO bio_history(discord_id, bio, set_at) VALUES(?1, ?2, ?3)")
+      .bind(discord_id.store())
+      .bind(text)
+      .bind(updated_at)
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_resurface_declined(&mut self, discord_id: UserId, enabled: bool) -> Result<()> {
+    sqlx::query("UPDATE users SET resurface_declined = ?1 WHERE discord_id = ?2")
+      .bind(enabled)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_paused(&mut self, discord_id: UserId, paused: bool) -> Result<()> {
+    sqlx::query("UPDATE users SET paused = ?1 WHERE discord_id = ?2")
+      .bind(paused)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn delete_profile(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    for statement in &[
+      "DELETE FROM responses WHERE discord_id = ?1 OR candidate_id = ?1",
+      "DELETE FROM reports WHERE discord_id = ?1 OR reported_id = ?1",
+      "DELETE FROM deferrals WHERE discord_id = ?1 OR candidate_id = ?1",
+      "DELETE FROM candidate_queue WHERE discord_id = ?1 OR candidate_id = ?1",
+      "DELETE FROM answers WHERE discord_id = ?1",
+      "DELETE FROM user_tags WHERE discord_id = ?1",
+      "DELETE FROM user_languages WHERE discord_id = ?1",
+      "DELETE FROM bio_history WHERE discord_id = ?1",
+    ] {
+      sqlx::query(statement)
+        .bind(discord_id)
+        .execute(&mut *self)
+        .await?;
+    }
+
+    sqlx::query(
+      "UPDATE users SET
+        welcomed = FALSE,
+        bio = NULL,
+        bio_updated_at = NULL,
+        resurface_declined = FALSE,
+        paused = FALSE,
+        tagged = FALSE,
+        age_bracket = NULL,
+        min_age_bracket = NULL,
+        max_age_bracket = NULL,
+        utc_offset = NULL,
+        timezone_window = NULL,
+        connections = 0,
+        exposure = 0,
+        last_shown_at = NULL,
+        queue_exhausted = FALSE,
+        updated_at = ?1
+      WHERE
+        discord_id = ?2",
+    )
+    .bind(updated_at)
+    .bind(discord_id)
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn set_tags(&mut self, discord_id: UserId, tags: &[String]) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query("DELETE FROM user_tags WHERE discord_id = ?1")
+      .bind(discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    for tag in tags {
+      sqlx::query("INSERT INTO tags(name) VALUES(?1) ON CONFLICT (name) DO NOTHING")
+        .bind(tag)
+        .execute(&mut *self)
+        .await?;
+
+      sqlx::query(
+        "INSERT INTO user_tags(discord_id, tag_id) SELECT ?1, id FROM tags WHERE name = ?2",
+      )
+      .bind(discord_id)
+      .bind(tag)
+      .execute(&mut *self)
+      .await?;
+    }
+
+    sqlx::query("UPDATE users SET tagged = TRUE WHERE discord_id = ?1")
+      .bind(discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_preferences(&mut self, discord_id: UserId, preferences: &Preferences) -> Result<()> {
+    let discord_id = discord_id.store();
+
+    sqlx::query(
+      "UPDATE users
+      SET
+        age_bracket = ?1,
+        min_age_bracket = ?2,
+        max_age_bracket = ?3,
+        utc_offset = ?4,
+        timezone_window = ?5,
+        connections = ?6
+      WHERE
+        discord_id = ?7",
+    )
+    .bind(preferences.age.map(AgeBracket::store))
+    .bind(preferences.ages.map(|(min, _)| min.store()))
+    .bind(preferences.ages.map(|(_, max)| max.store()))
+    .bind(preferences.utc_offset)
+    .bind(preferences.timezone_window.map(u64::store))
+    .bind(Connection::mask(&preferences.connections).store())
+    .bind(discord_id)
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query("DELETE FROM user_languages WHERE discord_id = ?1")
+      .bind(discord_id)
+      .execute(&mut *self)
+      .await?;
+
+    for language in &preferences.languages {
+      sqlx::query("INSERT INTO user_languages(discord_id, language) VALUES(?1, ?2)")
+        .bind(discord_id)
+        .bind(language)
+        .execute(&mut *self)
+        .await?;
+    }
+
+    Ok(())
+  }
+
+  async fn set_answer(&mut self, discord_id: UserId, answer: &Answer) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO answers(discord_id, question, choice, accepted, importance)
+      VALUES(?1, ?2, ?3, ?4, ?5)
+      ON CONFLICT (discord_id, question) DO UPDATE SET
+        choice = excluded.choice,
+        accepted = excluded.accepted,
+        importance = excluded.importance",
+    )
+    .bind(discord_id.store())
+    .bind(answer.question.store())
+    .bind(answer.choice.store())
+    .bind(answer.accepted_mask().store())
+    .bind(answer.importance.store())
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn answers(&mut self, discord_id: UserId) -> Result<Vec<Answer>> {
+    sqlx::query(
+      "SELECT question, choice, accepted, importance FROM answers
+      WHERE discord_id = ?1
+      ORDER BY question",
+    )
+    .bind(discord_id.store())
+    .fetch_all(&mut *self)
+    .await?
+    .iter()
+    .map(|row| {
+      let accepted = u64::load(row.try_get("accepted")?).unwrap_infallible();
+
+      Ok(Answer {
+        question:   u64::load(row.try_get("question")?).unwrap_infallible(),
+        choice:     u64::load(row.try_get("choice")?).unwrap_infallible(),
+        accepted:   Answer::accepted_from_mask(accepted),
+        importance: Importance::load(row.try_get("importance")?)?,
+      })
+    })
+    .collect()
+  }
+
+  async fn queued_answers(&mut self, discord_id: UserId) -> Result<BTreeMap<UserId, Vec<Answer>>> {
+    let rows = sqlx::query(
+      "SELECT answers.discord_id, question, choice, accepted, importance
+      FROM candidate_queue
+      JOIN answers ON answers.discord_id = candidate_queue.candidate_id
+      WHERE candidate_queue.discord_id = ?1
+      ORDER BY answers.discord_id, question",
+    )
+    .bind(discord_id.store())
+    .fetch_all(&mut *self)
+    .await?;
+
+    let mut answers = BTreeMap::<UserId, Vec<Answer>>::new();
+
+    for row in rows {
+      let accepted = u64::load(row.try_get("accepted")?).unwrap_infallible();
+
+      answers
+        .entry(UserId::load(row.try_get("discord_id")?).unwrap_infallible())
+        .or_default()
+        .push(Answer {
+          question:   u64::load(row.try_get("question")?).unwrap_infallible(),
+          choice:     u64::load(row.try_get("choice")?).unwrap_infallible(),
+          accepted:   Answer::accepted_from_mask(accepted),
+          importance: Importance::load(row.try_get("importance")?)?,
+        });
+    }
+
+    Ok(answers)
+  }
+
+  async fn bio(&mut self, id: UserId) -> Result<String> {
+    let row = sqlx::query("SELECT bio FROM users WHERE discord_id = ?1")
+      .bind(id.store())
+      .fetch_optional(&mut *self)
+      .await?
+      .ok_or(Error::UserUnknown { id })?;
+
+    row
+      .try_get::<Option<String>, _>("bio")?
+      .ok_or(Error::UserMissingBio { id })
+  }
+
+  async fn bio_at(&mut self, discord_id: UserId, at: f64) -> Result<Option<String>> {
+    let row = sqlx::query(
+      "SELECT bio FROM bio_history
+      WHERE discord_id = ?1 AND set_at <= ?2
+      ORDER BY set_at DESC, id DESC
+      LIMIT 1",
+    )
+    .bind(discord_id.store())
+    .bind(at)
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| row.try_get("bio")).transpose()?)
+  }
+
+  async fn respond_to_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    response: bool,
+    responded_at: f64,
+  ) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO responses
+        (discord_id, candidate_id, response, dismissed, responded_at, created_at)
+      VALUES
+        (?1, ?2, ?3, FALSE, ?4, ?4)
+      ON CONFLICT (discord_id, candidate_id) DO UPDATE SET
+        response = ?3,
+        dismissed = FALSE,
+        super_like = FALSE,
+        responded_at = ?4",
+    )
+    .bind(user_id.store())
+    .bind(candidate_id.store())
+    .bind(response)
+    .bind(responded_at)
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn last_response(&mut self, user_id: UserId) -> Result<Option<(UserId, bool, f64)>> {
+    let row = sqlx::query(
+      "SELECT candidate_id, response, responded_at FROM responses
+      WHERE discord_id = ?1
+      ORDER BY responded_at DESC, id DESC
+      LIMIT 1",
+    )
+    .bind(user_id.store())
+    .fetch_optional(&mut *self)
+    .await?;
+
+    row
+      .map(|row| {
+        Ok((
+          UserId::load(row.try_get("candidate_id")?).unwrap_infallible(),
+          row.try_get("response")?,
+          row.try_get("responded_at")?,
+        ))
+      })
+      .transpose()
+  }
+
+  async fn delete_response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM responses WHERE discord_id = ?1 AND candidate_id = ?2")
+      .bind(user_id.store())
+      .bind(candidate_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    sqlx::query("DELETE FROM reports WHERE discord_id = ?1 AND reported_id = ?2")
+      .bind(user_id.store())
+      .bind(candidate_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn declined_with_new_bios(&mut self, user_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    sqlx::query(
+      "SELECT
+        responses.candidate_id, responses.responded_at
+      FROM
+        responses
+        JOIN users ON users.discord_id = responses.candidate_id
+      WHERE
+        responses.discord_id = ?1
+        AND
+        NOT responses.response
+        AND
+        users.bio_updated_at > responses.responded_at
+        AND
+        NOT EXISTS (
+          SELECT * FROM reports
+          WHERE discord_id = responses.discord_id AND reported_id = responses.candidate_id
+        )
+      ORDER BY
+        responses.id",
+    )
+    .bind(user_id.store())
+    .fetch_all(&mut *self)
+    .await?
+    .iter()
+    .map(|row| {
+      Ok((
+        UserId::load(row.try_get("candidate_id")?).unwrap_infallible(),
+        row.try_get("responded_at")?,
+      ))
+    })
+    .collect()
+  }
+
+  async fn dismiss_match(&mut self, user_id: UserId, match_id: UserId) -> Result<()> {
+    sqlx::query(
+      "UPDATE responses SET dismissed = TRUE WHERE discord_id = ?1 AND candidate_id = ?2",
+    )
+    .bind(user_id.store())
+    .bind(match_id.store())
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<Option<bool>> {
+    let row = sqlx::query(
+      "SELECT response FROM responses WHERE discord_id = ?1 AND candidate_id = ?2 LIMIT 1",
+    )
+    .bind(user_id.store())
+    .bind(candidate_id.store())
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(row.map(|row| row.try_get("response")).transpose()?)
+  }
+
+  async fn super_like(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()> {
+    sqlx::query(
+      "UPDATE responses SET super_like = TRUE WHERE discord_id = ?1 AND candidate_id = ?2",
+    )
+    .bind(user_id.store())
+    .bind(candidate_id.store())
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn super_liked(&mut self, user_id: UserId, candidate_id: UserId) -> Result<bool> {
+    let row = sqlx::query(
+      "SELECT super_like FROM responses WHERE discord_id = ?1 AND candidate_id = ?2 LIMIT 1",
+    )
+    .bind(user_id.store())
+    .bind(candidate_id.store())
+    .fetch_optional(&mut *self)
+    .await?;
+
+    Ok(
+      row
+        .map(|row| row.try_get("super_like"))
+        .transpose()?
+        .unwrap_or_default(),
+    )
+  }
+
+  async fn report(&mut self, user_id: UserId, reported_id: UserId) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO reports (discord_id, reported_id) VALUES (?1, ?2) ON CONFLICT DO NOTHING",
+    )
+    .bind(user_id.store())
+    .bind(reported_id.store())
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn defer_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    deferred_at: f64,
+  ) -> Result<()> {
+    sqlx::query(
+      "INSERT INTO deferrals(discord_id, candidate_id, deferred_at, created_at)
+      VALUES(?1, ?2, ?3, ?3)
+      ON CONFLICT (discord_id, candidate_id) DO UPDATE SET
+        deferred_at = excluded.deferred_at",
+    )
+    .bind(user_id.store())
+    .bind(candidate_id.store())
+    .bind(deferred_at)
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn deferrals(&mut self, discord_id: UserId) -> Result<Vec<(UserId, f64)>> {
+    sqlx::query(
+      "SELECT candidate_id, deferred_at FROM deferrals
+      WHERE discord_id = ?1
+      ORDER BY candidate_id",
+    )
+    .bind(discord_id.store())
+    .fetch_all(&mut *self)
+    .await?
+    .iter()
+    .map(|row| {
+      Ok((
+        UserId::load(row.try_get("candidate_id")?).unwrap_infallible(),
+        row.try_get("deferred_at")?,
+      ))
+    })
+    .collect()
+  }
+
+  async fn get_match(&mut self, discord_id: UserId) -> Result<Option<UserId>> {
+    let row = sqlx::query(
+      "SELECT
+        candidate_id
+      FROM
+        responses AS outer_responses
+      WHERE
+        discord_id = ?1
+        AND
+        response
+        AND
+        NOT dismissed
+        AND
+        EXISTS (
+          SELECT * FROM responses
+          WHERE
+            discord_id = outer_responses.candidate_id
+            AND
+            candidate_id = outer_responses.discord_id
+            AND
+            response
+        )
+      ORDER BY
+        id
+      LIMIT 1",
+    )
+    .bind(discord_id.store())
+    .fetch_optional(&mut *self)
+    .await?;
+
+    row
+      .map(|row| Ok(UserId::load(row.try_get("candidate_id")?).unwrap_infallible()))
+      .transpose()
+  }
+
+  async fn candidates(&mut self, discord_id: UserId) -> Result<Vec<Candidate>> {
+    sqlx::query(
+      "SELECT
+        potential_candidate.discord_id,
+        potential_candidate.exposure,
+        potential_candidate.last_shown_at,
+        potential_candidate.last_active_at,
+        EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = ?1 AND response
+        ) AS accepted,
+        (
+          SELECT COUNT(*) FROM user_tags
+          WHERE
+            discord_id = potential_candidate.discord_id
+            AND
+            tag_id IN (SELECT tag_id FROM user_tags WHERE discord_id = ?1)
+        ) AS shared_tags
+      FROM
+        candidate_queue
+        JOIN users AS potential_candidate
+          ON potential_candidate.discord_id = candidate_queue.candidate_id
+      WHERE
+        candidate_queue.discord_id = ?1
+        AND
+        potential_candidate.welcomed = TRUE
+        AND
+        potential_candidate.bio IS NOT NULL
+        AND
+        NOT potential_candidate.paused
+        AND
+        potential_candidate.discord_id != ?1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = ?1 AND NOT response
+        )
+        AND
+        EXISTS (
+          SELECT * FROM prompts
+          WHERE
+            recipient_discord_id = potential_candidate.discord_id AND discriminant = ?2
+        )
+        AND
+        potential_candidate.pool = (SELECT pool FROM users WHERE discord_id = ?1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        potential_candidate.id",
+    )
+    .bind(discord_id.store())
+    .bind(PromptDiscriminant::Quiescent.store())
+    .fetch_all(&mut *self)
+    .await?
+    .iter()
+    .map(|row| {
+      Ok(Candidate {
+        discord_id:     UserId::load(row.try_get("discord_id")?).unwrap_infallible(),
+        accepted:       row.try_get("accepted")?,
+        exposure:       row.try_get("exposure")?,
+        last_shown_at:  row.try_get("last_shown_at")?,
+        last_active_at: row.try_get("last_active_at")?,
+        shared_tags:    u64::load(row.try_get("shared_tags")?).unwrap_infallible(),
+        compatibility:  None,
+      })
+    })
+    .collect()
+  }
+
+  async fn refill_candidate_queue(&mut self, discord_id: UserId, limit: u64) -> Result<u64> {
+    let accepted = sqlx::query(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        ?1, discord_id
+      FROM
+        users AS potential_candidate
+      WHERE
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        NOT paused
+        AND
+        discord_id != ?1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = ?1 AND response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = ?1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM candidate_queue
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        id
+      LIMIT ?2",
+    )
+    .bind(discord_id.store())
+    .bind(limit.store())
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    let others = sqlx::query(
+      "INSERT INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        ?1, discord_id
+      FROM
+        users AS potential_candidate
+      WHERE
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        NOT paused
+        AND
+        discord_id != ?1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = potential_candidate.discord_id AND candidate_id = ?1 AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = ?1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM candidate_queue
+          WHERE discord_id = ?1 AND candidate_id = potential_candidate.discord_id
+        )
+      ORDER BY
+        exposure, id
+      LIMIT ?2",
+    )
+    .bind(discord_id.store())
+    .bind(limit.store())
+    .execute(&mut *self)
+    .await?
+    .rows_affected();
+
+    Ok(accepted + others)
+  }
+
+  async fn queue_exhausted(&mut self, discord_id: UserId) -> Result<bool> {
+    Ok(
+      sqlx::query("SELECT queue_exhausted FROM users WHERE discord_id = ?1")
+        .bind(discord_id.store())
+        .fetch_optional(&mut *self)
+        .await?
+        .map(|row| row.try_get("queue_exhausted"))
+        .transpose()?
+        .unwrap_or(false),
+    )
+  }
+
+  async fn set_queue_exhausted(&mut self, discord_id: UserId, exhausted: bool) -> Result<()> {
+    sqlx::query("UPDATE users SET queue_exhausted = ?1 WHERE discord_id = ?2")
+      .bind(exhausted)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn enqueue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO candidate_queue (discord_id, candidate_id) VALUES (?1, ?2)")
+      .bind(discord_id.store())
+      .bind(candidate_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn dequeue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM candidate_queue WHERE discord_id = ?1 AND candidate_id = ?2")
+      .bind(discord_id.store())
+      .bind(candidate_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn offer_candidate(&mut self, candidate_id: UserId, limit: u64) -> Result<()> {
+    sqlx::query(
+      "INSERT OR IGNORE INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        discord_id, ?1
+      FROM
+        users
+      WHERE
+        queue_exhausted
+        AND
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        discord_id != ?1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = users.discord_id AND candidate_id = ?1
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = ?1 AND candidate_id = users.discord_id AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = ?1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = users.discord_id AND candidate_id = ?1
+        )",
+    )
+    .bind(candidate_id.store())
+    .execute(&mut *self)
+    .await?;
+
+    sqlx::query(
+      "INSERT OR IGNORE INTO candidate_queue (discord_id, candidate_id)
+      SELECT
+        discord_id, ?1
+      FROM
+        users
+      WHERE
+        NOT queue_exhausted
+        AND
+        welcomed = TRUE
+        AND
+        bio IS NOT NULL
+        AND
+        discord_id != ?1
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = users.discord_id AND candidate_id = ?1
+        )
+        AND
+        NOT EXISTS (
+          SELECT * FROM responses
+          WHERE discord_id = ?1 AND candidate_id = users.discord_id AND NOT response
+        )
+        AND
+        pool = (SELECT pool FROM users WHERE discord_id = ?1)
+        AND
+        EXISTS (
+          SELECT * FROM compatible_users
+          WHERE discord_id = users.discord_id AND candidate_id = ?1
+        )
+      ORDER BY
+        last_active_at DESC NULLS LAST, id
+      LIMIT ?2",
+    )
+    .bind(candidate_id.store())
+    .bind(limit.store())
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn withdraw_candidate(&mut self, candidate_id: UserId) -> Result<()> {
+    sqlx::query("DELETE FROM candidate_queue WHERE candidate_id = ?1")
+      .bind(candidate_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn exposure(&mut self, id: UserId) -> Result<(f64, Option<f64>)> {
+    let row = sqlx::query("SELECT exposure, last_shown_at FROM users WHERE discord_id = ?1")
+      .bind(id.store())
+      .fetch_optional(&mut *self)
+      .await?
+      .ok_or(Error::UserUnknown { id })?;
+
+    Ok((row.try_get("exposure")?, row.try_get("last_shown_at")?))
+  }
+
+  async fn set_exposure(&mut self, discord_id: UserId, exposure: f64, shown_at: f64) -> Result<()> {
+    sqlx::query("UPDATE users SET exposure = ?1, last_shown_at = ?2 WHERE discord_id = ?3")
+      .bind(exposure)
+      .bind(shown_at)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_last_active(&mut self, discord_id: UserId, active_at: f64) -> Result<()> {
+    sqlx::query("UPDATE users SET last_active_at = ?1 WHERE discord_id = ?2")
+      .bind(active_at)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn set_updated_at(&mut self, discord_id: UserId, updated_at: f64) -> Result<()> {
+    sqlx::query("UPDATE users SET updated_at = ?1 WHERE discord_id = ?2")
+      .bind(updated_at)
+      .bind(discord_id.store())
+      .execute(&mut *self)
+      .await?;
+
+    Ok(())
+  }
+
+  async fn prompt(&mut self, discord_id: UserId) -> Result<Option<Prompt>> {
+    sqlx::query("SELECT discriminant, payload FROM prompts WHERE recipient_discord_id = ?1")
+      .bind(discord_id.store())
+      .fetch_optional(&mut *self)
+      .await?
+      .map(|row| Prompt::load((row.try_get("discriminant")?, row.try_get("payload")?)))
+      .transpose()
+  }
+
+  async fn shown_to(&mut self, discord_id: UserId) -> Result<Vec<UserId>> {
+    sqlx::query(
+      "SELECT recipient_discord_id FROM prompts
+      WHERE payload = ?1 AND discriminant IN (?2, ?3)
+      ORDER BY recipient_discord_id",
+    )
+    .bind(discord_id.store())
+    .bind(PromptDiscriminant::Candidate.store())
+    .bind(PromptDiscriminant::Match.store())
+    .fetch_all(&mut *self)
+    .await?
+    .iter()
+    .map(|row| Ok(UserId::load(row.try_get("recipient_discord_id")?).unwrap_infallible()))
+    .collect()
+  }
+
+  async fn set_prompt(
+    &mut self,
+    discord_id: UserId,
+    prompt_message: PromptMessage,
+    sent_at: f64,
+  ) -> Result<()> {
+    let (discriminant, payload) = prompt_message.prompt.store();
+
+    sqlx::query(
+      "INSERT INTO prompts
+        (discriminant, payload, message_id, recipient_discord_id, sent_at, created_at)
+      VALUES
+        (?1, ?2, ?3, ?4, ?5, ?5)
+      ON CONFLICT (recipient_discord_id) DO UPDATE SET
+        discriminant = ?1,
+        payload = ?2,
+        message_id = ?3,
+        sent_at = ?5",
+    )
+    .bind(discriminant)
+    .bind(payload)
+    .bind(prompt_message.message_id.store())
+    .bind(discord_id.store())
+    .bind(sent_at)
+    .execute(&mut *self)
+    .await?;
+
+    Ok(())
+  }
+
+  async fn dequeue_interrupt(&mut self, shards: Range<u64>) -> Result<Option<Interrupt>> {
+    let row = sqlx::query(
+      "SELECT
+        id, user_id, candidate_id, channel_id
+      FROM
+        interrupts
+      WHERE
+        shard >= ?1 AND shard < ?2
+      ORDER BY
+        id
+      LIMIT 1",
+    )
+    .bind(shards.start.store())
+    .bind(shards.end.store())
+    .fetch_optional(&mut *self)
+    .await?;
+
+    let row = match row {
+      Some(row) => row,
+      None => return Ok(None),
+    };
+
+    sqlx::query("DELETE FROM interrupts WHERE id = ?1")
+      .bind(row.try_get::<i64, _>("id")?)
+      .execute(&mut *self)
+      .await?;
+
+    Ok(Some(Interrupt {
+      user_id:      UserId::load(row.try_get("user_id")?).unwrap_infallible(),
+      candidate_id: UserId::load(row.try_get("candidate_id")?).unwrap_infallible(),
+      channel_id:   row
+        .try_get::<Option<i64>, _>("channel_id")?
+        .map(|id| ChannelId::load(id).unwrap_infallible()),
+    }))
+  }
+
+  async fn commit(self: Box<Self>) -> Result<()> {
+    sqlx::Transaction::commit(*self).await?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn foreign_keys_are_enforced() {
+    let store = SqliteStore::connect("sqlite::memory:", PoolOptions::default())
+      .await
+      .unwrap();
+
+    let mut tx = store.pool.begin().await.unwrap();
+
+    tx.insert_user(UserId(100), Db::DEFAULT_POOL, 0.0)
+      .await
+      .unwrap();
+
+    let error = sqlx::query(
+      "INSERT INTO responses
+        (discord_id, candidate_id, response, dismissed)
+      VALUES
+        (100, 1, TRUE, FALSE)",
+    )
+    .execute(&mut tx)
+    .await
+    .unwrap_err();
+
+    guard_unwrap!(let sqlx::Error::Database(error) = error);
+
+    assert_eq!(error.message(), "FOREIGN KEY constraint failed");
+  }
+
+  #[tokio::test(flavor = "multi_thread")]
+  async fn record_error() {
+    let store = SqliteStore::connect("sqlite::memory:", PoolOptions::default())
+      .await
+      .unwrap();
+
+    store
+      .record_error("ABCD2345", "Http", "Http error: 500", Some(UserId(100)))
+      .await
+      .unwrap();
+
+    let row = sqlx::query("SELECT variant, message, discord_id FROM errors WHERE reference = ?1")
+      .bind("ABCD2345")
+      .fetch_one(&store.pool)
+      .await
+      .unwrap();
+
+    assert_eq!(row.get::<String, _>("variant"), "Http");
+    assert_eq!(row.get::<String, _>("message"), "Http error: 500");
+    assert_eq!(row.get::<Option<i64>, _>("discord_id"), Some(100));
+  }
+}
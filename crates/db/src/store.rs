@@ -0,0 +1,41 @@
+use crate::common::*;
+
+/// Matching logic lives in `Db`, so that it is shared by all backends
+#[async_trait]
+pub trait Store: Debug + Send + Sync {
+  async fn begin(&self) -> Result<Box<dyn StoreTransaction>>;
+
+  async fn prompt_messages(&self) -> Result<Vec<(UserId, PromptMessage)>>;
+
+  async fn user_count(&self) -> Result<u64>;
+
+  /// IDs are positive and unique across namespaces
+  async fn intern(&self, namespace: &str, external_id: &str) -> Result<u64>;
+
+  async fn external_id(&self, namespace: &str, id: u64) -> Result<Option<String>>;
+
+  async fn prune_events(&self, before: f64) -> Result<u64>;
+
+  /// Returns `false` if the lease is held by another holder and has not
+  /// expired
+  async fn acquire_lease(&self, name: &str, holder: &str, duration: Duration) -> Result<bool>;
+
+  async fn lease_holder(&self, name: &str) -> Result<Option<String>>;
+
+  /// Ordered by when they acquired their lease
+  async fn live_lease_holders(&self, prefix: &str) -> Result<Vec<String>>;
+
+  async fn release_lease(&self, name: &str, holder: &str) -> Result<()>;
+
+  async fn release_leases(&self, holder: &str) -> Result<()>;
+
+  async fn enqueue_interrupt(&self, shard: u64, interrupt: Interrupt) -> Result<()>;
+
+  async fn record_error(
+    &self,
+    reference: &str,
+    variant: &str,
+    message: &str,
+    discord_id: Option<UserId>,
+  ) -> Result<()>;
+}
@@ -0,0 +1,125 @@
+use crate::common::*;
+
+/// Dropping a transaction without calling `commit` rolls it back
+#[async_trait]
+pub trait StoreTransaction: Send {
+  async fn load_user(&mut self, discord_id: UserId) -> Result<Option<User>>;
+
+  async fn insert_user(&mut self, discord_id: UserId, pool: &str, created_at: f64) -> Result<()>;
+
+  async fn record_event(&mut self, key: &str, processed_at: f64) -> Result<bool>;
+
+  async fn welcome(&mut self, discord_id: UserId) -> Result<()>;
+
+  async fn set_bio(&mut self, discord_id: UserId, text: &str, updated_at: f64) -> Result<()>;
+
+  async fn set_resurface_declined(&mut self, discord_id: UserId, enabled: bool) -> Result<()>;
+
+  async fn set_paused(&mut self, discord_id: UserId, paused: bool) -> Result<()>;
+
+  /// Leaves `discord_id` as if they had just been inserted, but keeps their
+  /// creation time
+  async fn delete_profile(&mut self, discord_id: UserId, updated_at: f64) -> Result<()>;
+
+  async fn set_tags(&mut self, discord_id: UserId, tags: &[String]) -> Result<()>;
+
+  async fn set_preferences(&mut self, discord_id: UserId, preferences: &Preferences) -> Result<()>;
+
+  async fn set_answer(&mut self, discord_id: UserId, answer: &Answer) -> Result<()>;
+
+  /// In question order
+  async fn answers(&mut self, discord_id: UserId) -> Result<Vec<Answer>>;
+
+  /// Answers of queued candidates who have answered any questions
+  async fn queued_answers(&mut self, discord_id: UserId) -> Result<BTreeMap<UserId, Vec<Answer>>>;
+
+  async fn bio(&mut self, discord_id: UserId) -> Result<String>;
+
+  async fn bio_at(&mut self, discord_id: UserId, at: f64) -> Result<Option<String>>;
+
+  async fn respond_to_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    response: bool,
+    responded_at: f64,
+  ) -> Result<()>;
+
+  /// The candidate, whether they were accepted, and when
+  async fn last_response(&mut self, user_id: UserId) -> Result<Option<(UserId, bool, f64)>>;
+
+  async fn delete_response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()>;
+
+  /// Unreported declines of candidates who have set their bio since
+  async fn declined_with_new_bios(&mut self, user_id: UserId) -> Result<Vec<(UserId, f64)>>;
+
+  async fn dismiss_match(&mut self, user_id: UserId, match_id: UserId) -> Result<()>;
+
+  async fn response(&mut self, user_id: UserId, candidate_id: UserId) -> Result<Option<bool>>;
+
+  async fn super_like(&mut self, user_id: UserId, candidate_id: UserId) -> Result<()>;
+
+  async fn super_liked(&mut self, user_id: UserId, candidate_id: UserId) -> Result<bool>;
+
+  async fn report(&mut self, user_id: UserId, reported_id: UserId) -> Result<()>;
+
+  async fn defer_candidate(
+    &mut self,
+    user_id: UserId,
+    candidate_id: UserId,
+    deferred_at: f64,
+  ) -> Result<()>;
+
+  async fn deferrals(&mut self, discord_id: UserId) -> Result<Vec<(UserId, f64)>>;
+
+  /// An undismissed mutual acceptance
+  async fn get_match(&mut self, discord_id: UserId) -> Result<Option<UserId>>;
+
+  /// Queued users who are quiescent, unpaused, onboarded, in the same pool,
+  /// compatible, and haven't been responded to by or declined `discord_id`
+  async fn candidates(&mut self, discord_id: UserId) -> Result<Vec<Candidate>>;
+
+  /// Adds up to `limit` users who accepted `discord_id`, and up to `limit`
+  /// others, least exposed first. Returns how many were added.
+  async fn refill_candidate_queue(&mut self, discord_id: UserId, limit: u64) -> Result<u64>;
+
+  async fn queue_exhausted(&mut self, discord_id: UserId) -> Result<bool>;
+
+  async fn set_queue_exhausted(&mut self, discord_id: UserId, exhausted: bool) -> Result<()>;
+
+  async fn enqueue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()>;
+
+  async fn dequeue_candidate(&mut self, discord_id: UserId, candidate_id: UserId) -> Result<()>;
+
+  /// Adds `candidate_id` to every eligible exhausted queue, and up to
+  /// `limit` others, most recently active first
+  async fn offer_candidate(&mut self, candidate_id: UserId, limit: u64) -> Result<()>;
+
+  async fn withdraw_candidate(&mut self, candidate_id: UserId) -> Result<()>;
+
+  /// Decayed as of when they were last shown, and when that was
+  async fn exposure(&mut self, discord_id: UserId) -> Result<(f64, Option<f64>)>;
+
+  async fn set_exposure(&mut self, discord_id: UserId, exposure: f64, shown_at: f64) -> Result<()>;
+
+  async fn set_last_active(&mut self, discord_id: UserId, active_at: f64) -> Result<()>;
+
+  async fn set_updated_at(&mut self, discord_id: UserId, updated_at: f64) -> Result<()>;
+
+  async fn prompt(&mut self, discord_id: UserId) -> Result<Option<Prompt>>;
+
+  async fn shown_to(&mut self, discord_id: UserId) -> Result<Vec<UserId>>;
+
+  async fn set_prompt(
+    &mut self,
+    discord_id: UserId,
+    prompt_message: PromptMessage,
+    sent_at: f64,
+  ) -> Result<()>;
+
+  /// Other transactions wait to dequeue the interrupt until this one is
+  /// committed or rolled back
+  async fn dequeue_interrupt(&mut self, shards: Range<u64>) -> Result<Option<Interrupt>>;
+
+  async fn commit(self: Box<Self>) -> Result<()>;
+}
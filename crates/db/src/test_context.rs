@@ -0,0 +1,101 @@
+use crate::common::*;
+
+/// Runs the test once against each enabled backend
+#[macro_export]
+macro_rules! backend_test {
+  (async fn $name:ident($context:ident: TestContext) $body:block) => {
+    mod $name {
+      use super::*;
+
+      #[tokio::test(flavor = "multi_thread")]
+      async fn memory() {
+        let $context = TestContext::memory();
+        $body
+      }
+
+      #[cfg(feature = "postgres")]
+      #[tokio::test(flavor = "multi_thread")]
+      async fn postgres() {
+        let $context = TestContext::postgres().await;
+        $body
+      }
+
+      #[cfg(feature = "sqlite")]
+      #[tokio::test(flavor = "multi_thread")]
+      async fn sqlite() {
+        let $context = TestContext::sqlite().await;
+        $body
+      }
+    }
+  };
+}
+
+pub(crate) struct TestContext {
+  pub(crate) db:  Db,
+  /// `None` for the in-memory backend
+  pub(crate) url: Option<String>,
+  _tempdir:       Option<TempDir>,
+}
+
+impl TestContext {
+  fn name() -> String {
+    static TEST_DATABASE_NUMBER: AtomicUsize = AtomicUsize::new(0);
+
+    let test_database_number = TEST_DATABASE_NUMBER.fetch_add(1, Ordering::Relaxed);
+
+    format!(
+      "quwue-test-{}-{}",
+      SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap()
+        .as_millis(),
+      test_database_number,
+    )
+  }
+
+  pub(crate) fn memory() -> Self {
+    Self {
+      db:       Db::memory(),
+      url:      None,
+      _tempdir: None,
+    }
+  }
+
+  #[cfg(feature = "postgres")]
+  pub(crate) fn postgres_url() -> String {
+    db_url::db_url(&Self::name())
+  }
+
+  #[cfg(feature = "postgres")]
+  pub(crate) async fn postgres() -> Self {
+    Self::connect(Self::postgres_url(), None).await
+  }
+
+  #[cfg(feature = "sqlite")]
+  pub(crate) async fn sqlite() -> Self {
+    let tempdir = tempfile::tempdir().unwrap();
+
+    let url = format!(
+      "sqlite://{}",
+      tempdir
+        .path()
+        .join(format!("{}.sqlite", Self::name()))
+        .display()
+    );
+
+    Self::connect(url, Some(tempdir)).await
+  }
+
+  async fn connect(url: String, tempdir: Option<TempDir>) -> Self {
+    let db = Db::connect_url(&url, PoolOptions::default())
+      .await
+      .unwrap()
+      .with_ranking(Ranking::new(0));
+
+    Self {
+      _tempdir: tempdir,
+      url: Some(url),
+      db,
+    }
+  }
+}
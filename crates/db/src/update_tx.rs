@@ -1,28 +1,47 @@
 use crate::common::*;
 
-pub struct UpdateTx<'a> {
-  pub(crate) prompt:  Prompt,
-  pub(crate) tx:      Transaction<'a>,
-  pub(crate) user_id: UserId,
+pub struct UpdateTx {
+  pub(crate) clock:     Clock,
+  pub(crate) prompt:    Prompt,
+  pub(crate) retracted: Vec<UserId>,
+  pub(crate) tx:        Box<dyn StoreTransaction>,
+  pub(crate) user_id:   UserId,
 }
 
-impl<'a> UpdateTx<'a> {
+impl UpdateTx {
   pub fn prompt(&self) -> Prompt {
     self.prompt
   }
 
-  pub async fn commit(self, prompt_message_id: MessageId) -> Result<()> {
+  /// Users who should stop being shown the user
+  pub fn retracted(&self) -> Vec<UserId> {
+    self.retracted.clone()
+  }
+
+  pub async fn commit(mut self, prompt_message_id: MessageId) -> Result<()> {
     let prompt_message = PromptMessage {
       prompt:     self.prompt,
       message_id: prompt_message_id,
     };
 
-    Db::commit(self.tx, self.user_id, prompt_message).await?;
+    let sent_at = self.clock.now();
+
+    self
+      .tx
+      .set_prompt(self.user_id, prompt_message, sent_at)
+      .await?;
+
+    self.tx.commit().await?;
 
     Ok(())
   }
 
-  pub fn inner_transaction(&mut self) -> &mut Transaction<'a> {
-    &mut self.tx
+  /// Leaves the user's current prompt message in place
+  pub async fn commit_unsent(self) -> Result<()> {
+    self.tx.commit().await
+  }
+
+  pub fn inner_transaction(&mut self) -> &mut dyn StoreTransaction {
+    self.tx.as_mut()
   }
 }
@@ -28,8 +28,20 @@ impl Value for Prompt {
 
   fn store(self) -> Self::Storage {
     let payload = match self {
-      Self::Bio | Self::Quiescent | Self::Welcome => None,
+      Self::Bio
+      | Self::Tags
+      | Self::Quiescent
+      | Self::Welcome
+      | Self::Age
+      | Self::AgeRange
+      | Self::UtcOffset
+      | Self::TimezoneWindow
+      | Self::Languages
+      | Self::Connections => None,
       Self::Candidate { id } | Self::Match { id } => Some(id.store()),
+      Self::QuestionChoice { question }
+      | Self::QuestionAccepted { question }
+      | Self::QuestionImportance { question } => Some(question.store()),
     };
 
     ((self.discriminant() as u64).store(), payload)
@@ -41,20 +53,41 @@ impl Value for Prompt {
     let discriminant = PromptDiscriminant::load(discriminant)?;
 
     match (discriminant, payload) {
+      (Age, None) => Ok(Self::Age),
+      (AgeRange, None) => Ok(Self::AgeRange),
       (Bio, None) => Ok(Self::Bio),
       (Candidate, Some(id)) => Ok(Self::Candidate {
         id: UserId::load(id).unwrap_infallible(),
       }),
+      (Connections, None) => Ok(Self::Connections),
+      (Languages, None) => Ok(Self::Languages),
       (Match, Some(id)) => Ok(Self::Match {
         id: UserId::load(id).unwrap_infallible(),
       }),
+      (QuestionAccepted, Some(question)) => Ok(Self::QuestionAccepted {
+        question: u64::load(question).unwrap_infallible(),
+      }),
+      (QuestionChoice, Some(question)) => Ok(Self::QuestionChoice {
+        question: u64::load(question).unwrap_infallible(),
+      }),
+      (QuestionImportance, Some(question)) => Ok(Self::QuestionImportance {
+        question: u64::load(question).unwrap_infallible(),
+      }),
       (Quiescent, None) => Ok(Self::Quiescent),
+      (Tags, None) => Ok(Self::Tags),
+      (TimezoneWindow, None) => Ok(Self::TimezoneWindow),
+      (UtcOffset, None) => Ok(Self::UtcOffset),
       (Welcome, None) => Ok(Self::Welcome),
-      (Bio | Quiescent | Welcome, Some(payload)) => Err(Error::PromptLoadSuperfluousPayload {
+      (
+        Age | AgeRange | Bio | Connections | Languages | Quiescent | Tags | TimezoneWindow
+        | UtcOffset | Welcome,
+        Some(payload),
+      ) => Err(Error::PromptLoadSuperfluousPayload {
         discriminant,
         payload,
       }),
-      (Candidate | Match, None) => Err(Error::PromptLoadMissingPayload { discriminant }),
+      (Candidate | Match | QuestionAccepted | QuestionChoice | QuestionImportance, None) =>
+        Err(Error::PromptLoadMissingPayload { discriminant }),
     }
   }
 }
@@ -76,6 +109,40 @@ impl Value for PromptDiscriminant {
   }
 }
 
+impl Value for AgeBracket {
+  type Err = Error;
+  type Storage = i64;
+
+  fn load(storage: Self::Storage) -> Result<Self, Self::Err> {
+    let storage = u64::load(storage).unwrap_infallible();
+
+    storage
+      .try_into()
+      .context(error::AgeBracketLoad { storage })
+  }
+
+  fn store(self) -> Self::Storage {
+    (self as u64).store()
+  }
+}
+
+impl Value for Importance {
+  type Err = Error;
+  type Storage = i64;
+
+  fn load(storage: Self::Storage) -> Result<Self, Self::Err> {
+    let storage = u64::load(storage).unwrap_infallible();
+
+    storage
+      .try_into()
+      .context(error::ImportanceLoad { storage })
+  }
+
+  fn store(self) -> Self::Storage {
+    (self as u64).store()
+  }
+}
+
 impl Value for UserId {
   type Err = Infallible;
   type Storage = i64;
@@ -101,3 +168,16 @@ impl Value for MessageId {
     Ok(MessageId(u64::load(storage).unwrap_infallible()))
   }
 }
+
+impl Value for ChannelId {
+  type Err = Infallible;
+  type Storage = i64;
+
+  fn store(self) -> Self::Storage {
+    self.0.store()
+  }
+
+  fn load(storage: Self::Storage) -> Result<Self, Self::Err> {
+    Ok(ChannelId(u64::load(storage).unwrap_infallible()))
+  }
+}
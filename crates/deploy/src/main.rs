@@ -1,9 +1,21 @@
-use {cradle::prelude::*, std::process::Command, structopt::StructOpt};
+use {
+  cradle::prelude::*,
+  std::{path::PathBuf, process::Command},
+  structopt::StructOpt,
+};
 
 #[derive(StructOpt)]
 struct Arguments {
   #[structopt(long)]
-  host: String,
+  host:             String,
+  #[structopt(
+    long,
+    help = "Use password in <db-password-file> to connect to the database, instead of `trust` \
+            authentication"
+  )]
+  db_password_file: Option<PathBuf>,
+  #[structopt(last = true, help = "Additional arguments passed to setup")]
+  setup_arguments:  Vec<String>,
 }
 
 impl Arguments {
@@ -12,16 +24,18 @@ impl Arguments {
 
     self.ssh("apt-get install --yes build-essential postgresql");
 
-    eprintln!(
-      "Set IPv4 and IPv6 local connections to trust in /etc/postgresql/13/main/pg_hba.conf"
-    );
-    eprintln!();
-    eprintln!("# IPv4 local connections:");
-    eprintln!("host    all             all             127.0.0.1/32            trust");
-    eprintln!("# IPv6 local connections:");
-    eprintln!("host    all             all             ::1/128                 trust");
+    if self.db_password_file.is_none() {
+      eprintln!(
+        "Set IPv4 and IPv6 local connections to trust in /etc/postgresql/13/main/pg_hba.conf"
+      );
+      eprintln!();
+      eprintln!("# IPv4 local connections:");
+      eprintln!("host    all             all             127.0.0.1/32            trust");
+      eprintln!("# IPv6 local connections:");
+      eprintln!("host    all             all             ::1/128                 trust");
 
-    Command::new("bash").args(&["-c", "read"]).output().unwrap();
+      Command::new("bash").args(&["-c", "read"]).output().unwrap();
+    }
 
     self.ssh_check(
       "[[ -f rustup.sh ]]",
@@ -38,6 +52,18 @@ impl Arguments {
       format!("root@{}:tmp/quwue/", self.host)
     );
 
+    let mut setup_arguments = self.setup_arguments.clone();
+
+    if let Some(db_password_file) = &self.db_password_file {
+      run!(
+        "scp",
+        db_password_file.display().to_string(),
+        format!("root@{}:tmp/db-password", self.host)
+      );
+      setup_arguments.push("--db-password-file".into());
+      setup_arguments.push("tmp/db-password".into());
+    }
+
     let status = Command::new("ssh")
       .args(&[
         "-t",
@@ -48,10 +74,16 @@ impl Arguments {
         "tmp/quwue/Cargo.toml",
         "--package",
         "setup",
+        "--",
       ])
+      .args(&setup_arguments)
       .status()
       .unwrap();
 
+    if self.db_password_file.is_some() {
+      self.ssh("rm -f tmp/db-password");
+    }
+
     if !status.success() {
       panic!("setup failed: {}", status);
     }
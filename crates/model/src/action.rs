@@ -4,7 +4,17 @@ use crate::common::*;
 pub enum Action {
   AcceptCandidate { id: UserId },
   DeclineCandidate { id: UserId },
+  DeferCandidate { id: UserId },
+  DeleteProfile,
   DismissMatch { id: UserId },
+  ReportCandidate { id: UserId },
+  SetAnswer { answer: Answer },
   SetBio { text: String },
+  SetPaused { paused: bool },
+  SetPreferences { preferences: Preferences },
+  SetResurfaceDeclined { enabled: bool },
+  SetTags { tags: Vec<String> },
+  SuperLikeCandidate { id: UserId },
+  Undo,
   Welcome,
 }
@@ -0,0 +1,87 @@
+use crate::common::*;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, TryFromPrimitive)]
+#[repr(u64)]
+pub enum AgeBracket {
+  Under18,
+  From18To24,
+  From25To34,
+  From35To44,
+  From45,
+}
+
+impl AgeBracket {
+  pub const ALL: &'static [AgeBracket] = &[
+    AgeBracket::Under18,
+    AgeBracket::From18To24,
+    AgeBracket::From25To34,
+    AgeBracket::From35To44,
+    AgeBracket::From45,
+  ];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::Under18 => "13-17",
+      Self::From18To24 => "18-24",
+      Self::From25To34 => "25-34",
+      Self::From35To44 => "35-44",
+      Self::From45 => "45+",
+    }
+  }
+
+  pub fn minor(self) -> bool {
+    self == Self::Under18
+  }
+
+  pub fn parse(text: &str) -> Option<Self> {
+    let number = text.trim().parse::<u64>().ok()?;
+
+    Self::try_from(number.checked_sub(1)?).ok()
+  }
+
+  pub fn parse_range(text: &str) -> Option<(Self, Self)> {
+    let (a, b) = if let Some(i) = text.find('-') {
+      (Self::parse(&text[..i])?, Self::parse(&text[i + 1..])?)
+    } else {
+      let bracket = Self::parse(text)?;
+      (bracket, bracket)
+    };
+
+    Some((a.min(b), a.max(b)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    assert_eq!(AgeBracket::parse("1"), Some(AgeBracket::Under18));
+    assert_eq!(AgeBracket::parse(" 5 "), Some(AgeBracket::From45));
+    assert_eq!(AgeBracket::parse("0"), None);
+    assert_eq!(AgeBracket::parse("6"), None);
+    assert_eq!(AgeBracket::parse("18-24"), None);
+
+    for (i, bracket) in AgeBracket::ALL.iter().enumerate() {
+      assert_eq!(AgeBracket::parse(&(i + 1).to_string()), Some(*bracket));
+    }
+  }
+
+  #[test]
+  fn parse_range() {
+    use AgeBracket::*;
+
+    assert_eq!(
+      AgeBracket::parse_range("2-3"),
+      Some((From18To24, From25To34))
+    );
+    assert_eq!(
+      AgeBracket::parse_range("4 - 2"),
+      Some((From18To24, From35To44))
+    );
+    assert_eq!(AgeBracket::parse_range("3"), Some((From25To34, From25To34)));
+    assert_eq!(AgeBracket::parse_range("2-"), None);
+    assert_eq!(AgeBracket::parse_range("2-9"), None);
+  }
+}
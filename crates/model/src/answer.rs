@@ -0,0 +1,72 @@
+use crate::common::*;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Answer {
+  pub question:   u64,
+  pub choice:     u64,
+  /// Empty if the user would accept any choice
+  pub accepted:   Vec<u64>,
+  pub importance: Importance,
+}
+
+impl Answer {
+  pub fn new(question: u64, choice: u64) -> Self {
+    Self {
+      accepted: Vec::new(),
+      importance: Importance::default(),
+      question,
+      choice,
+    }
+  }
+
+  pub fn accepts(&self, other: &Self) -> bool {
+    self.accepted.is_empty() || self.accepted.contains(&other.choice)
+  }
+
+  pub fn accepted_mask(&self) -> u64 {
+    self
+      .accepted
+      .iter()
+      .fold(0, |mask, choice| mask | 1_u64 << choice)
+  }
+
+  pub fn accepted_from_mask(mask: u64) -> Vec<u64> {
+    (0..64)
+      .filter(|choice| mask & 1_u64 << choice != 0)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn accepts() {
+    let theirs = Answer::new(0, 1);
+
+    let mut ours = Answer::new(0, 0);
+    assert!(ours.accepts(&theirs));
+
+    ours.accepted = vec![0, 2];
+    assert!(!ours.accepts(&theirs));
+
+    ours.accepted = vec![1];
+    assert!(ours.accepts(&theirs));
+  }
+
+  #[test]
+  fn accepted_mask() {
+    let answer = Answer {
+      accepted: vec![0, 2, 3],
+      ..Answer::new(0, 0)
+    };
+
+    assert_eq!(answer.accepted_mask(), 0b1101);
+    assert_eq!(
+      Answer::accepted_from_mask(answer.accepted_mask()),
+      answer.accepted
+    );
+    assert_eq!(Answer::accepted_from_mask(0), Vec::<u64>::new());
+  }
+}
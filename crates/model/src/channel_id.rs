@@ -0,0 +1,12 @@
+use crate::common::*;
+
+/// A channel that prompts are sent to, with IDs from the same space as
+/// `UserId`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct ChannelId(pub u64);
+
+impl Display for ChannelId {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
@@ -1,13 +1,19 @@
+// stdlib
+pub(crate) use std::{
+  convert::TryFrom,
+  fmt::{self, Display, Formatter},
+};
+
 // dependencies
 pub(crate) use {
   num_enum::TryFromPrimitive,
   strum::{EnumDiscriminants, EnumIter},
-  twilight_http::request::channel::reaction::RequestReactionType,
-  twilight_model::id::{EmojiId, UserId},
 };
 
 // structs and enums
 pub(crate) use crate::{
-  action::Action, emoji::Emoji, prompt::Prompt, prompt_message::PromptMessage, response::Response,
-  update::Update,
+  action::Action, age_bracket::AgeBracket, answer::Answer, connection::Connection, emoji::Emoji,
+  importance::Importance, interests::Interests, message_id::MessageId, preferences::Preferences,
+  prompt::Prompt, prompt_message::PromptMessage, question::Question, questionnaire::Questionnaire,
+  response::Response, update::Update, user_id::UserId,
 };
@@ -0,0 +1,80 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Connection {
+  Friends,
+  Gaming,
+  Study,
+  Dating,
+}
+
+impl Connection {
+  pub const ALL: &'static [Connection] = &[
+    Connection::Friends,
+    Connection::Gaming,
+    Connection::Study,
+    Connection::Dating,
+  ];
+
+  pub fn name(self) -> &'static str {
+    match self {
+      Self::Friends => "friends",
+      Self::Gaming => "gaming",
+      Self::Study => "study",
+      Self::Dating => "dating",
+    }
+  }
+
+  pub fn parse(text: &str) -> Option<Self> {
+    let text = text.trim();
+
+    if let Ok(number) = text.parse::<usize>() {
+      return Self::ALL.get(number.checked_sub(1)?).copied();
+    }
+
+    Self::ALL
+      .iter()
+      .copied()
+      .find(|connection| connection.name().eq_ignore_ascii_case(text))
+  }
+
+  /// The SQL backends filter candidates with bitwise operations on masks
+  pub fn mask(connections: &[Self]) -> u64 {
+    connections
+      .iter()
+      .fold(0, |mask, connection| mask | 1 << *connection as u64)
+  }
+
+  pub fn from_mask(mask: u64) -> Vec<Self> {
+    Self::ALL
+      .iter()
+      .copied()
+      .filter(|connection| mask & Self::mask(&[*connection]) != 0)
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    assert_eq!(Connection::parse("Gaming"), Some(Connection::Gaming));
+    assert_eq!(Connection::parse(" 4 "), Some(Connection::Dating));
+    assert_eq!(Connection::parse("5"), None);
+    assert_eq!(Connection::parse("romance"), None);
+  }
+
+  #[test]
+  fn masks() {
+    // The compatibility predicate in the SQL migrations relies on these
+    assert_eq!(Connection::mask(Connection::ALL), 15);
+    assert_eq!(Connection::mask(&[Connection::Dating]), 8);
+
+    let connections = [Connection::Friends, Connection::Study];
+    assert_eq!(
+      Connection::from_mask(Connection::mask(&connections)),
+      connections
+    );
+    assert_eq!(Connection::from_mask(0), Vec::new());
+  }
+}
@@ -1,15 +1,43 @@
-use crate::common::*;
-
 use strum::{EnumString, IntoStaticStr};
 
 #[derive(Debug, Eq, PartialEq, EnumString, IntoStaticStr, Copy, Clone)]
 #[strum(serialize_all = "lowercase")]
 pub enum Emoji {
+  One,
+  Two,
+  Three,
+  Four,
+  Five,
+  Six,
+  Seven,
+  Eight,
+  Nine,
+  #[strum(serialize = "keycap_ten")]
+  Ten,
+  #[strum(serialize = "triangular_flag_on_post")]
+  Report,
+  #[strum(serialize = "track_next")]
+  Skip,
+  #[strum(serialize = "star")]
+  SuperLike,
   ThumbsDown,
   ThumbsUp,
 }
 
 impl Emoji {
+  pub const KEYCAPS: &'static [Emoji] = &[
+    Emoji::One,
+    Emoji::Two,
+    Emoji::Three,
+    Emoji::Four,
+    Emoji::Five,
+    Emoji::Six,
+    Emoji::Seven,
+    Emoji::Eight,
+    Emoji::Nine,
+    Emoji::Ten,
+  ];
+
   pub fn markup(self) -> String {
     format!(":{}:", self.name())
   }
@@ -18,32 +46,52 @@ impl Emoji {
     self.into()
   }
 
-  pub fn char(self) -> char {
+  pub fn unicode(self) -> &'static str {
     use Emoji::*;
     match self {
-      ThumbsDown => '👎',
-      ThumbsUp => '👍',
+      One => "1\u{fe0f}\u{20e3}",
+      Two => "2\u{fe0f}\u{20e3}",
+      Three => "3\u{fe0f}\u{20e3}",
+      Four => "4\u{fe0f}\u{20e3}",
+      Five => "5\u{fe0f}\u{20e3}",
+      Six => "6\u{fe0f}\u{20e3}",
+      Seven => "7\u{fe0f}\u{20e3}",
+      Eight => "8\u{fe0f}\u{20e3}",
+      Nine => "9\u{fe0f}\u{20e3}",
+      Ten => "🔟",
+      Report => "🚩",
+      Skip => "⏭\u{fe0f}",
+      SuperLike => "⭐",
+      ThumbsDown => "👎",
+      ThumbsUp => "👍",
     }
   }
 
+  /// Some clients strip variation selectors
   pub fn from_chars(chars: &str) -> Option<Self> {
     use Emoji::*;
-    match chars {
+    match chars.replace('\u{fe0f}', "").as_str() {
       "👍" => Some(ThumbsUp),
       "👎" => Some(ThumbsDown),
-      _ => None,
+      "⏭" => Some(Skip),
+      "⭐" => Some(SuperLike),
+      "🚩" => Some(Report),
+      keycap => Self::KEYCAPS
+        .iter()
+        .copied()
+        .find(|emoji| emoji.unicode().replace('\u{fe0f}', "") == keycap),
     }
   }
-}
 
-impl From<Emoji> for RequestReactionType<'static> {
-  fn from(emoji: Emoji) -> Self {
-    Self::Unicode {
-      name: match emoji {
-        Emoji::ThumbsDown => "👎",
-        Emoji::ThumbsUp => "👍",
-      },
-    }
+  pub fn keycap(number: usize) -> Option<Self> {
+    Self::KEYCAPS.get(number.checked_sub(1)?).copied()
+  }
+
+  pub fn number(self) -> Option<usize> {
+    Self::KEYCAPS
+      .iter()
+      .position(|keycap| *keycap == self)
+      .map(|i| i + 1)
   }
 }
 
@@ -56,6 +104,9 @@ mod tests {
   #[test]
   fn from_str() {
     assert_eq!(Emoji::from_str("thumbsup"), Ok(Emoji::ThumbsUp));
+    assert_eq!(Emoji::from_str("three"), Ok(Emoji::Three));
+    assert_eq!(Emoji::from_str("keycap_ten"), Ok(Emoji::Ten));
+    assert_eq!(Emoji::from_str("star"), Ok(Emoji::SuperLike));
   }
 
   #[test]
@@ -65,7 +116,41 @@ mod tests {
 
   #[test]
   fn unicode() {
-    assert_eq!(Emoji::ThumbsUp.char(), '👍');
+    assert_eq!(Emoji::ThumbsUp.unicode(), "👍");
+    assert_eq!(Emoji::One.unicode(), "1️⃣");
+  }
+
+  #[test]
+  fn from_chars_round_trips() {
+    let others = [
+      Emoji::Report,
+      Emoji::Skip,
+      Emoji::SuperLike,
+      Emoji::ThumbsDown,
+      Emoji::ThumbsUp,
+    ];
+
+    for emoji in Emoji::KEYCAPS.iter().chain(&others) {
+      assert_eq!(Emoji::from_chars(emoji.unicode()), Some(*emoji));
+    }
+  }
+
+  #[test]
+  fn from_chars_ignores_variation_selectors() {
+    assert_eq!(Emoji::from_chars("2\u{20e3}"), Some(Emoji::Two));
+    assert_eq!(Emoji::from_chars("👍\u{fe0f}"), Some(Emoji::ThumbsUp));
+    assert_eq!(Emoji::from_chars("⏭"), Some(Emoji::Skip));
+    assert_eq!(Emoji::from_chars("2"), None);
+  }
+
+  #[test]
+  fn keycaps() {
+    assert_eq!(Emoji::keycap(1), Some(Emoji::One));
+    assert_eq!(Emoji::keycap(10), Some(Emoji::Ten));
+    assert_eq!(Emoji::keycap(0), None);
+    assert_eq!(Emoji::keycap(11), None);
+    assert_eq!(Emoji::Seven.number(), Some(7));
+    assert_eq!(Emoji::ThumbsUp.number(), None);
   }
 
   #[test]
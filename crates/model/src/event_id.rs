@@ -0,0 +1,51 @@
+use crate::common::*;
+
+/// Events may be delivered more than once, for example after a gateway
+/// resume
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum EventId {
+  Message {
+    id: MessageId,
+  },
+  Reaction {
+    message_id: MessageId,
+    user_id:    UserId,
+    emoji:      String,
+  },
+}
+
+impl Display for EventId {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    match self {
+      Self::Message { id } => write!(f, "message:{}", id),
+      Self::Reaction {
+        message_id,
+        user_id,
+        emoji,
+      } => write!(f, "reaction:{}:{}:{}", message_id, user_id, emoji),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn display() {
+    assert_eq!(
+      EventId::Message { id: MessageId(1) }.to_string(),
+      "message:1"
+    );
+
+    assert_eq!(
+      EventId::Reaction {
+        message_id: MessageId(1),
+        user_id:    UserId(2),
+        emoji:      "👍".into(),
+      }
+      .to_string(),
+      "reaction:1:2:👍"
+    );
+  }
+}
@@ -0,0 +1,71 @@
+use crate::common::*;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, TryFromPrimitive)]
+#[repr(u64)]
+pub enum Importance {
+  Irrelevant,
+  Little,
+  Somewhat,
+  Very,
+}
+
+impl Importance {
+  pub const ALL: &'static [Importance] = &[
+    Importance::Irrelevant,
+    Importance::Little,
+    Importance::Somewhat,
+    Importance::Very,
+  ];
+
+  pub fn label(self) -> &'static str {
+    match self {
+      Self::Irrelevant => "Irrelevant",
+      Self::Little => "A little important",
+      Self::Somewhat => "Somewhat important",
+      Self::Very => "Very important",
+    }
+  }
+
+  /// Weights grow steeply, so that a few very important answers outweigh
+  /// many less important ones
+  pub fn weight(self) -> u64 {
+    match self {
+      Self::Irrelevant => 0,
+      Self::Little => 1,
+      Self::Somewhat => 10,
+      Self::Very => 50,
+    }
+  }
+
+  pub fn parse(text: &str) -> Option<Self> {
+    let number = text.trim().parse::<u64>().ok()?;
+
+    Self::try_from(number.checked_sub(1)?).ok()
+  }
+}
+
+impl Default for Importance {
+  fn default() -> Self {
+    Self::Somewhat
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse() {
+    assert_eq!(Importance::parse("1"), Some(Importance::Irrelevant));
+    assert_eq!(Importance::parse(" 4 "), Some(Importance::Very));
+    assert_eq!(Importance::parse("0"), None);
+    assert_eq!(Importance::parse("5"), None);
+  }
+
+  #[test]
+  fn weights_increase_with_importance() {
+    for pair in Importance::ALL.windows(2) {
+      assert!(pair[0].weight() < pair[1].weight());
+    }
+  }
+}
@@ -0,0 +1,140 @@
+pub struct Interests;
+
+impl Interests {
+  pub const COMMAND: &'static str = "!tags";
+  pub const CURATED: &'static [&'static str] = &[
+    "anime",
+    "art",
+    "books",
+    "cooking",
+    "fitness",
+    "gaming",
+    "movies",
+    "music",
+    "outdoors",
+    "programming",
+    "science",
+    "travel",
+  ];
+  pub const MAX_LENGTH: usize = 32;
+  pub const MAX_TAGS: usize = 10;
+
+  /// `None` if the response contained no usable tags
+  pub fn parse(content: &str) -> Option<Vec<String>> {
+    let content = content.trim();
+
+    if content.eq_ignore_ascii_case("none") || content.eq_ignore_ascii_case("skip") {
+      return Some(Vec::new());
+    }
+
+    let mut tags = Vec::new();
+
+    for tag in content.split(&[',', '\n'][..]).filter_map(Self::normalize) {
+      if tags.len() == Self::MAX_TAGS {
+        break;
+      }
+
+      if !tags.contains(&tag) {
+        tags.push(tag);
+      }
+    }
+
+    tags.sort();
+
+    if tags.is_empty() {
+      None
+    } else {
+      Some(tags)
+    }
+  }
+
+  fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim();
+
+    if let Ok(number) = tag.parse::<usize>() {
+      return Self::CURATED
+        .get(number.checked_sub(1)?)
+        .map(|tag| (*tag).to_owned());
+    }
+
+    let tag = tag
+      .trim_start_matches('#')
+      .split_whitespace()
+      .collect::<Vec<&str>>()
+      .join(" ")
+      .to_lowercase();
+
+    if tag.is_empty() || tag.chars().count() > Self::MAX_LENGTH {
+      None
+    } else {
+      Some(tag)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn tags(tags: &[&str]) -> Vec<String> {
+    tags.iter().map(|tag| (*tag).to_owned()).collect()
+  }
+
+  #[test]
+  fn parse() {
+    assert_eq!(
+      Interests::parse("Rust, #Board  Games"),
+      Some(tags(&["board games", "rust"]))
+    );
+    assert_eq!(
+      Interests::parse("music\nmusic, MUSIC"),
+      Some(tags(&["music"]))
+    );
+    assert_eq!(Interests::parse(" None "), Some(tags(&[])));
+    assert_eq!(Interests::parse("Skip"), Some(tags(&[])));
+    assert_eq!(Interests::parse(", ,#"), None);
+    assert_eq!(Interests::parse(""), None);
+  }
+
+  #[test]
+  fn curated_tags_can_be_picked_by_number() {
+    assert_eq!(
+      Interests::parse("1, 6, rust"),
+      Some(tags(&["anime", "gaming", "rust"]))
+    );
+    assert_eq!(Interests::parse("0"), None);
+    assert_eq!(Interests::parse("13, art"), Some(tags(&["art"])));
+  }
+
+  #[test]
+  fn long_tags_are_ignored() {
+    let longest = "a".repeat(Interests::MAX_LENGTH);
+
+    assert_eq!(Interests::parse(&longest), Some(tags(&[longest.as_str()])));
+    assert_eq!(
+      Interests::parse(&"a".repeat(Interests::MAX_LENGTH + 1)),
+      None
+    );
+  }
+
+  #[test]
+  fn extra_tags_are_ignored() {
+    let content = (0..20)
+      .map(|i| format!("tag{:02}, tag00", i))
+      .collect::<Vec<String>>()
+      .join(",");
+
+    let tags = Interests::parse(&content).unwrap();
+
+    assert_eq!(tags.len(), Interests::MAX_TAGS);
+    assert_eq!(tags.first().unwrap(), "tag00");
+    assert_eq!(tags.last().unwrap(), "tag09");
+  }
+
+  #[test]
+  fn curated_tags_are_normalized() {
+    for tag in Interests::CURATED {
+      assert_eq!(Interests::parse(tag), Some(tags(&[*tag])));
+    }
+  }
+}
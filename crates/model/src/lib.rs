@@ -1,18 +1,42 @@
 pub use crate::{
   action::Action,
+  age_bracket::AgeBracket,
+  answer::Answer,
+  channel_id::ChannelId,
+  connection::Connection,
   emoji::Emoji,
+  event_id::EventId,
+  importance::Importance,
+  interests::Interests,
+  message_id::MessageId,
+  preferences::Preferences,
   prompt::{Prompt, PromptDiscriminant},
   prompt_message::PromptMessage,
+  question::Question,
+  questionnaire::Questionnaire,
   response::Response,
   update::Update,
   user::User,
+  user_id::UserId,
 };
 
 mod action;
+mod age_bracket;
+mod answer;
+mod channel_id;
 mod common;
+mod connection;
 mod emoji;
+mod event_id;
+mod importance;
+mod interests;
+mod message_id;
+mod preferences;
 mod prompt;
 mod prompt_message;
+mod question;
+mod questionnaire;
 mod response;
 mod update;
 mod user;
+mod user_id;
@@ -0,0 +1,12 @@
+use crate::common::*;
+
+/// A message sent by the bot or a user, with IDs from the same space as
+/// `UserId`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct MessageId(pub u64);
+
+impl Display for MessageId {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
@@ -0,0 +1,391 @@
+use crate::common::*;
+
+/// Unset fields match anyone
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct Preferences {
+  pub age:             Option<AgeBracket>,
+  /// Inclusive
+  pub ages:            Option<(AgeBracket, AgeBracket)>,
+  pub connections:     Vec<Connection>,
+  pub languages:       Vec<String>,
+  /// Hours, only set if `utc_offset` is
+  pub timezone_window: Option<u64>,
+  /// Minutes
+  pub utc_offset:      Option<i64>,
+}
+
+impl Preferences {
+  pub const COMMAND: &'static str = "!prefs";
+  pub const MAX_LANGUAGES: usize = 10;
+  pub const MAX_LANGUAGE_LENGTH: usize = 32;
+  const SKIP: &'static [&'static str] = &["any", "skip"];
+
+  /// Users under 18 are only shown to each other
+  pub fn compatible(&self, other: &Self) -> bool {
+    self.accepts(other)
+      && other.accepts(self)
+      && self.minor() == other.minor()
+      && Self::overlap(&self.languages, &other.languages)
+      && self
+        .connections()
+        .iter()
+        .any(|connection| other.connections().contains(connection))
+  }
+
+  pub fn answer(&self, prompt: Prompt, content: &str) -> Option<Self> {
+    let content = content.trim().to_lowercase();
+
+    let mut preferences = self.clone();
+
+    match prompt {
+      Prompt::Age => preferences.age = Self::optional(&content, AgeBracket::parse)?,
+      Prompt::AgeRange => preferences.ages = Self::optional(&content, AgeBracket::parse_range)?,
+      Prompt::UtcOffset => {
+        preferences.utc_offset = Self::optional(&content, Self::parse_utc_offset)?;
+        if preferences.utc_offset.is_none() {
+          preferences.timezone_window = None;
+        }
+      },
+      Prompt::TimezoneWindow =>
+        preferences.timezone_window = Self::optional(&content, Self::parse_timezone_window)?,
+      Prompt::Languages => {
+        preferences.languages =
+          Self::optional(&content, Self::parse_languages)?.unwrap_or_default();
+      },
+      Prompt::Connections => {
+        preferences.connections =
+          Self::optional(&content, Self::parse_connections)?.unwrap_or_default();
+      },
+      Prompt::Welcome
+      | Prompt::Bio
+      | Prompt::Tags
+      | Prompt::Quiescent
+      | Prompt::Candidate { .. }
+      | Prompt::Match { .. }
+      | Prompt::QuestionChoice { .. }
+      | Prompt::QuestionAccepted { .. }
+      | Prompt::QuestionImportance { .. } => return None,
+    }
+
+    Some(preferences)
+  }
+
+  fn accepts(&self, other: &Self) -> bool {
+    let ages = match self.ages {
+      Some((min, max)) => other.age.map_or(false, |age| (min..=max).contains(&age)),
+      None => true,
+    };
+
+    let timezone = match (self.timezone_window, self.utc_offset, other.utc_offset) {
+      (Some(window), Some(ours), Some(theirs)) => (ours - theirs).unsigned_abs() <= window * 60,
+      (Some(_), ..) => false,
+      (None, ..) => true,
+    };
+
+    ages && timezone
+  }
+
+  fn minor(&self) -> bool {
+    self.age.map_or(false, AgeBracket::minor)
+  }
+
+  /// Users under 18 are never matched for dating
+  fn connections(&self) -> Vec<Connection> {
+    let connections: &[Connection] = if self.connections.is_empty() {
+      Connection::ALL
+    } else {
+      &self.connections
+    };
+
+    connections
+      .iter()
+      .copied()
+      .filter(|connection| !(self.minor() && *connection == Connection::Dating))
+      .collect()
+  }
+
+  fn overlap<T: PartialEq>(a: &[T], b: &[T]) -> bool {
+    a.is_empty() || b.is_empty() || a.iter().any(|item| b.contains(item))
+  }
+
+  /// `Some(None)` if `content` is a skip answer
+  #[allow(clippy::option_option)]
+  fn optional<T>(content: &str, parse: impl FnOnce(&str) -> Option<T>) -> Option<Option<T>> {
+    if Self::SKIP.contains(&content) {
+      Some(None)
+    } else {
+      parse(content).map(Some)
+    }
+  }
+
+  /// Accepts offsets like `+2`, `-5`, `+5:30` or `UTC-3:30`
+  fn parse_utc_offset(content: &str) -> Option<i64> {
+    let content = content.trim_start_matches("utc").trim();
+
+    let (sign, content) = match content.strip_prefix('-') {
+      Some(content) => (-1, content),
+      None => (1, content.strip_prefix('+').unwrap_or(content)),
+    };
+
+    let (hours, minutes) = match content.find(':') {
+      Some(i) => (&content[..i], &content[i + 1..]),
+      None => (content, "0"),
+    };
+
+    let hours = hours.trim().parse::<i64>().ok()?;
+    let minutes = minutes.trim().parse::<i64>().ok()?;
+
+    if hours < 0 || !(0..60).contains(&minutes) {
+      return None;
+    }
+
+    let offset = sign * (hours * 60 + minutes);
+
+    if (-12 * 60..=14 * 60).contains(&offset) {
+      Some(offset)
+    } else {
+      None
+    }
+  }
+
+  fn parse_timezone_window(content: &str) -> Option<u64> {
+    let hours = content.trim_end_matches('h').trim().parse::<u64>().ok()?;
+
+    if hours <= 24 {
+      Some(hours)
+    } else {
+      None
+    }
+  }
+
+  fn parse_languages(content: &str) -> Option<Vec<String>> {
+    let mut languages = Vec::new();
+
+    for language in content.split(&[',', '\n'][..]) {
+      let language = language.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+      if languages.len() == Self::MAX_LANGUAGES {
+        break;
+      }
+
+      if !language.is_empty()
+        && language.chars().count() <= Self::MAX_LANGUAGE_LENGTH
+        && !languages.contains(&language)
+      {
+        languages.push(language);
+      }
+    }
+
+    languages.sort();
+
+    if languages.is_empty() {
+      None
+    } else {
+      Some(languages)
+    }
+  }
+
+  fn parse_connections(content: &str) -> Option<Vec<Connection>> {
+    let mut connections = content
+      .split(&[',', '\n'][..])
+      .filter(|connection| !connection.trim().is_empty())
+      .map(Connection::parse)
+      .collect::<Option<Vec<Connection>>>()?;
+
+    connections.sort();
+    connections.dedup();
+
+    if connections.is_empty() {
+      None
+    } else {
+      Some(connections)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn answer(prompt: Prompt, content: &str) -> Option<Preferences> {
+    Preferences::default().answer(prompt, content)
+  }
+
+  #[test]
+  fn answers() {
+    assert_eq!(
+      answer(Prompt::Age, "2").unwrap().age,
+      Some(AgeBracket::From18To24)
+    );
+    assert_eq!(
+      answer(Prompt::AgeRange, "4-2").unwrap().ages,
+      Some((AgeBracket::From18To24, AgeBracket::From35To44))
+    );
+    assert_eq!(
+      answer(Prompt::UtcOffset, "UTC+5:30").unwrap().utc_offset,
+      Some(330)
+    );
+    assert_eq!(
+      answer(Prompt::UtcOffset, "-3").unwrap().utc_offset,
+      Some(-180)
+    );
+    assert_eq!(
+      answer(Prompt::TimezoneWindow, "3h")
+        .unwrap()
+        .timezone_window,
+      Some(3)
+    );
+    assert_eq!(
+      answer(Prompt::Languages, "Spanish, english,  Old   Norse")
+        .unwrap()
+        .languages,
+      &["english", "old norse", "spanish"]
+    );
+    assert_eq!(
+      answer(Prompt::Connections, "dating, 1")
+        .unwrap()
+        .connections,
+      &[Connection::Friends, Connection::Dating]
+    );
+  }
+
+  #[test]
+  fn unrecognized_answers_are_rejected() {
+    assert_eq!(answer(Prompt::Age, "twenty"), None);
+    assert_eq!(answer(Prompt::AgeRange, "1-9"), None);
+    assert_eq!(answer(Prompt::UtcOffset, "+15"), None);
+    assert_eq!(answer(Prompt::UtcOffset, "+2:60"), None);
+    assert_eq!(answer(Prompt::TimezoneWindow, "25"), None);
+    assert_eq!(answer(Prompt::Languages, " , "), None);
+    assert_eq!(answer(Prompt::Connections, "friends, romance"), None);
+    assert_eq!(answer(Prompt::Quiescent, "any"), None);
+  }
+
+  #[test]
+  fn preferences_can_be_skipped() {
+    let mut preferences = Preferences {
+      age:             Some(AgeBracket::From45),
+      ages:            Some((AgeBracket::From35To44, AgeBracket::From45)),
+      connections:     vec![Connection::Study],
+      languages:       vec!["english".into()],
+      timezone_window: Some(2),
+      utc_offset:      Some(0),
+    };
+
+    for (prompt, content) in [
+      (Prompt::Age, "skip"),
+      (Prompt::AgeRange, "Any"),
+      (Prompt::UtcOffset, "skip"),
+      (Prompt::Languages, "any"),
+      (Prompt::Connections, "any"),
+    ] {
+      preferences = preferences.answer(prompt, content).unwrap();
+    }
+
+    assert_eq!(preferences, Preferences::default());
+  }
+
+  #[test]
+  fn unset_preferences_are_compatible() {
+    assert!(Preferences::default().compatible(&Preferences::default()));
+  }
+
+  #[test]
+  fn age_ranges_must_be_mutually_satisfied() {
+    let a = Preferences {
+      age: Some(AgeBracket::From25To34),
+      ages: Some((AgeBracket::From25To34, AgeBracket::From35To44)),
+      ..Preferences::default()
+    };
+
+    let mut b = Preferences {
+      age: Some(AgeBracket::From35To44),
+      ..Preferences::default()
+    };
+
+    assert!(a.compatible(&b));
+
+    b.ages = Some((AgeBracket::From35To44, AgeBracket::From45));
+    assert!(!a.compatible(&b));
+    assert!(!b.compatible(&a));
+
+    b.ages = None;
+    b.age = None;
+    assert!(!a.compatible(&b));
+  }
+
+  #[test]
+  fn timezones_must_be_within_window() {
+    let a = Preferences {
+      utc_offset: Some(60),
+      timezone_window: Some(2),
+      ..Preferences::default()
+    };
+
+    let mut b = Preferences {
+      utc_offset: Some(-60),
+      ..Preferences::default()
+    };
+
+    assert!(a.compatible(&b));
+
+    b.utc_offset = Some(-90);
+    assert!(!a.compatible(&b));
+
+    b.utc_offset = None;
+    assert!(!a.compatible(&b));
+  }
+
+  #[test]
+  fn languages_and_connections_must_overlap() {
+    let a = Preferences {
+      languages: vec!["english".into(), "french".into()],
+      connections: vec![Connection::Gaming],
+      ..Preferences::default()
+    };
+
+    let mut b = Preferences {
+      languages: vec!["french".into()],
+      ..Preferences::default()
+    };
+
+    assert!(a.compatible(&b));
+
+    b.connections = vec![Connection::Friends, Connection::Gaming];
+    assert!(a.compatible(&b));
+
+    b.connections = vec![Connection::Study];
+    assert!(!a.compatible(&b));
+
+    b.connections = Vec::new();
+    b.languages = vec!["german".into()];
+    assert!(!a.compatible(&b));
+  }
+
+  #[test]
+  fn minors_are_only_matched_with_minors_and_never_for_dating() {
+    let minor = Preferences {
+      age: Some(AgeBracket::Under18),
+      ..Preferences::default()
+    };
+
+    assert!(minor.compatible(&minor));
+    assert!(!minor.compatible(&Preferences::default()));
+    assert!(!Preferences::default().compatible(&minor));
+
+    let adult = Preferences {
+      age: Some(AgeBracket::From18To24),
+      ..Preferences::default()
+    };
+
+    assert!(!minor.compatible(&adult));
+
+    let dating = Preferences {
+      connections: vec![Connection::Dating],
+      ..minor.clone()
+    };
+
+    assert!(!dating.compatible(&minor));
+    assert!(!dating.compatible(&dating));
+  }
+}
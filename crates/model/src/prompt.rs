@@ -12,20 +12,56 @@ use {Emoji::*, Prompt::*};
 pub enum Prompt {
   Welcome,
   Bio,
+  Tags,
   Quiescent,
-  Candidate { id: UserId },
-  Match { id: UserId },
+  Candidate {
+    id: UserId,
+  },
+  Match {
+    id: UserId,
+  },
+  /// Comes after candidates and matches, so that neither interrupts a user
+  /// who is changing their preferences
+  Age,
+  AgeRange,
+  UtcOffset,
+  TimezoneWindow,
+  Languages,
+  Connections,
+  QuestionChoice {
+    question: u64,
+  },
+  QuestionAccepted {
+    question: u64,
+  },
+  QuestionImportance {
+    question: u64,
+  },
 }
 
 impl Prompt {
-  pub fn reactions(self) -> &'static [Emoji] {
+  pub fn reactions(self) -> Vec<Emoji> {
     match self {
-      Candidate { .. } => &[ThumbsUp, ThumbsDown],
-      Match { .. } | Welcome => &[ThumbsUp],
-      Bio | Quiescent => &[],
+      Candidate { .. } => vec![ThumbsUp, ThumbsDown, Skip, SuperLike, Report],
+      Match { .. } | Welcome => vec![ThumbsUp],
+      Tags | UtcOffset | TimezoneWindow | Languages => vec![Skip],
+      Age | AgeRange => Self::numbered(AgeBracket::ALL.len(), &[Skip]),
+      Connections => Self::numbered(Connection::ALL.len(), &[Skip]),
+      QuestionChoice { question } => Self::numbered(Self::choices(question), &[Skip]),
+      QuestionAccepted { question } => Self::numbered(Self::choices(question), &[ThumbsUp]),
+      QuestionImportance { .. } => Self::numbered(Importance::ALL.len(), &[]),
+      Bio | Quiescent => Vec::new(),
     }
   }
 
+  fn numbered(n: usize, rest: &[Emoji]) -> Vec<Emoji> {
+    Emoji::KEYCAPS[..n].iter().chain(rest).copied().collect()
+  }
+
+  fn choices(question: u64) -> usize {
+    Questionnaire::question(question).map_or(0, |question| question.choices.len())
+  }
+
   pub fn quiescent(self) -> bool {
     self == Quiescent
   }
@@ -51,7 +87,42 @@ mod tests {
     discriminants.sort();
 
     assert_eq!(discriminants, vec![
-      Welcome, Bio, Quiescent, Candidate, Match
+      Welcome,
+      Bio,
+      Tags,
+      Quiescent,
+      Candidate,
+      Match,
+      Age,
+      AgeRange,
+      UtcOffset,
+      TimezoneWindow,
+      Languages,
+      Connections,
+      QuestionChoice,
+      QuestionAccepted,
+      QuestionImportance,
+    ]);
+  }
+
+  #[test]
+  fn question_reactions() {
+    let choices = Questionnaire::QUESTIONS[0].choices.len();
+
+    let reactions = QuestionChoice { question: 0 }.reactions();
+    assert_eq!(reactions.len(), choices + 1);
+    assert_eq!(reactions.first(), Some(&One));
+    assert_eq!(reactions.last(), Some(&Skip));
+
+    let reactions = QuestionAccepted { question: 0 }.reactions();
+    assert_eq!(reactions.len(), choices + 1);
+    assert_eq!(reactions.last(), Some(&ThumbsUp));
+
+    assert_eq!(QuestionImportance { question: 0 }.reactions(), vec![
+      One, Two, Three, Four
     ]);
+
+    let missing = Questionnaire::QUESTIONS.len() as u64;
+    assert_eq!(QuestionChoice { question: missing }.reactions(), vec![Skip]);
   }
 }
@@ -0,0 +1,70 @@
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Question {
+  pub text:    &'static str,
+  pub choices: &'static [&'static str],
+}
+
+impl Question {
+  pub fn choice(&self, number: usize) -> Option<u64> {
+    if (1..=self.choices.len()).contains(&number) {
+      Some(number as u64 - 1)
+    } else {
+      None
+    }
+  }
+
+  pub fn parse_choice(&self, text: &str) -> Option<u64> {
+    self.choice(text.trim().parse().ok()?)
+  }
+
+  /// `any` accepts any choice, which is an empty list
+  pub fn parse_choices(&self, text: &str) -> Option<Vec<u64>> {
+    let text = text.trim();
+
+    if text.eq_ignore_ascii_case("any") {
+      return Some(Vec::new());
+    }
+
+    let mut choices = text
+      .split(|c: char| c == ',' || c.is_whitespace())
+      .filter(|number| !number.is_empty())
+      .map(|number| self.parse_choice(number))
+      .collect::<Option<Vec<u64>>>()?;
+
+    choices.sort_unstable();
+    choices.dedup();
+
+    if choices.is_empty() {
+      None
+    } else {
+      Some(choices)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const QUESTION: Question = Question {
+    text:    "Cats or dogs?",
+    choices: &["Cats", "Dogs", "Both"],
+  };
+
+  #[test]
+  fn parse_choice() {
+    assert_eq!(QUESTION.parse_choice(" 1 "), Some(0));
+    assert_eq!(QUESTION.parse_choice("3"), Some(2));
+    assert_eq!(QUESTION.parse_choice("0"), None);
+    assert_eq!(QUESTION.parse_choice("4"), None);
+    assert_eq!(QUESTION.parse_choice("cats"), None);
+  }
+
+  #[test]
+  fn parse_choices() {
+    assert_eq!(QUESTION.parse_choices("3, 1 1"), Some(vec![0, 2]));
+    assert_eq!(QUESTION.parse_choices("Any"), Some(Vec::new()));
+    assert_eq!(QUESTION.parse_choices("1, 4"), None);
+    assert_eq!(QUESTION.parse_choices(" , "), None);
+  }
+}
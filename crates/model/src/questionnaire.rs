@@ -0,0 +1,205 @@
+use crate::common::*;
+
+pub struct Questionnaire;
+
+impl Questionnaire {
+  pub const COMMAND: &'static str = "!questions";
+  /// Questions are identified by position, so new questions must be appended
+  pub const QUESTIONS: &'static [Question] = &[
+    Question {
+      text:    "How do you like to spend a free evening?",
+      choices: &["At home", "Out with friends", "Online", "It depends"],
+    },
+    Question {
+      text:    "How quickly do you usually reply to messages?",
+      choices: &[
+        "Within minutes",
+        "Within hours",
+        "Within a day or two",
+        "Whenever I get to it",
+      ],
+    },
+    Question {
+      text:    "How do you feel about voice chat?",
+      choices: &["Love it", "Sometimes", "Text only, please"],
+    },
+    Question {
+      text:    "Are you a morning person or a night owl?",
+      choices: &["Morning person", "Night owl", "Neither", "Both"],
+    },
+    Question {
+      text:    "How competitive are you?",
+      choices: &["Very", "A little", "Not at all"],
+    },
+    Question {
+      text:    "How often would you like to talk with a new friend?",
+      choices: &[
+        "Every day",
+        "A few times a week",
+        "Once a week or so",
+        "Now and then",
+      ],
+    },
+  ];
+
+  pub fn question(question: u64) -> Option<&'static Question> {
+    Self::QUESTIONS.get(usize::try_from(question).ok()?)
+  }
+
+  pub fn next(question: u64) -> Option<u64> {
+    let next = question + 1;
+
+    Self::question(next).map(|_| next)
+  }
+
+  /// The geometric mean of both users' satisfaction, as a percentage, so
+  /// that it's only high if both are satisfied
+  #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+  pub fn compatibility(ours: &[Answer], theirs: &[Answer]) -> Option<u64> {
+    let satisfaction = Self::satisfaction(ours, theirs)? * Self::satisfaction(theirs, ours)?;
+
+    Some((satisfaction.sqrt() * 100.0).round() as u64)
+  }
+
+  #[allow(clippy::cast_precision_loss)]
+  fn satisfaction(ours: &[Answer], theirs: &[Answer]) -> Option<f64> {
+    let mut common = false;
+    let mut satisfied = 0;
+    let mut total = 0;
+
+    for answer in ours {
+      if let Some(their_answer) = theirs
+        .iter()
+        .find(|theirs| theirs.question == answer.question)
+      {
+        let weight = answer.importance.weight();
+
+        common = true;
+        total += weight;
+
+        if answer.accepts(their_answer) {
+          satisfied += weight;
+        }
+      }
+    }
+
+    if !common {
+      None
+    } else if total == 0 {
+      Some(1.0)
+    } else {
+      Some(satisfied as f64 / total as f64)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn answer(question: u64, choice: u64, accepted: &[u64], importance: Importance) -> Answer {
+    Answer {
+      accepted: accepted.to_vec(),
+      question,
+      choice,
+      importance,
+    }
+  }
+
+  #[test]
+  fn questions_fit_on_keycaps() {
+    assert!(!Questionnaire::QUESTIONS.is_empty());
+
+    for question in Questionnaire::QUESTIONS {
+      assert!(question.choices.len() >= 2);
+      assert!(question.choices.len() <= Emoji::KEYCAPS.len());
+    }
+  }
+
+  #[test]
+  fn next() {
+    assert_eq!(Questionnaire::next(0), Some(1));
+
+    let last = Questionnaire::QUESTIONS.len() as u64 - 1;
+    assert_eq!(Questionnaire::next(last), None);
+  }
+
+  #[test]
+  fn no_common_questions() {
+    let ours = [answer(0, 0, &[], Importance::Very)];
+    let theirs = [answer(1, 0, &[], Importance::Very)];
+
+    assert_eq!(Questionnaire::compatibility(&ours, &theirs), None);
+    assert_eq!(Questionnaire::compatibility(&ours, &[]), None);
+  }
+
+  #[test]
+  fn identical_answers_are_fully_compatible() {
+    let answers = [
+      answer(0, 1, &[1], Importance::Very),
+      answer(2, 0, &[0, 1], Importance::Little),
+    ];
+
+    assert_eq!(Questionnaire::compatibility(&answers, &answers), Some(100));
+  }
+
+  #[test]
+  fn unacceptable_answers_to_important_questions_are_incompatible() {
+    let ours = [answer(0, 0, &[0], Importance::Very)];
+    let theirs = [answer(0, 1, &[], Importance::Very)];
+
+    assert_eq!(Questionnaire::compatibility(&ours, &theirs), Some(0));
+  }
+
+  #[test]
+  fn compatibility_is_weighted_by_importance() {
+    // We're satisfied on the very important question but not the slightly
+    // important one: 50 / 51. They accept anything: 1.
+    let ours = [
+      answer(0, 0, &[0], Importance::Very),
+      answer(1, 0, &[0], Importance::Little),
+    ];
+    let theirs = [
+      answer(0, 0, &[], Importance::Somewhat),
+      answer(1, 1, &[], Importance::Somewhat),
+    ];
+
+    assert_eq!(Questionnaire::compatibility(&ours, &theirs), Some(99));
+
+    // The other way around: 1 / 51
+    let unsatisfied = [
+      answer(0, 0, &[1], Importance::Very),
+      answer(1, 0, &[1], Importance::Little),
+    ];
+
+    assert_eq!(
+      Questionnaire::compatibility(&unsatisfied, &theirs),
+      Some(14)
+    );
+  }
+
+  #[test]
+  fn compatibility_is_symmetric() {
+    let ours = [
+      answer(0, 0, &[0, 1], Importance::Somewhat),
+      answer(3, 2, &[2], Importance::Very),
+    ];
+    let theirs = [
+      answer(0, 1, &[0], Importance::Little),
+      answer(3, 1, &[1, 2], Importance::Somewhat),
+    ];
+
+    assert_eq!(
+      Questionnaire::compatibility(&ours, &theirs),
+      Questionnaire::compatibility(&theirs, &ours),
+    );
+  }
+
+  #[test]
+  fn irrelevant_answers_are_satisfied() {
+    let ours = [answer(0, 0, &[0], Importance::Irrelevant)];
+    let theirs = [answer(0, 1, &[], Importance::Very)];
+
+    assert_eq!(Questionnaire::compatibility(&ours, &theirs), Some(100));
+  }
+}
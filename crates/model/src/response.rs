@@ -1,11 +1,12 @@
 use crate::common::*;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone)]
 pub enum Response {
   Message(String),
   Reaction(Emoji),
   UnrecognizedReaction(String),
-  Custom(EmojiId),
+  /// A platform-specific custom emoji, identified by its ID
+  Custom(String),
 }
 
 impl Response {
@@ -17,7 +18,7 @@ impl Response {
     Emoji::from_chars(&chars).map_or_else(|| Self::UnrecognizedReaction(chars), Self::Reaction)
   }
 
-  pub fn custom_reaction(id: EmojiId) -> Response {
-    Self::Custom(id)
+  pub fn custom_reaction(id: impl Into<String>) -> Response {
+    Self::Custom(id.into())
   }
 }
@@ -1,15 +1,33 @@
 use crate::common::*;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct User {
-  pub id:             u64,
-  pub discord_id:     UserId,
-  pub prompt_message: Option<PromptMessage>,
-  pub welcomed:       bool,
-  pub bio:            Option<String>,
+  pub id:                 u64,
+  pub discord_id:         UserId,
+  pub prompt_message:     Option<PromptMessage>,
+  /// Times are seconds since the Unix epoch
+  pub prompt_sent_at:     Option<f64>,
+  pub welcomed:           bool,
+  pub bio:                Option<String>,
+  pub resurface_declined: bool,
+  pub paused:             bool,
+  /// `None` until the user has answered the tags prompt
+  pub tags:               Option<Vec<String>>,
+  pub preferences:        Preferences,
+  pub answers:            Vec<Answer>,
+  /// `None` for users created before creation times were recorded
+  pub created_at:         Option<f64>,
+  pub updated_at:         Option<f64>,
+  pub last_active_at:     Option<f64>,
 }
 
 impl User {
+  pub const BIO_COMMAND: &'static str = "!bio";
+  pub const DELETE_COMMAND: &'static str = "!delete";
+  pub const PAUSE_COMMAND: &'static str = "!pause";
+  pub const RESURFACE_COMMAND: &'static str = "!resurface";
+  pub const UNDO_COMMAND: &'static str = "!undo";
+
   pub fn update(&self, response: &Response) -> Update {
     let prompt = if let Some(prompt_message) = self.prompt_message {
       prompt_message.prompt
@@ -20,9 +38,22 @@ impl User {
       };
     };
 
+    let command = match response {
+      Response::Message(content) => self.command(prompt, content),
+      Response::Reaction(Emoji::Skip) => Self::skip(prompt),
+      _ => None,
+    };
+
+    if let Some(next_prompt) = command {
+      return Update {
+        action: None,
+        next_prompt,
+      };
+    }
+
     let action = match response {
-      Response::Message(content) => Self::action_for_message(prompt, content),
-      Response::Reaction(emoji) => Self::action_for_reaction(prompt, *emoji),
+      Response::Message(content) => self.action_for_message(prompt, content),
+      Response::Reaction(emoji) => self.action_for_reaction(prompt, *emoji),
       Response::UnrecognizedReaction(..) | Response::Custom(..) => None,
     };
 
@@ -35,17 +66,129 @@ impl User {
       };
     };
 
+    let next_prompt = if Self::is_pick(prompt, response) {
+      prompt
+    } else {
+      self.next_prompt(prompt, &action)
+    };
+
     Update {
-      next_prompt: self.next_prompt(&action),
-      action:      Some(action),
+      action: Some(action),
+      next_prompt,
+    }
+  }
+
+  /// Picks are collected on the prompt's message until they're confirmed
+  pub fn is_pick(prompt: Prompt, response: &Response) -> bool {
+    matches!(
+      (prompt, response),
+      (Prompt::QuestionAccepted { .. }, Response::Reaction(emoji)) if emoji.number().is_some()
+    )
+  }
+
+  fn command(&self, prompt: Prompt, content: &str) -> Option<Prompt> {
+    let content = content.trim().to_lowercase();
+
+    // Flows can only be started once onboarding is complete
+    let start = |first| if self.onboarded() { first } else { prompt };
+
+    if content == Self::BIO_COMMAND {
+      return Some(start(Prompt::Bio));
     }
+
+    if content == Interests::COMMAND {
+      return Some(start(Prompt::Tags));
+    }
+
+    if content == Preferences::COMMAND {
+      return Some(start(Prompt::Age));
+    }
+
+    if content == Questionnaire::COMMAND {
+      return Some(start(Prompt::QuestionChoice { question: 0 }));
+    }
+
+    if content == "skip" {
+      return Self::skip(prompt);
+    }
+
+    None
+  }
+
+  /// `None` if skipping `prompt` is recorded as an answer
+  fn skip(prompt: Prompt) -> Option<Prompt> {
+    match prompt {
+      Prompt::QuestionChoice { question } => Some(Self::after_question(question)),
+      _ => None,
+    }
+  }
+
+  fn onboarded(&self) -> bool {
+    self.welcomed && self.bio.is_some() && self.tags.is_some()
   }
 
-  fn action_for_message(prompt: Prompt, content: &str) -> Option<Action> {
+  fn update_answer(&self, question: u64, update: impl FnOnce(&mut Answer)) -> Option<Action> {
+    let mut answer = self
+      .answers
+      .iter()
+      .find(|answer| answer.question == question)?
+      .clone();
+
+    update(&mut answer);
+
+    Some(Action::SetAnswer { answer })
+  }
+
+  fn choose_answer(&self, question: u64, choice: u64) -> Action {
+    let importance = self
+      .answers
+      .iter()
+      .find(|answer| answer.question == question)
+      .map_or_else(Importance::default, |answer| answer.importance);
+
+    Action::SetAnswer {
+      answer: Answer {
+        importance,
+        ..Answer::new(question, choice)
+      },
+    }
+  }
+
+  fn after_question(question: u64) -> Prompt {
+    Questionnaire::next(question).map_or(Prompt::Quiescent, |question| Prompt::QuestionChoice {
+      question,
+    })
+  }
+
+  fn action_for_message(&self, prompt: Prompt, content: &str) -> Option<Action> {
     use Prompt::*;
 
     let content = content.trim();
 
+    // Undo isn't offered partway through other flows
+    if self.onboarded()
+      && matches!(prompt, Quiescent | Candidate { .. } | Match { .. })
+      && content.eq_ignore_ascii_case(Self::UNDO_COMMAND)
+    {
+      return Some(Action::Undo);
+    }
+
+    if self.onboarded() && content.eq_ignore_ascii_case(Self::RESURFACE_COMMAND) {
+      return Some(Action::SetResurfaceDeclined {
+        enabled: !self.resurface_declined,
+      });
+    }
+
+    if self.onboarded() && content.eq_ignore_ascii_case(Self::PAUSE_COMMAND) {
+      return Some(Action::SetPaused {
+        paused: !self.paused,
+      });
+    }
+
+    if self.welcomed && content.eq_ignore_ascii_case(Self::DELETE_COMMAND) {
+      return Some(Action::DeleteProfile);
+    }
+
     match prompt {
       Welcome =>
         if content.to_lowercase() == "ok" {
@@ -55,46 +198,102 @@ impl User {
         return Some(Action::SetBio {
           text: content.to_owned(),
         }),
+      Tags => return Interests::parse(content).map(|tags| Action::SetTags { tags }),
       Candidate { id } => match content.to_lowercase().as_str() {
         "yes" | "y" => return Some(Action::AcceptCandidate { id }),
         "no" | "n" => return Some(Action::DeclineCandidate { id }),
+        "skip" | "later" => return Some(Action::DeferCandidate { id }),
         _ => {},
       },
       Match { id } =>
         if content.to_lowercase() == "ok" {
           return Some(Action::DismissMatch { id });
         },
+      Age | AgeRange | UtcOffset | TimezoneWindow | Languages | Connections =>
+        return self
+          .preferences
+          .answer(prompt, content)
+          .map(|preferences| Action::SetPreferences { preferences }),
+      QuestionChoice { question } => {
+        let choice = Questionnaire::question(question)?.parse_choice(content)?;
+        return Some(self.choose_answer(question, choice));
+      },
+      QuestionAccepted { question } => {
+        let accepted = Questionnaire::question(question)?.parse_choices(content)?;
+        return self.update_answer(question, |answer| answer.accepted = accepted);
+      },
+      QuestionImportance { question } => {
+        let importance = Importance::parse(content)?;
+        return self.update_answer(question, |answer| answer.importance = importance);
+      },
       Quiescent => {},
     }
 
     None
   }
 
-  fn action_for_reaction(prompt: Prompt, emoji: Emoji) -> Option<Action> {
+  fn action_for_reaction(&self, prompt: Prompt, emoji: Emoji) -> Option<Action> {
     use {Emoji::*, Prompt::*};
 
+    // Reactions that weren't offered with the prompt are ignored
+    if !prompt.reactions().contains(&emoji) {
+      return None;
+    }
+
     match prompt {
-      Welcome =>
-        if emoji == ThumbsUp {
-          Some(Action::Welcome)
-        } else {
-          None
-        },
+      Welcome => Some(Action::Welcome),
       Candidate { id } => match emoji {
         ThumbsUp => Some(Action::AcceptCandidate { id }),
         ThumbsDown => Some(Action::DeclineCandidate { id }),
+        Skip => Some(Action::DeferCandidate { id }),
+        SuperLike => Some(Action::SuperLikeCandidate { id }),
+        Report => Some(Action::ReportCandidate { id }),
+        _ => None,
       },
-      Match { id } =>
+      Match { id } => Some(Action::DismissMatch { id }),
+      Tags => Some(Action::SetTags { tags: Vec::new() }),
+      Age | AgeRange | UtcOffset | TimezoneWindow | Languages | Connections => {
+        let content = match emoji.number() {
+          Some(number) => number.to_string(),
+          None => "skip".into(),
+        };
+
+        self
+          .preferences
+          .answer(prompt, &content)
+          .map(|preferences| Action::SetPreferences { preferences })
+      },
+      QuestionChoice { question } => {
+        let choice = Questionnaire::question(question)?.choice(emoji.number()?)?;
+        Some(self.choose_answer(question, choice))
+      },
+      QuestionAccepted { question } =>
         if emoji == ThumbsUp {
-          Some(Action::DismissMatch { id })
+          self.update_answer(question, |_| {})
         } else {
-          None
+          let choice = Questionnaire::question(question)?.choice(emoji.number()?)?;
+          self.update_answer(question, |answer| {
+            if !answer.accepted.contains(&choice) {
+              answer.accepted.push(choice);
+              answer.accepted.sort_unstable();
+            }
+          })
         },
-      Quiescent | Bio => None,
+      QuestionImportance { question } => {
+        let importance = Importance::ALL
+          .get(emoji.number()?.checked_sub(1)?)
+          .copied()?;
+        self.update_answer(question, |answer| answer.importance = importance)
+      },
+      Bio | Quiescent => None,
     }
   }
 
-  fn next_prompt(&self, action: &Action) -> Prompt {
+  fn next_prompt(&self, prompt: Prompt, action: &Action) -> Prompt {
+    if *action == Action::DeleteProfile {
+      return Prompt::Welcome;
+    }
+
     if !(self.welcomed || *action == Action::Welcome) {
       return Prompt::Welcome;
     }
@@ -106,6 +305,276 @@ impl User {
       }
     }
 
+    if self.tags.is_none() {
+      if let Action::SetTags { .. } = action {
+      } else {
+        return Prompt::Tags;
+      }
+    }
+
+    if *action == Action::Undo {
+      return prompt;
+    }
+
+    if let Action::SetResurfaceDeclined { .. } = action {
+      return prompt;
+    }
+
+    if let Action::SetPaused { .. } = action {
+      return Prompt::Quiescent;
+    }
+
+    if let Action::SetPreferences { preferences } = action {
+      return match prompt {
+        Prompt::Age => Prompt::AgeRange,
+        Prompt::AgeRange => Prompt::UtcOffset,
+        Prompt::UtcOffset if preferences.utc_offset.is_some() => Prompt::TimezoneWindow,
+        Prompt::UtcOffset | Prompt::TimezoneWindow => Prompt::Languages,
+        Prompt::Languages => Prompt::Connections,
+        _ => Prompt::Quiescent,
+      };
+    }
+
+    if let Action::SetAnswer { answer } = action {
+      return match prompt {
+        Prompt::QuestionChoice { question } => Prompt::QuestionAccepted { question },
+        Prompt::QuestionAccepted { question } => Prompt::QuestionImportance { question },
+        _ => Self::after_question(answer.question),
+      };
+    }
+
     Prompt::Quiescent
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn user(prompt: Prompt) -> User {
+    User {
+      id:                 1,
+      discord_id:         UserId(100),
+      prompt_message:     Some(PromptMessage {
+        message_id: MessageId(200),
+        prompt,
+      }),
+      prompt_sent_at:     Some(0.0),
+      welcomed:           true,
+      bio:                Some("Bio".into()),
+      resurface_declined: false,
+      paused:             false,
+      tags:               Some(Vec::new()),
+      preferences:        Preferences::default(),
+      answers:            vec![Answer::new(0, 0)],
+      created_at:         None,
+      updated_at:         None,
+      last_active_at:     None,
+    }
+  }
+
+  #[test]
+  fn offered_reactions_are_understood() {
+    let prompts = [
+      Prompt::Welcome,
+      Prompt::Tags,
+      Prompt::Candidate { id: UserId(101) },
+      Prompt::Match { id: UserId(101) },
+      Prompt::Age,
+      Prompt::AgeRange,
+      Prompt::UtcOffset,
+      Prompt::TimezoneWindow,
+      Prompt::Languages,
+      Prompt::Connections,
+      Prompt::QuestionChoice { question: 0 },
+      Prompt::QuestionAccepted { question: 0 },
+      Prompt::QuestionImportance { question: 0 },
+    ];
+
+    for &prompt in &prompts {
+      let user = user(prompt);
+
+      for emoji in prompt.reactions() {
+        let update = user.update(&Response::Reaction(emoji));
+        assert!(
+          update.action.is_some() || update.next_prompt != prompt,
+          "{:?} was ignored at {:?}",
+          emoji,
+          prompt
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn reactions_that_were_not_offered_are_ignored() {
+    let prompt = Prompt::Candidate { id: UserId(101) };
+
+    let update = user(prompt).update(&Response::Reaction(Emoji::Three));
+
+    assert_eq!(update.action, None);
+    assert_eq!(update.next_prompt, prompt);
+  }
+
+  #[test]
+  fn candidate_reactions() {
+    let id = UserId(101);
+    let user = user(Prompt::Candidate { id });
+
+    for (emoji, action) in [
+      (Emoji::ThumbsUp, Action::AcceptCandidate { id }),
+      (Emoji::ThumbsDown, Action::DeclineCandidate { id }),
+      (Emoji::Skip, Action::DeferCandidate { id }),
+      (Emoji::SuperLike, Action::SuperLikeCandidate { id }),
+      (Emoji::Report, Action::ReportCandidate { id }),
+    ] {
+      assert_eq!(user.update(&Response::Reaction(emoji)).action, Some(action));
+    }
+
+    assert_eq!(
+      user.update(&Response::message("skip")).action,
+      Some(Action::DeferCandidate { id })
+    );
+  }
+
+  #[test]
+  fn undo() {
+    let prompt = Prompt::Candidate { id: UserId(101) };
+
+    let update = user(prompt).update(&Response::message("!UNDO"));
+    assert_eq!(update.action, Some(Action::Undo));
+    assert_eq!(update.next_prompt, prompt);
+
+    assert_ne!(
+      user(Prompt::Age)
+        .update(&Response::message(User::UNDO_COMMAND))
+        .action,
+      Some(Action::Undo)
+    );
+
+    let mut user = user(Prompt::Bio);
+    user.bio = None;
+    assert_eq!(
+      user.update(&Response::message(User::UNDO_COMMAND)).action,
+      Some(Action::SetBio {
+        text: User::UNDO_COMMAND.into(),
+      })
+    );
+  }
+
+  #[test]
+  fn bio_can_be_rewritten() {
+    let update = user(Prompt::Quiescent).update(&Response::message("!Bio"));
+    assert_eq!(update.action, None);
+    assert_eq!(update.next_prompt, Prompt::Bio);
+
+    let update = user(Prompt::Bio).update(&Response::message("New bio"));
+    assert_eq!(
+      update.action,
+      Some(Action::SetBio {
+        text: "New bio".into(),
+      })
+    );
+    assert_eq!(update.next_prompt, Prompt::Quiescent);
+
+    let mut user = user(Prompt::Tags);
+    user.tags = None;
+    assert_eq!(
+      user
+        .update(&Response::message(User::BIO_COMMAND))
+        .next_prompt,
+      Prompt::Tags
+    );
+  }
+
+  #[test]
+  fn tags_can_be_skipped_and_changed() {
+    let update = user(Prompt::Quiescent).update(&Response::message(Interests::COMMAND));
+    assert_eq!(update.action, None);
+    assert_eq!(update.next_prompt, Prompt::Tags);
+
+    let update = user(Prompt::Tags).update(&Response::message("rust"));
+    assert_eq!(
+      update.action,
+      Some(Action::SetTags {
+        tags: vec!["rust".into()],
+      })
+    );
+    assert_eq!(update.next_prompt, Prompt::Quiescent);
+
+    let mut user = user(Prompt::Tags);
+    user.tags = None;
+    let update = user.update(&Response::message("skip"));
+    assert_eq!(update.action, Some(Action::SetTags { tags: Vec::new() }));
+    assert_eq!(update.next_prompt, Prompt::Quiescent);
+  }
+
+  #[test]
+  fn resurface() {
+    let prompt = Prompt::Quiescent;
+
+    let mut user = user(prompt);
+
+    let update = user.update(&Response::message(User::RESURFACE_COMMAND));
+    assert_eq!(
+      update.action,
+      Some(Action::SetResurfaceDeclined { enabled: true })
+    );
+    assert_eq!(update.next_prompt, prompt);
+
+    user.resurface_declined = true;
+    assert_eq!(
+      user.update(&Response::message("!Resurface")).action,
+      Some(Action::SetResurfaceDeclined { enabled: false })
+    );
+  }
+  #[test]
+  fn pause() {
+    let update =
+      user(Prompt::Candidate { id: UserId(101) }).update(&Response::message(User::PAUSE_COMMAND));
+    assert_eq!(update.action, Some(Action::SetPaused { paused: true }));
+    assert_eq!(update.next_prompt, Prompt::Quiescent);
+
+    let mut paused = user(Prompt::Quiescent);
+    paused.paused = true;
+    assert_eq!(
+      paused.update(&Response::message("!Pause")).action,
+      Some(Action::SetPaused { paused: false })
+    );
+
+    let mut user = user(Prompt::Bio);
+    user.bio = None;
+    assert_eq!(
+      user.update(&Response::message(User::PAUSE_COMMAND)).action,
+      Some(Action::SetBio {
+        text: User::PAUSE_COMMAND.into(),
+      })
+    );
+  }
+
+  #[test]
+  fn delete() {
+    let update = user(Prompt::Quiescent).update(&Response::message(User::DELETE_COMMAND));
+    assert_eq!(update.action, Some(Action::DeleteProfile));
+    assert_eq!(update.next_prompt, Prompt::Welcome);
+
+    let mut user = user(Prompt::Bio);
+    user.bio = None;
+    assert_eq!(
+      user
+        .update(&Response::message(User::DELETE_COMMAND))
+        .next_prompt,
+      Prompt::Welcome
+    );
+
+    user.welcomed = false;
+    user.prompt_message = Some(PromptMessage {
+      message_id: MessageId(200),
+      prompt:     Prompt::Welcome,
+    });
+    assert_eq!(
+      user.update(&Response::message(User::DELETE_COMMAND)).action,
+      None
+    );
+  }
+}
@@ -0,0 +1,12 @@
+use crate::common::*;
+
+/// A user. Discord users are identified by their Discord user ID, and users
+/// on other platforms by an ID that their transport maps them onto.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub struct UserId(pub u64);
+
+impl Display for UserId {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
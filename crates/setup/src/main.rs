@@ -1,69 +1,233 @@
 use {
   cradle::prelude::*,
-  std::{path::Path, process::Command},
+  db_url::SslMode,
+  std::{
+    fs,
+    io::{self, Write},
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+  },
+  structopt::StructOpt,
+  toml::value::{Table, Value},
 };
 
-fn main() {
-  if !run_output!(LogCommand, %"id --user quwue") {
-    run!(LogCommand, %"useradd --system quwue");
-  }
+const CONFIG_PATH: &str = "/etc/quwue/quwue.toml";
+const CREDENTIALS_PATH: &str = "/etc/systemd/system/quwue.service.d/credentials.conf";
+const PASSWORD_CREDENTIAL: &str = "db-password";
+const PASSWORD_PATH: &str = "/etc/quwue/db-password";
+
+#[derive(StructOpt)]
+struct Arguments {
+  #[structopt(long, help = "Connect to database with URL <db-url>")]
+  db_url:           Option<String>,
+  #[structopt(long, help = "Connect to database server on <db-host>")]
+  db_host:          Option<String>,
+  #[structopt(long, help = "Connect to database server on port <db-port>")]
+  db_port:          Option<u16>,
+  #[structopt(long, help = "Connect to database as <db-user>")]
+  db_user:          Option<String>,
+  #[structopt(
+    long,
+    help = "Install password in <db-password-file> as a systemd credential, and set it as the \
+            `quwue` role's password if the database server is local"
+  )]
+  db_password_file: Option<PathBuf>,
+  #[structopt(long, help = "Connect to database with sslmode <db-ssl-mode>")]
+  db_ssl_mode:      Option<SslMode>,
+}
+
+impl Arguments {
+  fn run(&self) {
+    if !run_output!(LogCommand, %"id --user quwue") {
+      run!(LogCommand, %"useradd --system quwue");
+    }
+
+    if self.local() {
+      Self::create_roles();
+    }
+
+    run!(
+      LogCommand, %"cargo install --root /usr/local --path tmp/quwue --force"
+    );
+
+    run!(
+      LogCommand, %"cp tmp/quwue/quwue.service /etc/systemd/system/quwue.service"
+    );
 
-  let (StdoutTrimmed(stdout), Status(status)) = run_output!(
-    LogCommand,
-    "psql",
-    "postgres",
-    "-tAc",
-    "SELECT 1 FROM pg_roles WHERE rolname='root'"
-  );
+    run!(
+      LogCommand, %"chmod 664 /etc/systemd/system/quwue.service"
+    );
 
-  if !status.success() || stdout != "1" {
-    run!(LogCommand, %"sudo -Hiu postgres createuser root");
+    if !Path::new("/etc/systemd/system/quwue.service.d/override.conf").is_file() {
+      let status = Command::new("systemctl")
+        .args(&["edit", "quwue"])
+        .status()
+        .unwrap();
+
+      if !status.success() {
+        panic!("Command `systemctl edit quwue` failed: {}", status);
+      }
+    }
+
+    self.install_password();
+
+    self.write_config();
+
+    run!(
+      LogCommand, %"systemctl daemon-reload"
+    );
+
+    run!(
+      LogCommand, %"systemctl restart quwue"
+    );
+  }
+
+  fn local(&self) -> bool {
+    self.db_url.is_none()
+      && self
+        .db_host
+        .as_deref()
+        .map_or(true, |host| host == "localhost" || host.starts_with('/'))
   }
 
-  run!(LogCommand, %"sudo -Hiu postgres psql postgres -c", "ALTER user root createdb");
+  fn create_roles() {
+    let (StdoutTrimmed(stdout), Status(status)) = run_output!(
+      LogCommand,
+      "psql",
+      "postgres",
+      "-tAc",
+      "SELECT 1 FROM pg_roles WHERE rolname='root'"
+    );
+
+    if !status.success() || stdout != "1" {
+      run!(LogCommand, %"sudo -Hiu postgres createuser root");
+    }
 
-  let (StdoutTrimmed(stdout), Status(status)) = run_output!(
-    LogCommand,
-    "psql",
-    "postgres",
-    "-tAc",
-    "SELECT 1 FROM pg_roles WHERE rolname='quwue'"
-  );
+    run!(LogCommand, %"sudo -Hiu postgres psql postgres -c", "ALTER user root createdb");
 
-  if !status.success() || stdout != "1" {
-    run!(LogCommand, %"sudo -Hiu postgres createuser quwue");
+    let (StdoutTrimmed(stdout), Status(status)) = run_output!(
+      LogCommand,
+      "psql",
+      "postgres",
+      "-tAc",
+      "SELECT 1 FROM pg_roles WHERE rolname='quwue'"
+    );
+
+    if !status.success() || stdout != "1" {
+      run!(LogCommand, %"sudo -Hiu postgres createuser quwue");
+    }
+
+    run!(LogCommand, %"sudo -Hiu postgres psql postgres -c", "ALTER user quwue createdb");
   }
 
-  run!(LogCommand, %"sudo -Hiu postgres psql postgres -c", "ALTER user quwue createdb");
+  fn install_password(&self) {
+    let source = match &self.db_password_file {
+      Some(source) => source,
+      None => {
+        if Path::new(CREDENTIALS_PATH).is_file() {
+          fs::remove_file(CREDENTIALS_PATH).unwrap();
+        }
+        return;
+      },
+    };
 
-  run!(
-    LogCommand, %"cargo install --root /usr/local --path tmp/quwue --force"
-  );
+    let password = db_url::read_password(source).unwrap();
 
-  run!(
-    LogCommand, %"cp tmp/quwue/quwue.service /etc/systemd/system/quwue.service"
-  );
+    fs::create_dir_all("/etc/quwue").unwrap();
+    fs::write(PASSWORD_PATH, format!("{}\n", password)).unwrap();
+    fs::set_permissions(PASSWORD_PATH, fs::Permissions::from_mode(0o600)).unwrap();
 
-  run!(
-    LogCommand, %"chmod 664 /etc/systemd/system/quwue.service"
-  );
+    fs::create_dir_all(Path::new(CREDENTIALS_PATH).parent().unwrap()).unwrap();
+    fs::write(
+      CREDENTIALS_PATH,
+      format!(
+        "[Service]\nLoadCredential={}:{}\n",
+        PASSWORD_CREDENTIAL, PASSWORD_PATH
+      ),
+    )
+    .unwrap();
 
-  if !Path::new("/etc/systemd/system/quwue.service.d/override.conf").is_file() {
-    let status = Command::new("systemctl")
-      .args(&["edit", "quwue"])
-      .status()
+    if self.local() {
+      // Keep the password out of the process list
+      let mut child = Command::new("sudo")
+        .args(&["-Hiu", "postgres", "psql", "postgres", "--quiet"])
+        .stdin(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+      writeln!(
+        child.stdin.take().unwrap(),
+        "ALTER ROLE quwue PASSWORD '{}';",
+        password.replace('\'', "''")
+      )
       .unwrap();
 
-    if !status.success() {
-      panic!("Command `systemctl edit quwue` failed: {}", status);
+      let status = child.wait().unwrap();
+
+      if !status.success() {
+        panic!("Setting `quwue` role password failed: {}", status);
+      }
     }
   }
 
-  run!(
-    LogCommand, %"systemctl daemon-reload"
-  );
+  fn write_config(&self) {
+    let mut database = Table::new();
 
-  run!(
-    LogCommand, %"systemctl restart quwue"
-  );
+    match &self.db_url {
+      Some(url) => {
+        database.insert("url".into(), Value::String(url.clone()));
+      },
+      None => {
+        database.insert("name".into(), Value::String("quwue".into()));
+
+        if let Some(host) = &self.db_host {
+          database.insert("host".into(), Value::String(host.clone()));
+        }
+
+        if let Some(port) = self.db_port {
+          database.insert("port".into(), Value::Integer(port.into()));
+        }
+
+        if let Some(ssl_mode) = self.db_ssl_mode {
+          database.insert("ssl_mode".into(), Value::String(ssl_mode.to_string()));
+        }
+
+        // Requires `trust` authentication
+        match &self.db_user {
+          Some(user) => {
+            database.insert("user".into(), Value::String(user.clone()));
+          },
+          None if self.db_password_file.is_some() => {
+            database.insert("user".into(), Value::String("quwue".into()));
+          },
+          None => {},
+        }
+      },
+    }
+
+    if self.db_password_file.is_some() {
+      database.insert(
+        "password_credential".into(),
+        Value::String(PASSWORD_CREDENTIAL.into()),
+      );
+    }
+
+    // Other settings are kept, although comments are not
+    let mut config = match fs::read_to_string(CONFIG_PATH) {
+      Ok(existing) => toml::from_str::<Table>(&existing)
+        .unwrap_or_else(|err| panic!("Parsing `{}` failed: {}", CONFIG_PATH, err)),
+      Err(err) if err.kind() == io::ErrorKind::NotFound => Table::new(),
+      Err(err) => panic!("Reading `{}` failed: {}", CONFIG_PATH, err),
+    };
+
+    config.insert("database".into(), Value::Table(database));
+
+    fs::create_dir_all("/etc/quwue").unwrap();
+    fs::write(CONFIG_PATH, toml::to_string(&Value::Table(config)).unwrap()).unwrap();
+  }
+}
+
+fn main() {
+  Arguments::from_args().run();
 }
@@ -0,0 +1,398 @@
+use {
+  db::{Clock, Db, Interrupt, PoolOptions, Ranking, UpdateTx},
+  model::{Action, Emoji, Interests, MessageId, Prompt, Response, UserId},
+  rand::{rngs::StdRng, Rng, SeedableRng},
+  std::{
+    convert::TryFrom,
+    time::{Instant, SystemTime},
+  },
+  structopt::StructOpt,
+};
+
+#[derive(StructOpt)]
+#[structopt(
+  about = "Run synthetic users through the matching flow and report how fairly candidates are \
+           shown, and how fast"
+)]
+struct Arguments {
+  #[structopt(long, default_value = "1000", help = "Simulate <users> users")]
+  users:      usize,
+  #[structopt(
+    long,
+    default_value = "20",
+    help = "Send an average of <responses> responses per user"
+  )]
+  responses:  usize,
+  #[structopt(
+    long,
+    default_value = "0.2",
+    help = "Give each user a probability of accepting candidates of at least <accept-min>"
+  )]
+  accept_min: f64,
+  #[structopt(
+    long,
+    default_value = "0.8",
+    help = "Give each user a probability of accepting candidates of at most <accept-max>"
+  )]
+  accept_max: f64,
+  #[structopt(long, default_value = "0", help = "Seed random choices with <seed>")]
+  seed:       u64,
+  #[structopt(
+    long,
+    default_value = "60",
+    help = "Advance the simulated clock by <interval> seconds per response"
+  )]
+  interval:   f64,
+  #[structopt(
+    long,
+    help = "Run against the empty database at <db-url>, instead of a fresh local Postgres database"
+  )]
+  db_url:     Option<String>,
+  #[structopt(
+    long,
+    conflicts_with = "db_url",
+    help = "Run against an in-memory database"
+  )]
+  memory:     bool,
+  #[structopt(long, help = "Only print summary statistics, not per-user statistics")]
+  summary:    bool,
+}
+
+impl Arguments {
+  fn run(self) {
+    assert!(
+      0.0 <= self.accept_min && self.accept_min <= self.accept_max && self.accept_max <= 1.0,
+      "Accept probabilities must satisfy 0 <= <accept-min> <= <accept-max> <= 1"
+    );
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+      .enable_all()
+      .build()
+      .unwrap();
+
+    runtime.block_on(async {
+      let db = if self.memory {
+        Db::memory()
+      } else {
+        let url = self.db_url.clone().unwrap_or_else(|| {
+          db_url::db_url(&format!(
+            "quwue-simulate-{}",
+            SystemTime::now()
+              .duration_since(SystemTime::UNIX_EPOCH)
+              .unwrap()
+              .as_millis()
+          ))
+        });
+
+        Db::connect_url(&url, PoolOptions::default()).await.unwrap()
+      };
+
+      let mut rng = StdRng::seed_from_u64(self.seed);
+
+      let users = (0..self.users)
+        .map(|_| Synthetic {
+          accept_probability: rng.gen_range(self.accept_min..=self.accept_max),
+          exposure:           0,
+          matches:            0,
+          prompt:             None,
+        })
+        .collect();
+
+      let mut simulation = Simulation {
+        clock: 0.0,
+        interval: self.interval,
+        message_id: 0,
+        queries: 0,
+        seed: self.seed,
+        db,
+        rng,
+        users,
+      };
+
+      let start = Instant::now();
+
+      simulation.run(self.responses).await;
+
+      simulation.report(start.elapsed().as_secs_f64(), self.summary);
+    });
+  }
+}
+
+/// Users are identified by their index plus one
+struct Synthetic {
+  accept_probability: f64,
+  exposure:           u64,
+  matches:            u64,
+  prompt:             Option<Prompt>,
+}
+
+struct Simulation {
+  /// Simulated seconds
+  clock:      f64,
+  db:         Db,
+  interval:   f64,
+  message_id: u64,
+  queries:    u64,
+  rng:        StdRng,
+  seed:       u64,
+  users:      Vec<Synthetic>,
+}
+
+impl Simulation {
+  fn user_id(index: usize) -> UserId {
+    UserId(u64::try_from(index).unwrap() + 1)
+  }
+
+  fn index(user_id: UserId) -> usize {
+    usize::try_from(user_id.0 - 1).unwrap()
+  }
+
+  fn db(&self) -> Db {
+    self
+      .db
+      .clone()
+      .with_ranking(Ranking::new(self.seed))
+      .with_clock(Clock::Fixed(self.clock))
+  }
+
+  /// Users sign up over the first half of the simulation
+  async fn run(&mut self, responses: usize) {
+    let steps = self.users.len() * responses;
+    let signup_interval = (responses / 2).max(1);
+
+    let mut signed_up = 0;
+
+    for step in 0..steps {
+      self.clock += self.interval;
+
+      if signed_up < self.users.len() && step % signup_interval == 0 {
+        self.sign_up(signed_up).await;
+        signed_up += 1;
+        continue;
+      }
+
+      let index = self.rng.gen_range(0..signed_up);
+
+      let response = match self.users[index].prompt {
+        Some(Prompt::Candidate { .. }) =>
+          if self.rng.gen_bool(self.users[index].accept_probability) {
+            Response::Reaction(Emoji::ThumbsUp)
+          } else {
+            Response::Reaction(Emoji::ThumbsDown)
+          },
+        Some(Prompt::Match { .. }) => Response::Reaction(Emoji::ThumbsUp),
+        _ => Response::message("Any new matches?"),
+      };
+
+      self.respond(Self::user_id(index), &response).await;
+    }
+  }
+
+  async fn sign_up(&mut self, index: usize) {
+    let user_id = Self::user_id(index);
+
+    self.respond(user_id, &Response::message("Hi!")).await;
+    self.respond(user_id, &Response::message("ok")).await;
+    self
+      .respond(
+        user_id,
+        &Response::message(format!("Synthetic user {}", index)),
+      )
+      .await;
+
+    let tags = (0..3)
+      .map(|_| self.rng.gen_range(1..=Interests::CURATED.len()).to_string())
+      .collect::<Vec<String>>();
+
+    self
+      .respond(user_id, &Response::message(tags.join(", ")))
+      .await;
+  }
+
+  async fn respond(&mut self, user_id: UserId, response: &Response) {
+    let db = self.db();
+
+    let user = db.user(user_id).await.unwrap();
+
+    let update = user.update(response);
+
+    let tx = db.prepare(user_id, &update).await.unwrap();
+
+    self.queries += 2;
+
+    self.commit(user_id, tx).await;
+
+    if let Some(Action::AcceptCandidate { id } | Action::SuperLikeCandidate { id }) = update.action
+    {
+      let interrupt = Interrupt {
+        channel_id: None,
+        candidate_id: id,
+        user_id,
+      };
+
+      db.enqueue_interrupt(0, interrupt).await.unwrap();
+
+      let prepared = db.prepare_interrupt(0..1).await.unwrap();
+
+      self.queries += 2;
+
+      if let Some((_, Some(tx))) = prepared {
+        self.commit(id, tx).await;
+      }
+    }
+  }
+
+  async fn commit(&mut self, recipient_id: UserId, tx: UpdateTx) {
+    let prompt = tx.prompt();
+
+    match prompt {
+      Prompt::Candidate { id } => self.users[Self::index(id)].exposure += 1,
+      Prompt::Match { .. } => self.users[Self::index(recipient_id)].matches += 1,
+      Prompt::Welcome
+      | Prompt::Bio
+      | Prompt::Tags
+      | Prompt::Quiescent
+      | Prompt::Age
+      | Prompt::AgeRange
+      | Prompt::UtcOffset
+      | Prompt::TimezoneWindow
+      | Prompt::Languages
+      | Prompt::Connections
+      | Prompt::QuestionChoice { .. }
+      | Prompt::QuestionAccepted { .. }
+      | Prompt::QuestionImportance { .. } => {},
+    }
+
+    self.users[Self::index(recipient_id)].prompt = Some(prompt);
+
+    self.message_id += 1;
+
+    tx.commit(MessageId(self.message_id)).await.unwrap();
+
+    self.queries += 1;
+  }
+
+  #[allow(clippy::cast_precision_loss)]
+  fn report(&self, seconds: f64, summary: bool) {
+    if !summary {
+      println!("user\taccept\texposure\tmatches");
+      for (index, user) in self.users.iter().enumerate() {
+        println!(
+          "{}\t{:.2}\t{}\t{}",
+          Self::user_id(index),
+          user.accept_probability,
+          user.exposure,
+          user.matches
+        );
+      }
+      println!();
+    }
+
+    let mut exposure = self
+      .users
+      .iter()
+      .map(|user| user.exposure)
+      .collect::<Vec<u64>>();
+
+    exposure.sort_unstable();
+
+    let matches = self.users.iter().map(|user| user.matches).sum::<u64>();
+
+    println!("users:            {}", self.users.len());
+    println!(
+      "never shown:      {}",
+      exposure.iter().filter(|exposure| **exposure == 0).count()
+    );
+    if let (Some(min), Some(max)) = (exposure.first(), exposure.last()) {
+      println!("exposure min:     {}", min);
+      println!("exposure median:  {}", exposure[exposure.len() / 2]);
+      println!("exposure max:     {}", max);
+    }
+    println!("exposure gini:    {:.3}", gini(&exposure));
+    println!("matches shown:    {}", matches);
+    println!("queries:          {}", self.queries);
+    println!("seconds:          {:.3}", seconds);
+    println!("queries/second:   {:.1}", self.queries as f64 / seconds);
+  }
+}
+
+/// `values` must be sorted in ascending order
+#[allow(clippy::cast_precision_loss)]
+fn gini(values: &[u64]) -> f64 {
+  let total = values.iter().sum::<u64>();
+
+  if total == 0 {
+    return 0.0;
+  }
+
+  let n = values.len() as f64;
+
+  let weighted = values
+    .iter()
+    .zip(1..)
+    .map(|(value, rank)| (rank * *value) as f64)
+    .sum::<f64>();
+
+  2.0 * weighted / (n * total as f64) - (n + 1.0) / n
+}
+
+fn main() {
+  Arguments::from_args().run();
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn gini_of_equal_values_is_zero() {
+    assert!(gini(&[3, 3, 3, 3]).abs() < 1e-9);
+    assert!(gini(&[0, 0]).abs() < 1e-9);
+  }
+
+  #[test]
+  fn gini_of_single_nonzero_value() {
+    assert!((gini(&[0, 0, 0, 4]) - 0.75).abs() < 1e-9);
+  }
+
+  #[test]
+  fn simulation_is_deterministic() {
+    let run = || {
+      tokio::runtime::Builder::new_multi_thread()
+        .build()
+        .unwrap()
+        .block_on(async {
+          let mut simulation = Simulation {
+            clock:      0.0,
+            db:         Db::memory(),
+            interval:   60.0,
+            message_id: 0,
+            queries:    0,
+            rng:        StdRng::seed_from_u64(0),
+            seed:       0,
+            users:      (0..10)
+              .map(|_| Synthetic {
+                accept_probability: 0.5,
+                exposure:           0,
+                matches:            0,
+                prompt:             None,
+              })
+              .collect(),
+          };
+
+          simulation.run(10).await;
+
+          simulation
+            .users
+            .iter()
+            .map(|user| (user.exposure, user.matches))
+            .collect::<Vec<(u64, u64)>>()
+        })
+    };
+
+    let exposure = run();
+
+    assert!(exposure.iter().any(|(exposure, _)| *exposure > 0));
+    assert_eq!(exposure, run());
+  }
+}
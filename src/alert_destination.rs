@@ -0,0 +1,101 @@
+use crate::common::*;
+
+/// Where summaries of internal errors are posted
+#[derive(Debug)]
+pub(crate) enum AlertDestination {
+  Channel {
+    client:     Client,
+    channel_id: ChannelId,
+  },
+  /// A webhook that accepts a JSON body with the message in `content`, like
+  /// Discord's
+  Webhook {
+    client: reqwest::Client,
+    url:    Url,
+  },
+}
+
+impl AlertDestination {
+  pub(crate) async fn send(&self, content: &str) -> Result<()> {
+    match self {
+      Self::Channel { client, channel_id } => {
+        client
+          .create_message(channel_id.discord())
+          .content(content)?
+          .exec()
+          .await?;
+      },
+      Self::Webhook { client, url } => {
+        client
+          .post(url.clone())
+          .json(&json!({ "content": content }))
+          .send()
+          .await
+          .and_then(reqwest::Response::error_for_status)
+          .context(error::AlertWebhook { url: url.clone() })?;
+      },
+    }
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  use {
+    hyper::{
+      body,
+      service::{make_service_fn, service_fn},
+      Body, Request, Server,
+    },
+    std::{convert::Infallible, net::TcpListener},
+  };
+
+  #[tokio::test]
+  async fn webhooks_are_sent_content() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    let url = format!("http://{}/hook", listener.local_addr().unwrap())
+      .parse()
+      .unwrap();
+
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    let server = Server::from_tcp(listener)
+      .unwrap()
+      .serve(make_service_fn(move |_| {
+        let sender = sender.clone();
+        async move {
+          Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+            let sender = sender.clone();
+            async move {
+              let path = request.uri().path().to_owned();
+              let body = body::to_bytes(request.into_body()).await.unwrap();
+              sender
+                .send((path, serde_json::from_slice::<Value>(&body).unwrap()))
+                .unwrap();
+              Ok::<_, Infallible>(hyper::Response::new(Body::empty()))
+            }
+          }))
+        }
+      }));
+
+    tokio::spawn(server);
+
+    AlertDestination::Webhook {
+      client: reqwest::Client::new(),
+      url,
+    }
+    .send("Something broke")
+    .await
+    .unwrap();
+
+    assert_eq!(
+      receiver.recv().await.unwrap(),
+      ("/hook".into(), json!({ "content": "Something broke" }))
+    );
+  }
+}
@@ -3,7 +3,149 @@ use crate::common::*;
 #[derive(StructOpt)]
 pub(crate) struct Arguments {
   #[structopt(long)]
-  pub(crate) db_name: String,
+  pub(crate) alert_channel:          Option<u64>,
   #[structopt(long)]
-  pub(crate) log_dir: Option<PathBuf>,
+  pub(crate) config:                 Option<PathBuf>,
+  #[structopt(long)]
+  pub(crate) db_host:                Option<String>,
+  #[structopt(long)]
+  pub(crate) db_name:                Option<String>,
+  #[structopt(long)]
+  pub(crate) db_password_credential: Option<String>,
+  #[structopt(long)]
+  pub(crate) db_password_file:       Option<PathBuf>,
+  #[structopt(long)]
+  pub(crate) db_port:                Option<u16>,
+  #[structopt(long)]
+  pub(crate) db_ssl_mode:            Option<SslMode>,
+  #[structopt(long)]
+  pub(crate) db_ssl_root_cert:       Option<PathBuf>,
+  #[structopt(long)]
+  pub(crate) db_url:                 Option<String>,
+  #[structopt(long)]
+  pub(crate) db_user:                Option<String>,
+  #[structopt(long)]
+  pub(crate) instance:               Option<String>,
+  #[structopt(long)]
+  pub(crate) log_dir:                Option<PathBuf>,
+  #[structopt(long, default_value = "1")]
+  pub(crate) shards:                 u64,
+  #[structopt(subcommand)]
+  pub(crate) subcommand:             Option<Subcommand>,
+}
+
+impl Arguments {
+  pub(crate) fn config(&self) -> Result<Config> {
+    let mut config = match &self.config {
+      Some(path) => Config::load(path)?,
+      None => Config::default(),
+    };
+
+    if let Some(alert_channel) = self.alert_channel {
+      config.alert_channel = Some(alert_channel);
+    }
+
+    self.override_database(&mut config.database);
+
+    config.validate()?;
+
+    Ok(config)
+  }
+
+  /// A URL replaces discrete options from the config file, and discrete
+  /// options replace a URL
+  fn override_database(&self, database: &mut DatabaseConfig) {
+    if self.db_url.is_some() {
+      database.clear_discrete_options();
+      database.url = self.db_url.clone();
+    }
+
+    if self.db_host.is_some()
+      || self.db_name.is_some()
+      || self.db_port.is_some()
+      || self.db_ssl_mode.is_some()
+      || self.db_ssl_root_cert.is_some()
+      || self.db_user.is_some()
+    {
+      database.url = None;
+    }
+
+    if let Some(host) = &self.db_host {
+      database.host = Some(host.clone());
+    }
+
+    if let Some(name) = &self.db_name {
+      database.name = Some(name.clone());
+    }
+
+    if let Some(port) = self.db_port {
+      database.port = Some(port);
+    }
+
+    if let Some(ssl_mode) = self.db_ssl_mode {
+      database.ssl_mode = Some(ssl_mode);
+    }
+
+    if let Some(ssl_root_cert) = &self.db_ssl_root_cert {
+      database.ssl_root_cert = Some(ssl_root_cert.clone());
+    }
+
+    if let Some(user) = &self.db_user {
+      database.user = Some(user.clone());
+    }
+
+    if self.db_password_file.is_some() || self.db_password_credential.is_some() {
+      database.password_file = self.db_password_file.clone();
+      database.password_credential = self.db_password_credential.clone();
+    }
+  }
+
+  pub(crate) fn instance(&self) -> String {
+    self.instance.clone().unwrap_or_else(|| {
+      let host = env::var("HOSTNAME").unwrap_or_else(|_| "localhost".into());
+      format!("{}:{}", host, process::id())
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn database(args: &[&str], config: &str) -> DatabaseConfig {
+    let arguments = Arguments::from_iter(std::iter::once("quwue").chain(args.iter().copied()));
+    let mut database = toml::from_str::<Config>(config).unwrap().database;
+    arguments.override_database(&mut database);
+    database
+  }
+
+  #[test]
+  fn db_name() {
+    assert_eq!(
+      database(&["--db-name", "quwue"], "").url().unwrap(),
+      "postgresql://localhost/quwue"
+    );
+  }
+
+  #[test]
+  fn command_line_url_replaces_discrete_options() {
+    let database = database(
+      &["--db-url", "postgresql://db.example.com/quwue"],
+      "[database]\nname = \"other\"\nuser = \"quwue\"",
+    );
+    assert!(!database.has_discrete_options());
+    assert_eq!(database.url().unwrap(), "postgresql://db.example.com/quwue");
+  }
+
+  #[test]
+  fn command_line_options_replace_url() {
+    let database = database(
+      &["--db-name", "quwue", "--db-ssl-mode", "require"],
+      "[database]\nurl = \"postgresql://db.example.com/other\"",
+    );
+    assert_eq!(
+      database.url().unwrap(),
+      "postgresql://localhost/quwue?sslmode=require"
+    );
+  }
 }
@@ -0,0 +1,73 @@
+use crate::common::*;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct Assignment {
+  pub(crate) shards: Range<u64>,
+  pub(crate) total:  u64,
+}
+
+impl Assignment {
+  /// Senior instances receive any extra shards, so with more instances than
+  /// shards the most junior instances wait on standby
+  pub(crate) fn new(index: u64, instances: u64, total: u64) -> Self {
+    let boundary = |index: u64| (index * total + instances - 1) / instances;
+
+    Self {
+      shards: boundary(index)..boundary(index + 1),
+      total,
+    }
+  }
+
+  pub(crate) fn contains(&self, shard: u64) -> bool {
+    self.shards.contains(&shard)
+  }
+
+  /// Users are partitioned by the timestamp bits of their ID, which spreads
+  /// them evenly
+  pub(crate) fn shard_for(&self, user_id: UserId) -> u64 {
+    (user_id.0 >> 22) % self.total
+  }
+
+  pub(crate) fn scheme(&self) -> ShardScheme {
+    ShardScheme::Range {
+      from:  self.shards.start,
+      to:    self.shards.end - 1,
+      total: self.total,
+    }
+  }
+}
+
+impl Display for Assignment {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "shards {}..{} of {}",
+      self.shards.start, self.shards.end, self.total
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn new() {
+    assert_eq!(Assignment::new(0, 1, 1).shards, 0..1);
+    assert_eq!(Assignment::new(0, 2, 1).shards, 0..1);
+    assert_eq!(Assignment::new(1, 2, 1).shards, 1..1);
+    assert_eq!(Assignment::new(0, 3, 8).shards, 0..3);
+    assert_eq!(Assignment::new(1, 3, 8).shards, 3..6);
+    assert_eq!(Assignment::new(2, 3, 8).shards, 6..8);
+    assert_eq!(Assignment::new(0, 2, 4).shards, 0..2);
+    assert_eq!(Assignment::new(1, 2, 4).shards, 2..4);
+  }
+
+  #[test]
+  fn shard_for() {
+    let assignment = Assignment::new(0, 1, 4);
+    assert_eq!(assignment.shard_for(UserId(0)), 0);
+    assert_eq!(assignment.shard_for(UserId(3 << 22)), 3);
+    assert_eq!(assignment.shard_for(UserId(5 << 22)), 1);
+  }
+}
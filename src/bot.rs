@@ -1,15 +1,5 @@
 use crate::common::*;
 
-async_static! {
-  test_cluster,
-  (Cluster, Arc<Mutex<Events>>),
-  {
-    Bot::initialize_cluster(true)
-      .await
-      .expect("Failed to initialize test cluster")
-  }
-}
-
 #[derive(Clone, Debug)]
 pub(crate) struct Bot {
   inner: Arc<Inner>,
@@ -17,12 +7,13 @@ pub(crate) struct Bot {
 
 #[derive(Debug)]
 pub(crate) struct Inner {
-  cache:   InMemoryCache,
-  cluster: Cluster,
-  db:      Db,
-  events:  Arc<Mutex<Events>>,
-  test_id: Option<TestId>,
-  user:    twilight_model::user::User,
+  assignment: Assignment,
+  config:     Config,
+  db:         Db,
+  error_sink: Option<ErrorSink>,
+  interrupts: Notify,
+  test_id:    Option<TestId>,
+  transports: Vec<Arc<dyn Transport>>,
 }
 
 impl Deref for Bot {
@@ -37,11 +28,105 @@ impl Bot {
   pub(crate) fn main() -> Result<()> {
     let arguments = Arguments::from_args();
 
+    if let Some(subcommand) = &arguments.subcommand {
+      return subcommand.run(&arguments);
+    }
+
+    let config = arguments.config()?;
+
     logging::init(arguments.log_dir.as_deref());
 
-    let runtime = runtime::init()?;
+    let runtime = runtime::init(config.runtime.worker_threads)?;
+
+    runtime.block_on(Self::serve(&arguments, config))?;
+
+    Ok(())
+  }
+
+  pub(crate) fn repl(arguments: &Arguments) -> Result<()> {
+    let config = arguments.config()?;
+
+    let runtime = runtime::init(config.runtime.worker_threads)?;
+
+    runtime.block_on(async {
+      let bot = Self::new(
+        Db::memory(),
+        Assignment::new(0, 1, 1),
+        config,
+        None,
+        vec![Arc::new(TerminalTransport::stdio())],
+        None,
+      );
+
+      bot.run().await
+    })
+  }
+
+  async fn serve(arguments: &Arguments, config: Config) -> Result<()> {
+    let db = Db::connect_url(&config.database_url()?, config.database.pool_options()).await?;
+
+    let instance = arguments.instance();
+
+    let mut coordinator = Coordinator::start(db.clone(), instance.clone(), arguments.shards);
+
+    let destinations = config.alert_destinations()?;
+
+    let error_sink = if destinations.is_empty() {
+      None
+    } else {
+      Some(ErrorSink::start(destinations))
+    };
+
+    loop {
+      let assignment = coordinator.assignment().await?;
+
+      let mut transports: Vec<Arc<dyn Transport>> = vec![Arc::new(
+        DiscordTransport::new(&config, &assignment, None).await?,
+      )];
+
+      // Matrix has no shards, so the instance that handles shard 0 receives
+      // all Matrix messages
+      if let Some(matrix) = &config.matrix {
+        transports.push(Arc::new(
+          MatrixTransport::new(db.clone(), matrix, assignment.contains(0)).await?,
+        ));
+      }
+
+      let bot = Self::new(
+        db.clone(),
+        assignment.clone(),
+        config.clone(),
+        error_sink.clone(),
+        transports,
+        None,
+      );
+
+      let handle = bot.clone();
+
+      let run = async move {
+        if bot.config.features.catch_up && bot.assignment.contains(0) {
+          bot.catch_up().await?;
+        }
+        bot.run().await
+      };
+
+      tokio::select! {
+        result = run => {
+          result?;
+          break;
+        },
+        result = coordinator.changed(&assignment) => {
+          result?;
+          info!("Shard assignment changed, stopping transports.");
+          for transport in &handle.transports {
+            transport.stop();
+          }
+          coordinator.stopped()?;
+        },
+      }
+    }
 
-    runtime.block_on(async { Self::new(&arguments.db_name, None).await?.run().await })?;
+    db.release_leases(&instance).await?;
 
     Ok(())
   }
@@ -50,19 +135,52 @@ impl Bot {
     self.test_id.is_some()
   }
 
+  fn transport(&self, user_id: UserId) -> Result<&dyn Transport> {
+    self
+      .transports
+      .iter()
+      .find(|transport| transport.owns(user_id))
+      .map(Arc::as_ref)
+      .ok_or(Error::TransportUnavailable { user_id })
+  }
+
   pub(crate) async fn run(self) -> Result<()> {
+    tokio::select! {
+      result = self.process_events() => result,
+      result = self.process_interrupts() => result,
+      result = self.prune_events() => result,
+    }
+  }
+
+  async fn process_events(&self) -> Result<()> {
     info!("Starting run loop.");
 
-    let mut events = self.events.lock().await;
+    future::select_all(
+      self
+        .transports
+        .iter()
+        .map(|transport| Box::pin(self.process_transport_events(transport.as_ref()))),
+    )
+    .await;
+
+    Ok(())
+  }
 
-    while let Some((shard_id, event)) = events.next().await {
+  async fn process_transport_events(&self, transport: &dyn Transport) {
+    while let Some(received) = transport.receive().await {
       let clone = self.clone();
       let handle = tokio::spawn(async move {
-        if let Err(err) = clone.handle_event(shard_id, event).await {
+        let result = match received {
+          Ok(incoming) => clone.clone().handle_incoming(incoming).await,
+          Err(err) => Err(err),
+        };
+
+        if let Err(err) = result {
           if cfg!(test) {
             panic!("Error handling event: {}", err);
           } else {
             error!("Error handling event: {}", err);
+            clone.report_error(&err, None, None);
           }
         }
       });
@@ -75,145 +193,224 @@ impl Bot {
         }
       }
     }
+  }
 
-    Ok(())
+  async fn process_interrupts(&self) -> Result<()> {
+    const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+    loop {
+      let delivered = match self.deliver_interrupt().await {
+        Ok(delivered) => delivered,
+        Err(err) => {
+          if cfg!(test) {
+            panic!("Error delivering interrupt: {}", err);
+          } else {
+            error!("Error delivering interrupt: {}", err);
+            self.report_error(&err, None, None);
+          }
+          false
+        },
+      };
+
+      if !delivered {
+        tokio::time::timeout(POLL_INTERVAL, self.interrupts.notified())
+          .await
+          .ok();
+      }
+    }
+  }
+
+  async fn prune_events(&self) -> Result<()> {
+    const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    loop {
+      match self.db.prune_events().await {
+        Ok(pruned) => info!("Pruned {} processed events.", pruned),
+        Err(err) => {
+          let err = Error::from(err);
+          error!("Error pruning processed events: {}", err);
+          self.report_error(&err, None, None);
+        },
+      }
+
+      tokio::time::sleep(PRUNE_INTERVAL).await;
+    }
   }
 
-  async fn handle_event(self, _shard_id: u64, event: Event) -> Result<()> {
-    info!("Quwue received event: {:?}", event.kind());
-
-    self.cache.update(&event);
-
-    let (channel_id, result) = match event {
-      Event::MessageCreate(message_create) => (
-        message_create.channel_id,
-        self.handle_message_create(*message_create).await,
-      ),
-      Event::ReactionAdd(reaction_add) => (
-        reaction_add.channel_id,
-        self.handle_reaction_add(*reaction_add).await,
-      ),
-      _ => return Err(Error::UnexpectedEvent { event }),
+  /// Returns `false` if there were no interrupts
+  async fn deliver_interrupt(&self) -> Result<bool> {
+    let (interrupt, tx) = match self
+      .db
+      .prepare_interrupt(self.assignment.shards.clone())
+      .await?
+    {
+      Some(prepared) => prepared,
+      None => return Ok(false),
     };
 
-    if let Err(err) = result {
-      eprintln!("Error handling event: {}", err);
+    if let Some(tx) = tx {
+      let Interrupt {
+        candidate_id,
+        channel_id,
+        ..
+      } = interrupt;
 
-      self
-        .client()
-        .create_message(channel_id)
-        .content(&format!(
-          "Internal error: {}\n\nThis is a bug in Quwue.",
-          err.user_facing_message()
-        ))?
-        .exec()
-        .await?;
+      let channel_id = match channel_id {
+        Some(channel_id) => channel_id,
+        None =>
+          self
+            .transport(candidate_id)?
+            .private_channel(candidate_id)
+            .await?,
+      };
+
+      self.send_prompt(tx, channel_id, candidate_id).await?;
     }
 
-    Ok(())
+    Ok(true)
   }
 
-  async fn handle_reaction_add(&self, reaction_add: ReactionAdd) -> Result<()> {
-    let ReactionAdd(reaction) = reaction_add;
-
-    let bot = self
-      .client()
-      .user(reaction.user_id)
-      .exec()
-      .await?
-      .model()
-      .await?
-      .bot;
-
-    let user_id = if self.is_test() {
-      let message = self
-        .client()
-        .message(reaction.channel_id, reaction.message_id)
-        .exec()
-        .await?
-        .model()
-        .await?;
+  async fn catch_up(&self) -> Result<()> {
+    info!("Catching up on missed direct messages.");
 
-      let test_message =
-        TestMessage::parse(&message.content).expect("failed to parse reaction message");
+    for (user_id, prompt_message) in self.db.prompt_messages().await? {
+      if let Err(err) = self.catch_up_user(user_id, prompt_message).await {
+        warn!("Failed to catch up on messages from {}: {}", user_id, err);
+      }
+    }
 
-      test_message.test_user_id().to_discord_user_id()
-    } else {
-      reaction.user_id
-    };
+    info!("Caught up on missed direct messages.");
 
-    let user = self.db.user(user_id).await?;
+    Ok(())
+  }
 
-    if user
-      .prompt_message
-      .map(|prompt_message| prompt_message.message_id != reaction.message_id)
-      .unwrap_or_default()
+  async fn catch_up_user(&self, user_id: UserId, prompt_message: PromptMessage) -> Result<()> {
+    for incoming in self
+      .transport(user_id)?
+      .missed_responses(user_id, prompt_message)
+      .await?
     {
-      return Ok(());
+      self.clone().handle_incoming(incoming).await?;
     }
 
-    let response = match reaction.emoji {
-      ReactionType::Unicode { name } => Response::unicode_reaction(name),
-      ReactionType::Custom { id, .. } => Response::custom_reaction(id),
-    };
-
-    self
-      .handle_response(bot, reaction.user_id, user, reaction.channel_id, response)
-      .await?;
-
     Ok(())
   }
 
-  async fn handle_message_create(&self, message: MessageCreate) -> Result<()> {
-    let (sender_id, user_id, content) = if let Some(test_id) = &self.test_id {
-      match test_id.filter(message.content.as_str()) {
-        Some(test_message) => (
-          message.author.id,
-          test_message.test_user_id().to_discord_user_id(),
-          test_message.text,
-        ),
-        None => return Ok(()),
-      }
-    } else {
-      (
-        message.author.id,
-        message.author.id,
-        message.content.clone(),
-      )
-    };
+  async fn handle_incoming(self, incoming: Incoming) -> Result<()> {
+    let channel_id = incoming.channel_id;
+    let user_id = incoming.sender;
 
-    let response = Response::message(content);
+    let result = self.handle_response(incoming).await;
 
-    let user = self.db.user(user_id).await?;
+    if let Err(err) = result {
+      let content = if err.is_user_error() {
+        info!("User error handling event: {}", err);
+        err.user_facing_message()
+      } else {
+        let reference = ErrorReference::new();
+
+        error!("Internal error {} handling event: {}", reference, err);
+
+        if let Err(db_error) = self
+          .db
+          .record_error(
+            reference.as_str(),
+            err.variant(),
+            &err.to_string(),
+            Some(user_id),
+          )
+          .await
+        {
+          error!(
+            "Failed to record internal error {}: {}",
+            reference, db_error
+          );
+        }
 
-    self
-      .handle_response(
-        message.author.bot,
-        sender_id,
-        user,
-        message.channel_id,
-        response,
-      )
-      .await?;
+        if self.error_sink.is_some() {
+          let prompt = match self.db.current_prompt(user_id).await {
+            Ok(prompt) => prompt.map(Prompt::discriminant),
+            Err(db_error) => {
+              error!(
+                "Failed to load prompt for internal error {}: {}",
+                reference, db_error
+              );
+              None
+            },
+          };
+
+          self.report_error(&err, prompt, Some(&reference));
+        }
+
+        format!(
+          concat!(
+            "Internal error: {}\n\n",
+            "This is a bug in Quwue. ",
+            "If you report it, please include the reference code `{}`.",
+          ),
+          err.user_facing_message(),
+          reference,
+        )
+      };
+
+      self
+        .transport(user_id)?
+        .send_message(channel_id, &content)
+        .await?;
+    }
 
     Ok(())
   }
 
-  async fn handle_response(
+  fn report_error(
     &self,
-    bot: bool,
-    sender: UserId,
-    user: User,
-    channel_id: ChannelId,
-    response: Response,
-  ) -> Result<()> {
+    error: &Error,
+    prompt: Option<PromptDiscriminant>,
+    reference: Option<&ErrorReference>,
+  ) {
+    if let Some(error_sink) = &self.error_sink {
+      error_sink.report(error, prompt, reference);
+    }
+  }
+
+  async fn handle_response(&self, incoming: Incoming) -> Result<()> {
+    let Incoming {
+      bot,
+      channel_id,
+      event,
+      message_id,
+      response,
+      sender,
+      user_id,
+    } = incoming;
+
     info!("Received response: {:?}", response);
 
-    if sender == self.user.id {
+    let transport = self.transport(sender)?;
+
+    if sender == transport.user_id() {
       info!("Ignoring message from self.");
       return Ok(());
     }
 
+    let user = self.db.user_in_pool(user_id, transport.pool()).await?;
+
+    // Reactions only count if they're to the user's current prompt
+    if let Some(message_id) = message_id {
+      if user
+        .prompt_message
+        .map(|prompt_message| prompt_message.message_id != message_id)
+        .unwrap_or_default()
+      {
+        return Ok(());
+      }
+    }
+
+    let bot = match bot {
+      Some(bot) => bot,
+      None => transport.is_bot(sender).await?,
+    };
+
     if bot {
       if self.is_test() {
         info!("Processing message from bot.");
@@ -222,7 +419,7 @@ impl Bot {
       }
     }
 
-    if !self.is_private_channel(channel_id).await? {
+    if !transport.is_private_channel(channel_id).await? {
       if self.is_test() {
         info!("Processing public channel message.");
       } else {
@@ -230,34 +427,58 @@ impl Bot {
       }
     }
 
+    // Custom emoji are only understood if they stand in for one we know
+    let response = match response {
+      Response::Custom(id) => match self.config.custom_emoji(&id) {
+        Some(emoji) => Response::Reaction(emoji),
+        None => Response::Custom(id),
+      },
+      response => response,
+    };
+
     let update = user.update(&response);
 
-    let user_id = user.discord_id;
+    let tx = if let Some(tx) = self.db.prepare_event(&event, user_id, &update).await? {
+      tx
+    } else {
+      info!("Ignoring already processed event: {}", event);
+      return Ok(());
+    };
 
-    let tx = self.db.prepare(user_id, &update).await?;
+    let retracted = tx.retracted();
 
-    self.send_prompt(tx, channel_id, user_id).await?;
+    let pick = user.prompt_message.map_or(false, |prompt_message| {
+      User::is_pick(prompt_message.prompt, &response) && tx.prompt() == prompt_message.prompt
+    });
 
-    if let Some(Action::AcceptCandidate { id: candidate_id }) = update.action {
-      if let Some(tx) = self
-        .db
-        .prepare_interrupt_for_accept(user_id, candidate_id)
-        .await?
-      {
-        let channel_id = if cfg!(test) {
-          channel_id
+    if pick {
+      tx.commit_unsent().await?;
+    } else {
+      self.send_prompt(tx, channel_id, user_id).await?;
+    }
+
+    let interrupted = match update.action {
+      Some(Action::AcceptCandidate { id } | Action::SuperLikeCandidate { id }) => vec![id],
+      _ => retracted,
+    };
+
+    for candidate_id in interrupted {
+      let interrupt = Interrupt {
+        channel_id: if self.is_test() {
+          Some(channel_id)
         } else {
-          self
-            .client()
-            .create_private_channel(candidate_id)
-            .exec()
-            .await?
-            .model()
-            .await?
-            .id
-        };
-        self.send_prompt(tx, channel_id, candidate_id).await?;
-      }
+          None
+        },
+        user_id,
+        candidate_id,
+      };
+
+      self
+        .db
+        .enqueue_interrupt(self.assignment.shard_for(candidate_id), interrupt)
+        .await?;
+
+      self.interrupts.notify_one();
     }
 
     Ok(())
@@ -265,163 +486,90 @@ impl Bot {
 
   async fn send_prompt(
     &self,
-    mut tx: UpdateTx<'_>,
+    mut tx: UpdateTx,
     channel_id: ChannelId,
     recipient_id: UserId,
   ) -> Result<()> {
+    let transport = self.transport(recipient_id)?;
+
     let prompt = tx.prompt();
 
-    let prompt_text = Db::prompt_text(&mut tx.inner_transaction(), prompt).await?;
+    let mut prompt_text = match self.config.prompts.text(prompt) {
+      Some(text) => text.to_owned(),
+      None => Db::prompt_text(tx.inner_transaction(), recipient_id, prompt).await?,
+    };
 
-    let avatar_url = if let Prompt::Candidate { id } | Prompt::Match { id } = prompt {
-      let id = if cfg!(test) { self.user.id } else { id };
-      self
-        .client()
-        .user(id)
-        .exec()
-        .await?
-        .model()
-        .await?
-        .avatar
-        .map(|hash| format!("https://cdn.discordapp.com/avatars/{}/{}.png", id, hash))
+    // Matches may be on another platform
+    if let Prompt::Match { id } = prompt {
+      let mention = self.transport(id)?.mention(id).await?;
+      prompt_text = prompt_text.replace(&format!("<@{}>", id), &mention);
+    }
+
+    let image_url = if let Prompt::Candidate { id } | Prompt::Match { id } = prompt {
+      if self.is_test() {
+        transport.profile_image_url(transport.user_id()).await?
+      } else {
+        self.transport(id)?.profile_image_url(id).await?
+      }
     } else {
       None
     };
 
-    rate_limit::wait().await;
-    let prompt_message = self
-      .create_message(recipient_id, channel_id, &prompt_text, avatar_url)
+    let message_id = transport
+      .send_prompt(
+        recipient_id,
+        channel_id,
+        &prompt_text,
+        image_url,
+        &prompt.reactions(),
+      )
       .await?;
 
-    for emoji in prompt.reactions().iter().copied() {
-      let reaction_type = emoji.into();
-
-      rate_limit::wait().await;
-      self
-        .client()
-        .create_reaction(channel_id, prompt_message.id, &reaction_type)
-        .exec()
-        .await?;
-    }
-
-    tx.commit(prompt_message.id).await?;
+    tx.commit(message_id).await?;
 
     Ok(())
   }
 
-  async fn is_private_channel(&self, id: ChannelId) -> Result<bool> {
-    if let Some(private_channel) = self.cache.private_channel(id) {
-      return Ok(matches!(private_channel.kind, ChannelType::Private));
-    }
-
-    let channel = self.client().channel(id).exec().optional_model().await?;
-
-    match channel {
-      Some(Channel::Private(_)) => Ok(true),
-      Some(Channel::Group(_) | Channel::Guild(_)) | None => Ok(false),
-    }
-  }
-
-  async fn create_message(
-    &self,
-    user_id: UserId,
-    channel_id: ChannelId,
-    content: &str,
-    image_url: Option<String>,
-  ) -> Result<Message> {
-    let mut create_message = self.client().create_message(channel_id);
-
-    let content = self.test_id.as_ref().map_or_else(
-      || content.into(),
-      |test_id| test_id.prefix_message(user_id.0, content),
-    );
-
-    let mut embeds = Vec::new();
-    if let Some(image_url) = image_url {
-      embeds.push(
-        EmbedBuilder::new()
-          .image(ImageSource::url(image_url)?)
-          .build()?,
-      );
-    }
-
-    create_message = create_message.embeds(&embeds)?;
-
-    Ok(
-      create_message
-        .content(&content)?
-        .exec()
-        .await?
-        .model()
-        .await?,
-    )
-  }
-
   #[cfg(test)]
   pub(crate) async fn new_test_instance(db_name: &str, test_id: TestId) -> Result<Self> {
-    Self::new(db_name, Some(test_id)).await
+    let config = Config::default();
+    let assignment = Assignment::new(0, 1, 1);
+
+    let transport = DiscordTransport::new(&config, &assignment, Some(test_id.clone())).await?;
+
+    Ok(Self::new(
+      Db::connect(db_name).await?,
+      assignment,
+      config,
+      None,
+      vec![Arc::new(transport)],
+      Some(test_id),
+    ))
   }
 
-  pub(crate) fn client(&self) -> &Client {
-    self.cluster.config().http_client()
-  }
-
-  async fn initialize_cluster(test: bool) -> Result<(Cluster, Arc<Mutex<Events>>)> {
-    let token = env::var("QUWUE_TOKEN").context(error::Token)?;
-
-    let mut intents = Intents::DIRECT_MESSAGES | Intents::DIRECT_MESSAGE_REACTIONS;
-
-    if test {
-      intents |= Intents::GUILD_MESSAGES;
-      intents |= Intents::GUILD_MESSAGE_REACTIONS;
-    }
-
-    let (cluster, mut events) = Cluster::builder(token, intents)
-      .event_types(
-        EventTypeFlags::READY | EventTypeFlags::MESSAGE_CREATE | EventTypeFlags::REACTION_ADD,
-      )
-      .build()
-      .await?;
-
-    cluster.up().await;
-
-    match events.next().await {
-      Some((_, Event::Ready(_))) => {},
-      event => return Err(Error::ClusterReady { event }),
-    }
-
-    Ok((cluster, Arc::new(Mutex::new(events))))
-  }
-
-  async fn new(db_name: &str, test_id: Option<TestId>) -> Result<Self> {
-    let (cluster, events) = if test_id.is_some() {
-      test_cluster::get().await.clone()
-    } else {
-      Self::initialize_cluster(false).await?
-    };
-
-    let client = cluster.config().http_client();
-
-    let user_id = client.current_user().exec().await?.model().await?.id;
-
-    let user = client.user(user_id).exec().await?.model().await?;
-
-    let cache = InMemoryCache::new();
-
-    let db = Db::connect(db_name).await?;
-
+  pub(crate) fn new(
+    db: Db,
+    assignment: Assignment,
+    config: Config,
+    error_sink: Option<ErrorSink>,
+    transports: Vec<Arc<dyn Transport>>,
+    test_id: Option<TestId>,
+  ) -> Self {
     let inner = Inner {
-      cache,
-      cluster,
-      db,
-      events,
+      interrupts: Notify::new(),
+      db: db
+        .with_defer_cooldown(config.matching.defer_cooldown())
+        .with_resurface_delay(config.matching.resurface_delay()),
+      assignment,
+      config,
+      error_sink,
       test_id,
-      user,
+      transports,
     };
 
-    Ok(Bot {
+    Bot {
       inner: Arc::new(inner),
-    })
+    }
   }
 
   #[cfg(test)]
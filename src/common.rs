@@ -1,50 +1,63 @@
 // stdlib
 pub(crate) use std::{
+  collections::{BTreeMap, VecDeque},
+  convert::TryFrom,
   env,
-  fmt::{self, Display, Formatter},
-  io,
+  fmt::{self, Debug, Display, Formatter},
+  fs, io,
   marker::Unpin,
-  ops::Deref,
+  ops::{Deref, Range},
   panic,
   path::{Path, PathBuf},
   process,
-  sync::Arc,
-  time::{Duration, Instant},
+  sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc,
+  },
+  time::{Duration, Instant, SystemTime},
 };
 
 // dependencies
 pub(crate) use {
   async_trait::async_trait,
-  futures_util::StreamExt,
-  serde::de::DeserializeOwned,
+  futures_util::{future, StreamExt},
+  reqwest::Method,
+  serde::{de::DeserializeOwned, Deserialize},
+  serde_json::{json, Value},
   snafu::{ResultExt, Snafu},
   structopt::StructOpt,
-  tokio::{runtime::Runtime, sync::Mutex},
+  strum::IntoStaticStr,
+  tokio::{
+    runtime::Runtime,
+    sync::{mpsc, Mutex, Notify},
+  },
   tracing_log::LogTracer,
   tracing_subscriber::{layer::SubscriberExt, EnvFilter},
   twilight_cache_inmemory::InMemoryCache,
   twilight_embed_builder::{image_source::ImageSource, EmbedBuilder},
   twilight_gateway::{
-    cluster::{ClusterStartError, Events},
+    cluster::{ClusterStartError, Events, ShardScheme},
     Cluster, EventTypeFlags, Intents,
   },
   twilight_http::{
-    api_error::ApiError, client::Client, response::ResponseFuture, Error as HttpError,
+    api_error::ApiError, client::Client, request::channel::reaction::RequestReactionType,
+    response::ResponseFuture, Error as HttpError,
   },
   twilight_model::{
-    channel::{Channel, ChannelType, Message, ReactionType},
-    gateway::{
-      event::Event,
-      payload::{MessageCreate, ReactionAdd},
-    },
-    id::{ChannelId, UserId},
+    channel::{Channel, ChannelType, Message, Reaction, ReactionType},
+    gateway::event::Event,
   },
+  url::Url,
 };
 
 // local dependencies
 pub(crate) use {
-  db::{Db, UpdateTx},
-  model::{Action, Prompt, Response, User},
+  db::{Db, Interrupt, PoolOptions, UpdateTx},
+  db_url::{ConnectionOptions, SslMode},
+  model::{
+    Action, ChannelId, Emoji, EventId, MessageId, Prompt, PromptDiscriminant, PromptMessage,
+    Response, User, UserId,
+  },
 };
 
 // logging macros
@@ -56,8 +69,40 @@ pub(crate) use crate::{async_static, error, logging, rate_limit, runtime};
 
 // structs and enums
 pub(crate) use crate::{
-  arguments::Arguments, bot::Bot, error::Error, response_future_ext::ResponseFutureExt,
-  test_id::TestId, test_message::TestMessage, test_run_id::TestRunId, test_user_id::TestUserId,
+  alert_destination::AlertDestination,
+  arguments::Arguments,
+  assignment::Assignment,
+  bot::Bot,
+  config::Config,
+  config_subcommand::ConfigSubcommand,
+  coordinator::Coordinator,
+  database_config::DatabaseConfig,
+  discord_id::DiscordId,
+  discord_transport::DiscordTransport,
+  error::Error,
+  error_reference::ErrorReference,
+  error_sink::ErrorSink,
+  error_summaries::{ErrorKey, ErrorSummaries},
+  features_config::FeaturesConfig,
+  incoming::Incoming,
+  lease::Lease,
+  matching_config::MatchingConfig,
+  matrix_config::MatrixConfig,
+  matrix_event::MatrixEvent,
+  matrix_transport::MatrixTransport,
+  prompts_config::PromptsConfig,
+  rate_limit_config::RateLimitConfig,
+  response_future_ext::ResponseFutureExt,
+  runtime_config::RuntimeConfig,
+  subcommand::Subcommand,
+  terminal_transport::TerminalTransport,
+  test_id::TestId,
+  test_message::TestMessage,
+  test_run_id::TestRunId,
+  test_user_id::TestUserId,
+  token_source::TokenSource,
+  token_table::TokenTable,
+  transport::Transport,
 };
 
 // type aliases
@@ -67,7 +112,7 @@ pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
 #[cfg(test)]
 mod test {
   // stdlib
-  pub(crate) use std::{collections::BTreeMap, error::Error as _};
+  pub(crate) use std::{collections::BTreeSet, error::Error as _};
 
   // dependencies
   pub(crate) use {
@@ -76,22 +121,14 @@ mod test {
       select,
     },
     once_cell::sync::Lazy,
-    serde::Deserialize,
-    tokio::{
-      sync::{mpsc, RwLock},
-      time,
-    },
+    tokio::{sync::RwLock, time},
     tracing::instrument,
     twilight_model::{
       channel::{GuildChannel, TextChannel},
       guild::{Guild, Member},
-      id::MessageId,
     },
   };
 
-  // local dependencies
-  pub(crate) use model::Emoji;
-
   // macros
   pub(crate) use crate::test_bot;
 
@@ -100,7 +137,8 @@ mod test {
 
   // structs and enums
   pub(crate) use crate::{
-    test_dispatcher::TestDispatcher, test_event::TestEvent, test_user::TestUser,
+    matrix_homeserver::MatrixHomeserver, test_dispatcher::TestDispatcher, test_event::TestEvent,
+    test_user::TestUser,
   };
 }
 
@@ -0,0 +1,28 @@
+use crate::common::*;
+
+#[derive(StructOpt)]
+pub(crate) enum ConfigSubcommand {
+  #[structopt(about = "Check that configuration is valid and the bot token can be loaded")]
+  Check,
+}
+
+impl ConfigSubcommand {
+  pub(crate) fn run(&self, arguments: &Arguments) -> Result<()> {
+    match self {
+      Self::Check => {
+        let config = arguments.config()?;
+
+        config.database_url()?;
+
+        config.token.load()?;
+
+        match &arguments.config {
+          Some(path) => eprintln!("Configuration in `{}` is valid.", path.display()),
+          None => eprintln!("Default configuration is valid."),
+        }
+
+        Ok(())
+      },
+    }
+  }
+}
@@ -0,0 +1,274 @@
+use crate::common::*;
+
+use tokio::sync::watch;
+
+/// Each instance holds an `instance:{name}` lease as a heartbeat, and live
+/// instances divide shards between them by seniority, holding `shard:{id}`
+/// leases. Leases on shards that are no longer assigned are kept until the
+/// cluster running them has stopped.
+pub(crate) struct Coordinator {
+  receiver: watch::Receiver<Option<Assignment>>,
+  running:  watch::Sender<Option<Assignment>>,
+}
+
+struct State {
+  db:        Db,
+  instance:  Lease,
+  published: Option<Assignment>,
+  running:   watch::Receiver<Option<Assignment>>,
+  shards:    BTreeMap<u64, Lease>,
+  status:    String,
+  total:     u64,
+}
+
+impl Coordinator {
+  const INSTANCE_PREFIX: &'static str = "instance:";
+  const INTERVAL: Duration = Duration::from_secs(5);
+
+  pub(crate) fn start(db: Db, instance: String, total: u64) -> Self {
+    let (sender, receiver) = watch::channel(None);
+    let (running, running_receiver) = watch::channel(None);
+
+    let mut state = State {
+      instance: Lease::new(
+        db.clone(),
+        format!("{}{}", Self::INSTANCE_PREFIX, instance),
+        instance,
+      ),
+      published: None,
+      running: running_receiver,
+      shards: BTreeMap::new(),
+      status: String::new(),
+      db,
+      total,
+    };
+
+    tokio::spawn(async move {
+      let mut heartbeat = Instant::now();
+
+      loop {
+        let started = Instant::now();
+
+        let assignment = match state.rebalance().await {
+          Ok(assignment) => {
+            heartbeat = started;
+            assignment
+          },
+          Err(err) => {
+            warn!("Failed to rebalance shards: {}", err);
+
+            if Self::leases_outlast_interval(heartbeat.elapsed()) {
+              state.published.clone()
+            } else {
+              state.report(format!(
+                "Instance `{}` failed to renew its leases and is inactive.",
+                state.instance.holder()
+              ));
+              None
+            }
+          },
+        };
+
+        if assignment != state.published {
+          state.published = assignment.clone();
+
+          if sender.send(assignment).is_err() {
+            break;
+          }
+        }
+
+        tokio::time::sleep(Self::INTERVAL).await;
+      }
+    });
+
+    Self { receiver, running }
+  }
+
+  /// The assignment is considered running until `stopped` is called
+  pub(crate) async fn assignment(&mut self) -> Result<Assignment> {
+    loop {
+      let assignment = self.receiver.borrow().clone();
+
+      if let Some(assignment) = assignment {
+        self
+          .running
+          .send(Some(assignment.clone()))
+          .map_err(|_| Error::CoordinatorStopped)?;
+        return Ok(assignment);
+      }
+
+      self.wait().await?;
+    }
+  }
+
+  pub(crate) async fn changed(&mut self, assignment: &Assignment) -> Result<()> {
+    loop {
+      let current = self.receiver.borrow().clone();
+
+      if current.as_ref() != Some(assignment) {
+        return Ok(());
+      }
+
+      self.wait().await?;
+    }
+  }
+
+  pub(crate) fn stopped(&self) -> Result<()> {
+    self
+      .running
+      .send(None)
+      .map_err(|_| Error::CoordinatorStopped)
+  }
+
+  async fn wait(&mut self) -> Result<()> {
+    self
+      .receiver
+      .changed()
+      .await
+      .map_err(|_| Error::CoordinatorStopped)
+  }
+
+  fn leases_outlast_interval(elapsed: Duration) -> bool {
+    elapsed + Self::INTERVAL < Lease::DURATION
+  }
+
+  fn shard_lease(shard: u64) -> String {
+    format!("shard:{}", shard)
+  }
+}
+
+impl State {
+  async fn rebalance(&mut self) -> Result<Option<Assignment>> {
+    if !self.instance.acquire().await? {
+      return Err(Error::InstanceNameInUse {
+        instance: self.instance.holder().to_owned(),
+      });
+    }
+
+    let instances = self
+      .db
+      .live_lease_holders(Coordinator::INSTANCE_PREFIX)
+      .await?;
+
+    let index = instances
+      .iter()
+      .position(|instance| instance == self.instance.holder())
+      .unwrap_or(instances.len());
+
+    let assignment = Assignment::new(
+      index as u64,
+      instances.len().max(index + 1) as u64,
+      self.total,
+    );
+
+    let dropped = self
+      .shards
+      .keys()
+      .copied()
+      .filter(|shard| !assignment.contains(*shard))
+      .collect::<Vec<u64>>();
+
+    // The running or about-to-start cluster may still be connected to
+    // dropped shards, so their leases are renewed until it has stopped
+    let running = self.running.borrow().clone();
+
+    let mut lost = Vec::new();
+
+    for shard in dropped {
+      let in_use = [&running, &self.published]
+        .iter()
+        .copied()
+        .flatten()
+        .any(|assignment| assignment.contains(shard));
+
+      if in_use {
+        if let Some(lease) = self.shards.get(&shard) {
+          if !lease.acquire().await? {
+            self.shards.remove(&shard);
+            lost.push(shard.to_string());
+          }
+        }
+      } else if let Some(lease) = self.shards.remove(&shard) {
+        lease.release().await?;
+      }
+    }
+
+    // Another instance has taken over a shard that may still be running
+    // here, so the running cluster must stop
+    if !lost.is_empty() {
+      self.report(format!(
+        "Instance `{}` lost its leases on shards {} and is stopping.",
+        self.instance.holder(),
+        lost.join(", "),
+      ));
+      return Ok(None);
+    }
+
+    if assignment.shards.is_empty() {
+      self.report(format!(
+        "Instance `{}` is waiting on standby, active instances: {}",
+        self.instance.holder(),
+        instances[..index].join(", "),
+      ));
+      return Ok(None);
+    }
+
+    let mut complete = true;
+
+    for shard in assignment.shards.clone() {
+      let lease = Lease::new(
+        self.db.clone(),
+        Coordinator::shard_lease(shard),
+        self.instance.holder().to_owned(),
+      );
+
+      if lease.acquire().await? {
+        self.shards.insert(shard, lease);
+      } else {
+        self.shards.remove(&shard);
+        complete = false;
+      }
+    }
+
+    if !complete {
+      self.report(format!(
+        "Instance `{}` is waiting for other instances to release {}.",
+        self.instance.holder(),
+        assignment
+      ));
+      return Ok(None);
+    }
+
+    self.report(format!(
+      "Instance `{}` is active with {}.",
+      self.instance.holder(),
+      assignment
+    ));
+
+    Ok(Some(assignment))
+  }
+
+  fn report(&mut self, status: String) {
+    if status != self.status {
+      info!("{}", status);
+      self.status = status;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn leases_must_outlast_the_next_rebalance() {
+    assert!(Coordinator::leases_outlast_interval(Duration::from_secs(0)));
+    assert!(Coordinator::leases_outlast_interval(
+      Lease::DURATION - Coordinator::INTERVAL - Duration::from_secs(1)
+    ));
+    assert!(!Coordinator::leases_outlast_interval(
+      Lease::DURATION - Coordinator::INTERVAL
+    ));
+    assert!(!Coordinator::leases_outlast_interval(Lease::DURATION));
+  }
+}
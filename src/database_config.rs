@@ -0,0 +1,153 @@
+use crate::common::*;
+
+/// Either a complete `url`, or discrete options with at least a database
+/// `name`, must be given
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct DatabaseConfig {
+  pub(crate) connect_timeout_seconds: u64,
+  pub(crate) host:                    Option<String>,
+  pub(crate) max_connections:         u32,
+  pub(crate) name:                    Option<String>,
+  pub(crate) password_credential:     Option<String>,
+  pub(crate) password_file:           Option<PathBuf>,
+  pub(crate) port:                    Option<u16>,
+  pub(crate) ssl_mode:                Option<SslMode>,
+  pub(crate) ssl_root_cert:           Option<PathBuf>,
+  pub(crate) url:                     Option<String>,
+  pub(crate) user:                    Option<String>,
+}
+
+impl DatabaseConfig {
+  pub(crate) fn pool_options(&self) -> PoolOptions {
+    PoolOptions {
+      connect_timeout: Duration::from_secs(self.connect_timeout_seconds),
+      max_connections: self.max_connections,
+    }
+  }
+
+  pub(crate) fn has_discrete_options(&self) -> bool {
+    self.host.is_some()
+      || self.name.is_some()
+      || self.port.is_some()
+      || self.ssl_mode.is_some()
+      || self.ssl_root_cert.is_some()
+      || self.user.is_some()
+  }
+
+  pub(crate) fn clear_discrete_options(&mut self) {
+    self.host = None;
+    self.name = None;
+    self.port = None;
+    self.ssl_mode = None;
+    self.ssl_root_cert = None;
+    self.user = None;
+  }
+
+  pub(crate) fn url(&self) -> Result<String> {
+    let password = self.password()?;
+
+    match &self.url {
+      Some(url) => match password {
+        Some(password) => db_url::with_password(url, &password).context(error::DatabaseUrlParse),
+        None => Ok(url.clone()),
+      },
+      None => Ok(
+        ConnectionOptions {
+          host: self.host.clone(),
+          name: self.name.clone().ok_or(Error::DatabaseUnspecified)?,
+          password,
+          port: self.port,
+          ssl_mode: self.ssl_mode,
+          ssl_root_cert: self.ssl_root_cert.clone(),
+          user: self.user.clone(),
+        }
+        .url(),
+      ),
+    }
+  }
+
+  fn password(&self) -> Result<Option<String>> {
+    let path = match (&self.password_file, &self.password_credential) {
+      (Some(path), _) => path.clone(),
+      (None, Some(credential)) =>
+        db_url::credential_path(credential).ok_or_else(|| Error::CredentialsDirectory {
+          credential: credential.clone(),
+        })?,
+      (None, None) => return Ok(None),
+    };
+
+    let password =
+      db_url::read_password(&path).context(error::DatabasePasswordRead { path: &path })?;
+
+    Ok(Some(password))
+  }
+
+  pub(crate) fn problems(&self) -> Result<(), String> {
+    if let Some(url) = &self.url {
+      let parsed =
+        url::Url::parse(url).map_err(|err| format!("`database.url` is invalid: {}", err))?;
+
+      match parsed.scheme() {
+        "postgres" | "postgresql" => {},
+        "sqlite" =>
+          if self.password_file.is_some() || self.password_credential.is_some() {
+            return Err("SQLite databases do not take a password".into());
+          },
+        scheme =>
+          return Err(format!(
+            "`database.url` has scheme `{}`, expected `postgres`, `postgresql`, or `sqlite`",
+            scheme
+          )),
+      }
+
+      if self.has_discrete_options() {
+        return Err(
+          "`database.url` may not be combined with `host`, `name`, `port`, `ssl_mode`, \
+           `ssl_root_cert`, or `user`"
+            .into(),
+        );
+      }
+    }
+
+    if self.password_file.is_some() && self.password_credential.is_some() {
+      return Err(
+        "`database.password_file` and `database.password_credential` may not both be set".into(),
+      );
+    }
+
+    if self.port == Some(0) {
+      return Err("`database.port` must be at least 1".into());
+    }
+
+    if self.connect_timeout_seconds == 0 {
+      return Err("`database.connect_timeout_seconds` must be at least 1".into());
+    }
+
+    if self.max_connections == 0 {
+      return Err("`database.max_connections` must be at least 1".into());
+    }
+
+    Ok(())
+  }
+}
+
+impl Default for DatabaseConfig {
+  fn default() -> Self {
+    let pool_options = PoolOptions::default();
+
+    Self {
+      connect_timeout_seconds: pool_options.connect_timeout.as_secs(),
+      host:                    None,
+      max_connections:         pool_options.max_connections,
+      name:                    None,
+      password_credential:     None,
+      password_file:           None,
+      port:                    None,
+      ssl_mode:                None,
+      ssl_root_cert:           None,
+      url:                     None,
+      user:                    None,
+    }
+  }
+}
@@ -0,0 +1,47 @@
+use crate::common::*;
+
+/// Conversion between model IDs and the twilight IDs of the Discord users,
+/// channels and messages that they identify.
+pub(crate) trait DiscordId {
+  type Discord;
+
+  fn from_discord(id: Self::Discord) -> Self;
+
+  fn discord(self) -> Self::Discord;
+}
+
+impl DiscordId for UserId {
+  type Discord = twilight_model::id::UserId;
+
+  fn from_discord(id: Self::Discord) -> Self {
+    Self(id.0)
+  }
+
+  fn discord(self) -> Self::Discord {
+    twilight_model::id::UserId(self.0)
+  }
+}
+
+impl DiscordId for ChannelId {
+  type Discord = twilight_model::id::ChannelId;
+
+  fn from_discord(id: Self::Discord) -> Self {
+    Self(id.0)
+  }
+
+  fn discord(self) -> Self::Discord {
+    twilight_model::id::ChannelId(self.0)
+  }
+}
+
+impl DiscordId for MessageId {
+  type Discord = twilight_model::id::MessageId;
+
+  fn from_discord(id: Self::Discord) -> Self {
+    Self(id.0)
+  }
+
+  fn discord(self) -> Self::Discord {
+    twilight_model::id::MessageId(self.0)
+  }
+}
@@ -0,0 +1,437 @@
+use crate::common::*;
+
+async_static! {
+  test_cluster,
+  (Cluster, Arc<Mutex<Events>>),
+  {
+    DiscordTransport::initialize_cluster(true, &Config::default(), &Assignment::new(0, 1, 1))
+      .await
+      .expect("Failed to initialize test cluster")
+  }
+}
+
+#[derive(Debug)]
+pub(crate) struct DiscordTransport {
+  action_interval: Duration,
+  cache:           InMemoryCache,
+  cluster:         Cluster,
+  events:          Arc<Mutex<Events>>,
+  test_id:         Option<TestId>,
+  user:            twilight_model::user::User,
+}
+
+impl DiscordTransport {
+  pub(crate) async fn new(
+    config: &Config,
+    assignment: &Assignment,
+    test_id: Option<TestId>,
+  ) -> Result<Self> {
+    let (cluster, events) = if test_id.is_some() {
+      test_cluster::get().await.clone()
+    } else {
+      Self::initialize_cluster(false, config, assignment).await?
+    };
+
+    let client = cluster.config().http_client();
+
+    let user_id = client.current_user().exec().await?.model().await?.id;
+
+    let user = client.user(user_id).exec().await?.model().await?;
+
+    Ok(Self {
+      action_interval: config.rate_limit.action_interval(),
+      cache: InMemoryCache::new(),
+      cluster,
+      events,
+      test_id,
+      user,
+    })
+  }
+
+  async fn initialize_cluster(
+    test: bool,
+    config: &Config,
+    assignment: &Assignment,
+  ) -> Result<(Cluster, Arc<Mutex<Events>>)> {
+    let token = config.token.load()?;
+
+    let mut intents = Intents::DIRECT_MESSAGES | Intents::DIRECT_MESSAGE_REACTIONS;
+
+    if test || config.features.guild_messages {
+      intents |= Intents::GUILD_MESSAGES;
+      intents |= Intents::GUILD_MESSAGE_REACTIONS;
+    }
+
+    let (cluster, mut events) = Cluster::builder(token, intents)
+      .event_types(
+        EventTypeFlags::READY | EventTypeFlags::MESSAGE_CREATE | EventTypeFlags::REACTION_ADD,
+      )
+      .shard_scheme(assignment.scheme())
+      .build()
+      .await?;
+
+    cluster.up().await;
+
+    match events.next().await {
+      Some((_, Event::Ready(_))) => {},
+      event => return Err(Error::ClusterReady { event }),
+    }
+
+    Ok((cluster, Arc::new(Mutex::new(events))))
+  }
+
+  fn client(&self) -> &Client {
+    self.cluster.config().http_client()
+  }
+
+  fn reaction_type(emoji: Emoji) -> RequestReactionType<'static> {
+    RequestReactionType::Unicode {
+      name: emoji.unicode(),
+    }
+  }
+
+  fn incoming_message(&self, message: Message) -> Option<Incoming> {
+    let (user_id, content) = if let Some(test_id) = &self.test_id {
+      let test_message = test_id.filter(message.content.as_str())?;
+      (
+        test_message.test_user_id().to_discord_user_id(),
+        test_message.text,
+      )
+    } else {
+      (UserId::from_discord(message.author.id), message.content)
+    };
+
+    Some(Incoming {
+      bot: Some(message.author.bot),
+      channel_id: ChannelId::from_discord(message.channel_id),
+      event: EventId::Message {
+        id: MessageId::from_discord(message.id),
+      },
+      message_id: None,
+      response: Response::message(content),
+      sender: UserId::from_discord(message.author.id),
+      user_id,
+    })
+  }
+
+  async fn incoming_reaction(&self, reaction: Reaction) -> Result<Incoming> {
+    let user_id = if self.test_id.is_some() {
+      let message = self
+        .client()
+        .message(reaction.channel_id, reaction.message_id)
+        .exec()
+        .await?
+        .model()
+        .await?;
+
+      TestMessage::parse(&message.content)
+        .expect("failed to parse reaction message")
+        .test_user_id()
+        .to_discord_user_id()
+    } else {
+      UserId::from_discord(reaction.user_id)
+    };
+
+    let (emoji, response) = match reaction.emoji {
+      ReactionType::Unicode { name } => (name.clone(), Response::unicode_reaction(name)),
+      ReactionType::Custom { id, .. } => {
+        let id = id.to_string();
+        (id.clone(), Response::custom_reaction(id))
+      },
+    };
+
+    let message_id = MessageId::from_discord(reaction.message_id);
+    let sender = UserId::from_discord(reaction.user_id);
+
+    Ok(Incoming {
+      bot: None,
+      channel_id: ChannelId::from_discord(reaction.channel_id),
+      event: EventId::Reaction {
+        message_id,
+        user_id: sender,
+        emoji,
+      },
+      message_id: Some(message_id),
+      response,
+      sender,
+      user_id,
+    })
+  }
+
+  async fn missed_reaction(
+    &self,
+    user_id: UserId,
+    channel_id: ChannelId,
+    prompt_message: PromptMessage,
+  ) -> Result<Option<Incoming>> {
+    for emoji in prompt_message.prompt.reactions() {
+      let reactors = self
+        .client()
+        .reactions(
+          channel_id.discord(),
+          prompt_message.message_id.discord(),
+          &Self::reaction_type(emoji),
+        )
+        .exec()
+        .await?
+        .models()
+        .await?;
+
+      if reactors
+        .iter()
+        .any(|reactor| reactor.id == user_id.discord())
+      {
+        let reaction = Reaction {
+          emoji:      ReactionType::Unicode {
+            name: emoji.unicode().to_owned(),
+          },
+          guild_id:   None,
+          member:     None,
+          message_id: prompt_message.message_id.discord(),
+          channel_id: channel_id.discord(),
+          user_id:    user_id.discord(),
+        };
+
+        return Ok(Some(self.incoming_reaction(reaction).await?));
+      }
+    }
+
+    Ok(None)
+  }
+
+  async fn create_message(
+    &self,
+    user_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+    image_url: Option<String>,
+  ) -> Result<Message> {
+    let mut create_message = self.client().create_message(channel_id.discord());
+
+    let content = self.test_id.as_ref().map_or_else(
+      || content.into(),
+      |test_id| test_id.prefix_message(user_id.0, content),
+    );
+
+    let mut embeds = Vec::new();
+    if let Some(image_url) = image_url {
+      embeds.push(
+        EmbedBuilder::new()
+          .image(ImageSource::url(image_url)?)
+          .build()?,
+      );
+    }
+
+    create_message = create_message.embeds(&embeds)?;
+
+    Ok(
+      create_message
+        .content(&content)?
+        .exec()
+        .await?
+        .model()
+        .await?,
+    )
+  }
+}
+
+#[async_trait]
+impl Transport for DiscordTransport {
+  fn user_id(&self) -> UserId {
+    UserId::from_discord(self.user.id)
+  }
+
+  /// Snowflakes never have the high bit set
+  fn owns(&self, user_id: UserId) -> bool {
+    user_id.0 >> 63 == 0
+  }
+
+  fn pool(&self) -> &str {
+    Db::DEFAULT_POOL
+  }
+
+  async fn receive(&self) -> Option<Result<Incoming>> {
+    let mut events = self.events.lock().await;
+
+    while let Some((_shard_id, event)) = events.next().await {
+      info!("Quwue received event: {:?}", event.kind());
+
+      self.cache.update(&event);
+
+      match event {
+        Event::MessageCreate(message_create) =>
+          if let Some(incoming) = self.incoming_message(message_create.0) {
+            return Some(Ok(incoming));
+          },
+        Event::ReactionAdd(reaction_add) =>
+          return Some(self.incoming_reaction(reaction_add.0).await),
+        Event::Ready(_) => {},
+        _ => return Some(Err(Error::UnexpectedEvent { event })),
+      }
+    }
+
+    None
+  }
+
+  fn stop(&self) {
+    self.cluster.down();
+  }
+
+  async fn missed_responses(
+    &self,
+    user_id: UserId,
+    prompt_message: PromptMessage,
+  ) -> Result<Vec<Incoming>> {
+    let mut missed = Vec::new();
+
+    let channel_id = self.private_channel(user_id).await?;
+
+    let mut answered = false;
+
+    let mut after = prompt_message.message_id.discord();
+
+    loop {
+      let mut messages = self
+        .client()
+        .channel_messages(channel_id.discord())
+        .after(after)
+        .exec()
+        .await?
+        .models()
+        .await?;
+
+      messages.sort_by_key(|message| message.id);
+
+      after = match messages.last() {
+        Some(message) => message.id,
+        None => break,
+      };
+
+      for message in messages {
+        if message.author.id == user_id.discord() {
+          answered = true;
+          missed.extend(self.incoming_message(message));
+        }
+      }
+    }
+
+    // Reactions aren't timestamped, so they're only considered if the user
+    // hasn't messaged the bot since the prompt
+    if !answered {
+      missed.extend(
+        self
+          .missed_reaction(user_id, channel_id, prompt_message)
+          .await?,
+      );
+    }
+
+    Ok(missed)
+  }
+
+  async fn private_channel(&self, user_id: UserId) -> Result<ChannelId> {
+    Ok(ChannelId::from_discord(
+      self
+        .client()
+        .create_private_channel(user_id.discord())
+        .exec()
+        .await?
+        .model()
+        .await?
+        .id,
+    ))
+  }
+
+  async fn is_private_channel(&self, channel_id: ChannelId) -> Result<bool> {
+    if let Some(private_channel) = self.cache.private_channel(channel_id.discord()) {
+      return Ok(matches!(private_channel.kind, ChannelType::Private));
+    }
+
+    let channel = self
+      .client()
+      .channel(channel_id.discord())
+      .exec()
+      .optional_model()
+      .await?;
+
+    match channel {
+      Some(Channel::Private(_)) => Ok(true),
+      Some(Channel::Group(_) | Channel::Guild(_)) | None => Ok(false),
+    }
+  }
+
+  async fn is_bot(&self, user_id: UserId) -> Result<bool> {
+    Ok(
+      self
+        .client()
+        .user(user_id.discord())
+        .exec()
+        .await?
+        .model()
+        .await?
+        .bot,
+    )
+  }
+
+  async fn profile_image_url(&self, user_id: UserId) -> Result<Option<String>> {
+    Ok(
+      self
+        .client()
+        .user(user_id.discord())
+        .exec()
+        .await?
+        .model()
+        .await?
+        .avatar
+        .map(|hash| {
+          format!(
+            "https://cdn.discordapp.com/avatars/{}/{}.png",
+            user_id, hash
+          )
+        }),
+    )
+  }
+
+  async fn mention(&self, user_id: UserId) -> Result<String> {
+    Ok(format!("<@{}>", user_id))
+  }
+
+  async fn send_prompt(
+    &self,
+    recipient_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+    image_url: Option<String>,
+    choices: &[Emoji],
+  ) -> Result<MessageId> {
+    rate_limit::wait(self.action_interval).await;
+    let message = self
+      .create_message(recipient_id, channel_id, content, image_url)
+      .await?;
+
+    for emoji in choices.iter().copied() {
+      rate_limit::wait(self.action_interval).await;
+      self
+        .client()
+        .create_reaction(
+          channel_id.discord(),
+          message.id,
+          &Self::reaction_type(emoji),
+        )
+        .exec()
+        .await?;
+    }
+
+    Ok(MessageId::from_discord(message.id))
+  }
+
+  async fn send_message(&self, channel_id: ChannelId, content: &str) -> Result<()> {
+    self
+      .client()
+      .create_message(channel_id.discord())
+      .content(content)?
+      .exec()
+      .await?;
+
+    Ok(())
+  }
+}
@@ -4,9 +4,12 @@ use twilight_http::{
   request::channel::message::create_message::CreateMessageError, response::DeserializeBodyError,
 };
 
-#[derive(Debug, Snafu)]
+#[derive(Debug, Snafu, IntoStaticStr)]
 #[snafu(visibility(pub(crate)))]
 pub(crate) enum Error {
+  #[snafu(display("Failed to post to alert webhook `{}`: {}", url, source))]
+  AlertWebhook { url: Url, source: reqwest::Error },
+
   #[snafu(display("Received a response from a bot:\n{:?}", response))]
   BotResponse { response: Response },
 
@@ -16,9 +19,43 @@ pub(crate) enum Error {
   #[snafu(display("Did not get ready event after starting cluster."))]
   ClusterReady { event: Option<(u64, Event)> },
 
+  #[snafu(display("Invalid configuration: {}", message))]
+  ConfigInvalid { message: String },
+
+  #[snafu(display("Failed to parse config file `{}`: {}", path.display(), source))]
+  ConfigParse {
+    path:   PathBuf,
+    source: toml::de::Error,
+  },
+
+  #[snafu(display("Failed to read config file `{}`: {}", path.display(), source))]
+  ConfigRead { path: PathBuf, source: io::Error },
+
+  #[snafu(display("Shard coordinator task stopped."))]
+  CoordinatorStopped,
+
+  #[snafu(display(
+    "Database password credential `{}` requested, but no systemd credentials were passed. Is \
+     `LoadCredential=` set in the service file?",
+    credential
+  ))]
+  CredentialsDirectory { credential: String },
+
   #[snafu(context(false), display("Error creating message: {}", source))]
   CreateMessage { source: CreateMessageError },
 
+  #[snafu(display(
+    "No database configured. Pass `--db-url` or `--db-name`, or set `database.url` or \
+     `database.name` in the config file."
+  ))]
+  DatabaseUnspecified,
+
+  #[snafu(display("Failed to read database password from `{}`: {}", path.display(), source))]
+  DatabasePasswordRead { path: PathBuf, source: io::Error },
+
+  #[snafu(display("Failed to parse database URL: {}", source))]
+  DatabaseUrlParse { source: url::ParseError },
+
   #[snafu(context(false))]
   Db { source: db::Error },
 
@@ -50,6 +87,28 @@ pub(crate) enum Error {
     source: twilight_embed_builder::image_source::ImageSourceUrlError,
   },
 
+  #[snafu(display("Instance name `{}` is in use by another instance.", instance))]
+  InstanceNameInUse { instance: String },
+
+  #[snafu(display("Matrix homeserver returned {} {}: {}", status, errcode, message))]
+  MatrixApi {
+    status:  u16,
+    errcode: String,
+    message: String,
+  },
+
+  #[snafu(display("Matrix homeserver URL `{}` cannot have a path", url))]
+  MatrixHomeserverUrl { url: Url },
+
+  #[snafu(display("No Matrix ID is mapped to {}", id))]
+  MatrixIdUnknown { id: u64 },
+
+  #[snafu(context(false), display("Matrix request failed: {}", source))]
+  MatrixRequest { source: reqwest::Error },
+
+  #[snafu(display("Matrix homeserver response is missing `{}`", field))]
+  MatrixResponseField { field: String },
+
   #[snafu(context(false), display("Database migration failed: {}", source))]
   Migration { source: sqlx::migrate::MigrateError },
 
@@ -59,8 +118,20 @@ pub(crate) enum Error {
   #[snafu(display("Failed to initialize runtime: {}", source))]
   Runtime { source: io::Error },
 
-  #[snafu(display("Failed to retrieve `QUWUE_TOKEN` from environment: {}", source))]
-  Token { source: env::VarError },
+  #[snafu(display("No terminal user has ID {}", user_id))]
+  TerminalUserUnknown { user_id: UserId },
+
+  #[snafu(display("Failed to retrieve `{}` from environment: {}", var, source))]
+  Token {
+    var:    String,
+    source: env::VarError,
+  },
+
+  #[snafu(display("Failed to read token from `{}`: {}", path.display(), source))]
+  TokenFile { path: PathBuf, source: io::Error },
+
+  #[snafu(display("No transport for user {}", user_id))]
+  TransportUnavailable { user_id: UserId },
 
   #[snafu(display("Received unexpected event: {:?}", event.kind()))]
   UnexpectedEvent { event: Event },
@@ -73,12 +144,29 @@ pub(crate) enum Error {
 }
 
 impl Error {
+  pub(crate) fn is_user_error(&self) -> bool {
+    matches!(self, Self::BotResponse { .. } | Self::PublicResponse { .. })
+  }
+
+  pub(crate) fn variant(&self) -> &'static str {
+    self.into()
+  }
+
   pub(crate) fn user_facing_message(&self) -> String {
     match self {
-      Self::BotResponse { .. } => "Received a response from a bot".into(),
+      Self::AlertWebhook { .. } => "Failed to post to alert webhook".into(),
+      Self::BotResponse { .. } => "Sorry, Quwue can only be used by humans, not bots.".into(),
       Self::ClusterReady { .. } => "Did not get ready event after starting cluster".into(),
       Self::ClusterStart { .. } => "Discord gateway error".into(),
+      Self::ConfigInvalid { .. } => "Invalid configuration".into(),
+      Self::ConfigParse { .. } => "Failed to parse config file".into(),
+      Self::ConfigRead { .. } => "Failed to read config file".into(),
+      Self::CoordinatorStopped => "Shard coordinator stopped".into(),
       Self::CreateMessage { .. } => "Failed to send message".into(),
+      Self::CredentialsDirectory { .. } => "Missing systemd credentials".into(),
+      Self::DatabasePasswordRead { .. } => "Failed to read database password".into(),
+      Self::DatabaseUnspecified => "No database configured".into(),
+      Self::DatabaseUrlParse { .. } => "Failed to parse database URL".into(),
       Self::Db { .. } => "Database error".into(),
       Self::DeserializeBody { .. } => "Failed to deserialize response body".into(),
       Self::EmbedBuild { .. } => "Failed to build embed".into(),
@@ -101,10 +189,22 @@ impl Error {
         }
       },
       Self::ImageSourceUrl { .. } => "Failed to create image source URL".into(),
+      Self::InstanceNameInUse { .. } => "Instance name in use".into(),
+      Self::MatrixApi {
+        status, errcode, ..
+      } => format!("Matrix error {} {}", status, errcode),
+      Self::MatrixHomeserverUrl { .. } => "Invalid Matrix homeserver URL".into(),
+      Self::MatrixIdUnknown { .. } => "Unknown Matrix ID".into(),
+      Self::MatrixRequest { .. } => "Matrix request failed".into(),
+      Self::MatrixResponseField { .. } => "Unexpected Matrix homeserver response".into(),
       Self::Migration { .. } => "Database migration error".into(),
-      Self::PublicResponse { .. } => "Received a non-private response".into(),
+      Self::PublicResponse { .. } =>
+        "Please send me a direct message instead of posting in a server channel.".into(),
       Self::Runtime { .. } => "Failed to initialize runtime".into(),
+      Self::TerminalUserUnknown { .. } => "Unknown terminal user".into(),
       Self::Token { .. } => "Failed to get authentication token from environment".into(),
+      Self::TokenFile { .. } => "Failed to read authentication token from file".into(),
+      Self::TransportUnavailable { .. } => "No transport for user".into(),
       Self::UnexpectedEvent { .. } => "Unexpected event".into(),
       Self::User => "Failed to get current user".into(),
       Self::UserUnavailable { .. } => "Failed to retrieve Discord user by ID".into(),
@@ -0,0 +1,52 @@
+use crate::common::*;
+
+use rand::seq::SliceRandom;
+
+/// Shown to the affected user and logged, so that reports from users can be
+/// correlated with logs
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub(crate) struct ErrorReference(String);
+
+impl ErrorReference {
+  const ALPHABET: &'static [u8] = b"23456789ABCDEFGHJKLMNPQRSTUVWXYZ";
+  const LENGTH: usize = 8;
+
+  pub(crate) fn new() -> Self {
+    let mut rng = rand::thread_rng();
+
+    Self(
+      (0..Self::LENGTH)
+        .map(|_| char::from(*Self::ALPHABET.choose(&mut rng).unwrap()))
+        .collect(),
+    )
+  }
+
+  pub(crate) fn as_str(&self) -> &str {
+    &self.0
+  }
+}
+
+impl Display for ErrorReference {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn new() {
+    let reference = ErrorReference::new();
+
+    assert_eq!(reference.as_str().len(), ErrorReference::LENGTH);
+
+    assert!(reference
+      .as_str()
+      .bytes()
+      .all(|byte| ErrorReference::ALPHABET.contains(&byte)));
+
+    assert_ne!(reference, ErrorReference::new());
+  }
+}
@@ -0,0 +1,78 @@
+use crate::common::*;
+
+/// Posts deduplicated, rate-limited summaries of internal errors to operator
+/// channels and webhooks, so that outages are noticed without tailing logs.
+#[derive(Debug, Clone)]
+pub(crate) struct ErrorSink {
+  sender: mpsc::UnboundedSender<(ErrorKey, String)>,
+}
+
+impl ErrorSink {
+  const MAX_MESSAGE_LENGTH: usize = 1000;
+  const WINDOW: Duration = Duration::from_secs(300);
+
+  pub(crate) fn start(destinations: Vec<AlertDestination>) -> Self {
+    let (sender, mut receiver) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+      let mut summaries = ErrorSummaries::new(Self::WINDOW);
+
+      let mut interval = tokio::time::interval(Self::WINDOW / 10);
+
+      loop {
+        let ready = tokio::select! {
+          incoming = receiver.recv() => match incoming {
+            Some((key, message)) => summaries
+              .record(Instant::now(), key, message)
+              .into_iter()
+              .collect(),
+            None => break,
+          },
+          _ = interval.tick() => summaries.flush(Instant::now()),
+        };
+
+        for summary in ready {
+          let content = summary.to_string();
+          for destination in &destinations {
+            if let Err(err) = destination.send(&content).await {
+              warn!("Failed to send error summary: {}", err);
+            }
+          }
+        }
+      }
+    });
+
+    Self { sender }
+  }
+
+  pub(crate) fn report(
+    &self,
+    error: &Error,
+    prompt: Option<PromptDiscriminant>,
+    reference: Option<&ErrorReference>,
+  ) {
+    let key = ErrorKey {
+      variant: error.variant(),
+      prompt,
+    };
+
+    let mut message = error.to_string();
+
+    if message.len() > Self::MAX_MESSAGE_LENGTH {
+      let mut end = Self::MAX_MESSAGE_LENGTH;
+      while !message.is_char_boundary(end) {
+        end -= 1;
+      }
+      message.truncate(end);
+      message.push('…');
+    }
+
+    if let Some(reference) = reference {
+      message = format!("{} (reference `{}`)", message, reference);
+    }
+
+    if self.sender.send((key, message)).is_err() {
+      warn!("Error sink task stopped.");
+    }
+  }
+}
@@ -0,0 +1,207 @@
+use crate::common::*;
+
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Copy, Clone)]
+pub(crate) struct ErrorKey {
+  pub(crate) variant: &'static str,
+  pub(crate) prompt:  Option<PromptDiscriminant>,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub(crate) struct ErrorSummary {
+  pub(crate) count:  usize,
+  pub(crate) key:    ErrorKey,
+  pub(crate) latest: String,
+  pub(crate) window: Duration,
+}
+
+impl Display for ErrorSummary {
+  fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+    write!(
+      f,
+      "Internal error `{}` occurred {} time{} in the last {} minutes",
+      self.key.variant,
+      self.count,
+      if self.count == 1 { "" } else { "s" },
+      self.window.as_secs() / 60,
+    )?;
+
+    if let Some(prompt) = self.key.prompt {
+      write!(f, " while handling `{:?}` prompts", prompt)?;
+    }
+
+    write!(f, ".\nLatest: {}", self.latest)
+  }
+}
+
+/// At most one summary is produced for each error key per window, and
+/// suppressed occurrences are reported by `flush` once it has passed
+pub(crate) struct ErrorSummaries {
+  entries: BTreeMap<ErrorKey, Entry>,
+  window:  Duration,
+}
+
+#[derive(Default)]
+struct Entry {
+  last_alert:  Option<Instant>,
+  latest:      String,
+  occurrences: VecDeque<Instant>,
+  pending:     bool,
+}
+
+impl ErrorSummaries {
+  pub(crate) fn new(window: Duration) -> Self {
+    Self {
+      entries: BTreeMap::new(),
+      window,
+    }
+  }
+
+  pub(crate) fn record(
+    &mut self,
+    now: Instant,
+    key: ErrorKey,
+    message: String,
+  ) -> Option<ErrorSummary> {
+    let window = self.window;
+
+    let entry = self.entries.entry(key).or_default();
+
+    entry.occurrences.push_back(now);
+    entry.latest = message;
+
+    if entry
+      .last_alert
+      .map_or(true, |last_alert| now.duration_since(last_alert) >= window)
+    {
+      Some(Self::summarize(now, window, key, entry))
+    } else {
+      entry.pending = true;
+      None
+    }
+  }
+
+  pub(crate) fn flush(&mut self, now: Instant) -> Vec<ErrorSummary> {
+    let window = self.window;
+
+    let mut summaries = Vec::new();
+
+    for (key, entry) in &mut self.entries {
+      if entry.pending
+        && entry
+          .last_alert
+          .map_or(true, |last_alert| now.duration_since(last_alert) >= window)
+      {
+        summaries.push(Self::summarize(now, window, *key, entry));
+      }
+    }
+
+    self.entries.retain(|_, entry| {
+      entry
+        .last_alert
+        .map_or(false, |last_alert| now.duration_since(last_alert) < window)
+    });
+
+    summaries
+  }
+
+  fn summarize(now: Instant, window: Duration, key: ErrorKey, entry: &mut Entry) -> ErrorSummary {
+    while let Some(occurrence) = entry.occurrences.front() {
+      if now.duration_since(*occurrence) < window {
+        break;
+      }
+      entry.occurrences.pop_front();
+    }
+
+    entry.last_alert = Some(now);
+    entry.pending = false;
+
+    ErrorSummary {
+      count: entry.occurrences.len(),
+      latest: entry.latest.clone(),
+      key,
+      window,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WINDOW: Duration = Duration::from_secs(300);
+
+  fn key(variant: &'static str) -> ErrorKey {
+    ErrorKey {
+      prompt: Some(PromptDiscriminant::Candidate),
+      variant,
+    }
+  }
+
+  #[test]
+  fn first_occurrence_is_reported() {
+    let mut summaries = ErrorSummaries::new(WINDOW);
+
+    assert_eq!(
+      summaries.record(Instant::now(), key("Http"), "foo".into()),
+      Some(ErrorSummary {
+        count:  1,
+        key:    key("Http"),
+        latest: "foo".into(),
+        window: WINDOW,
+      })
+    );
+  }
+
+  #[test]
+  fn repeated_occurrences_are_suppressed_until_flushed() {
+    let mut summaries = ErrorSummaries::new(WINDOW);
+
+    let start = Instant::now();
+
+    assert!(summaries.record(start, key("Http"), "a".into()).is_some());
+    assert!(summaries
+      .record(start + Duration::from_secs(1), key("Http"), "b".into())
+      .is_none());
+    assert!(summaries
+      .record(start + Duration::from_secs(2), key("Http"), "c".into())
+      .is_none());
+
+    assert_eq!(summaries.flush(start + Duration::from_secs(60)), Vec::new());
+
+    assert_eq!(summaries.flush(start + WINDOW), vec![ErrorSummary {
+      count:  2,
+      key:    key("Http"),
+      latest: "c".into(),
+      window: WINDOW,
+    }]);
+
+    assert_eq!(summaries.flush(start + WINDOW * 2), Vec::new());
+  }
+
+  #[test]
+  fn keys_are_rate_limited_independently() {
+    let mut summaries = ErrorSummaries::new(WINDOW);
+
+    let start = Instant::now();
+
+    assert!(summaries.record(start, key("Http"), "a".into()).is_some());
+    assert!(summaries.record(start, key("Db"), "b".into()).is_some());
+    assert!(summaries.record(start, key("Http"), "c".into()).is_none());
+  }
+
+  #[test]
+  fn display() {
+    let summary = ErrorSummary {
+      count:  3,
+      key:    key("Http"),
+      latest: "Http error: 500".into(),
+      window: WINDOW,
+    };
+
+    assert_eq!(
+      summary.to_string(),
+      "Internal error `Http` occurred 3 times in the last 5 minutes while handling `Candidate` \
+       prompts.\nLatest: Http error: 500"
+    );
+  }
+}
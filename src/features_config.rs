@@ -0,0 +1,20 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct FeaturesConfig {
+  pub(crate) catch_up:       bool,
+  /// Lets users who post in server channels be told to send a direct message
+  /// instead. Off by default, since the bot would then reply to every message
+  /// in every server channel it can read.
+  pub(crate) guild_messages: bool,
+}
+
+impl Default for FeaturesConfig {
+  fn default() -> Self {
+    Self {
+      catch_up:       true,
+      guild_messages: false,
+    }
+  }
+}
@@ -0,0 +1,14 @@
+use crate::common::*;
+
+#[derive(Debug)]
+pub(crate) struct Incoming {
+  /// `None` if the transport doesn't know without a lookup
+  pub(crate) bot:        Option<bool>,
+  pub(crate) channel_id: ChannelId,
+  pub(crate) event:      EventId,
+  pub(crate) message_id: Option<MessageId>,
+  pub(crate) response:   Response,
+  pub(crate) sender:     UserId,
+  /// Only differs from `sender` in tests
+  pub(crate) user_id:    UserId,
+}
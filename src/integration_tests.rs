@@ -2,7 +2,7 @@ use crate::common::*;
 
 fn test<F: Future>(f: F) -> F::Output {
   static RUNTIME: Lazy<Runtime> =
-    Lazy::new(|| runtime::init().expect("Failed to initialize test runtime."));
+    Lazy::new(|| runtime::init(None).expect("Failed to initialize test runtime."));
 
   RUNTIME.block_on(f)
 }
@@ -136,6 +136,37 @@ fn multi_user_candidate() {
   })
 }
 
+#[instrument]
+#[test]
+#[ignore]
+fn candidate_shows_shared_tags() {
+  test(async {
+    let mut bot = test_bot!().await;
+    let mut a = bot.new_user().await;
+    let mut b = bot.new_user().await;
+
+    for (user, tags) in vec![(&mut a, "music, #Rust"), (&mut b, "music, books")] {
+      user.send_message("hi").await;
+      let id = user.expect_prompt(Prompt::Welcome).await;
+      user.send_reaction(id, Emoji::ThumbsUp).await;
+      user.expect_prompt(Prompt::Bio).await;
+      user.send_message("bio").await;
+      user.expect_prompt(Prompt::Tags).await;
+      user.send_message(tags).await;
+    }
+
+    a.expect_prompt(Prompt::Quiescent).await;
+
+    let prompt = Prompt::Candidate { id: a.id() };
+    assert!(bot
+      .db()
+      .prompt_text_outside_update_transaction(b.id(), prompt)
+      .await
+      .ends_with("You both like: music"));
+    b.expect_prompt(prompt).await;
+  })
+}
+
 #[instrument]
 #[test]
 #[ignore]
@@ -153,11 +184,12 @@ fn multi_user_candidate_accept() {
     let id = b.expect_prompt(Prompt::Candidate { id: a.id() }).await;
     b.send_reaction(id, Emoji::ThumbsDown).await;
 
+    // `b` hasn't been shown to anyone yet, so comes before `a`
     c.setup().await;
-    let id = c.expect_prompt(Prompt::Candidate { id: a.id() }).await;
+    let id = c.expect_prompt(Prompt::Candidate { id: b.id() }).await;
     c.send_reaction(id, Emoji::ThumbsUp).await;
 
-    let id = c.expect_prompt(Prompt::Candidate { id: b.id() }).await;
+    let id = c.expect_prompt(Prompt::Candidate { id: a.id() }).await;
     c.send_reaction(id, Emoji::ThumbsUp).await;
 
     c.expect_prompt(Prompt::Quiescent).await;
@@ -275,7 +307,7 @@ fn match_prompt() {
     let prompt = Prompt::Match { id: b.id() };
     assert!(bot
       .db()
-      .prompt_text_outside_update_transaction(prompt)
+      .prompt_text_outside_update_transaction(a.id(), prompt)
       .await
       .contains("b's bio!"));
     a.expect_prompt(prompt).await;
@@ -283,7 +315,7 @@ fn match_prompt() {
     let prompt = Prompt::Match { id: a.id() };
     assert!(bot
       .db()
-      .prompt_text_outside_update_transaction(prompt)
+      .prompt_text_outside_update_transaction(b.id(), prompt)
       .await
       .contains("a's bio!"));
     b.expect_prompt(prompt).await;
@@ -326,7 +358,7 @@ fn accept_candidate_with_message() {
     let prompt = Prompt::Match { id: b.id() };
     assert!(bot
       .db()
-      .prompt_text_outside_update_transaction(prompt)
+      .prompt_text_outside_update_transaction(a.id(), prompt)
       .await
       .contains("b's bio!"));
     a.expect_prompt(prompt).await;
@@ -334,7 +366,7 @@ fn accept_candidate_with_message() {
     let prompt = Prompt::Match { id: a.id() };
     assert!(bot
       .db()
-      .prompt_text_outside_update_transaction(prompt)
+      .prompt_text_outside_update_transaction(b.id(), prompt)
       .await
       .contains("a's bio!"));
     b.expect_prompt(prompt).await;
@@ -0,0 +1,34 @@
+use crate::common::*;
+
+#[derive(Debug)]
+pub(crate) struct Lease {
+  db:     Db,
+  holder: String,
+  name:   String,
+}
+
+impl Lease {
+  pub(crate) const DURATION: Duration = Duration::from_secs(30);
+
+  pub(crate) fn new(db: Db, name: String, holder: String) -> Self {
+    Self { db, holder, name }
+  }
+
+  pub(crate) async fn acquire(&self) -> Result<bool> {
+    Ok(
+      self
+        .db
+        .acquire_lease(&self.name, &self.holder, Self::DURATION)
+        .await?,
+    )
+  }
+
+  pub(crate) async fn release(&self) -> Result<()> {
+    self.db.release_lease(&self.name, &self.holder).await?;
+    Ok(())
+  }
+
+  pub(crate) fn holder(&self) -> &str {
+    &self.holder
+  }
+}
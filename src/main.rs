@@ -3,6 +3,8 @@ mod expect_var;
 #[cfg(test)]
 mod integration_tests;
 #[cfg(test)]
+mod matrix_homeserver;
+#[cfg(test)]
 mod test_bot;
 #[cfg(test)]
 mod test_dispatcher;
@@ -13,19 +15,45 @@ mod test_name;
 #[cfg(test)]
 mod test_user;
 
+mod alert_destination;
 mod arguments;
+mod assignment;
 mod async_static;
 mod bot;
 mod common;
+mod config;
+mod config_subcommand;
+mod coordinator;
+mod database_config;
+mod discord_id;
+mod discord_transport;
 mod error;
+mod error_reference;
+mod error_sink;
+mod error_summaries;
+mod features_config;
+mod incoming;
+mod lease;
 mod logging;
+mod matching_config;
+mod matrix_config;
+mod matrix_event;
+mod matrix_transport;
+mod prompts_config;
 mod rate_limit;
+mod rate_limit_config;
 mod response_future_ext;
 mod runtime;
+mod runtime_config;
+mod subcommand;
+mod terminal_transport;
 mod test_id;
 mod test_message;
 mod test_run_id;
 mod test_user_id;
+mod token_source;
+mod token_table;
+mod transport;
 
 fn main() {
   use crate::common::*;
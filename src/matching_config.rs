@@ -0,0 +1,27 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct MatchingConfig {
+  pub(crate) defer_cooldown_hours: u64,
+  pub(crate) resurface_delay_days: u64,
+}
+
+impl MatchingConfig {
+  pub(crate) fn defer_cooldown(&self) -> Duration {
+    Duration::from_secs(self.defer_cooldown_hours * 60 * 60)
+  }
+
+  pub(crate) fn resurface_delay(&self) -> Duration {
+    Duration::from_secs(self.resurface_delay_days * 24 * 60 * 60)
+  }
+}
+
+impl Default for MatchingConfig {
+  fn default() -> Self {
+    Self {
+      defer_cooldown_hours: Db::DEFAULT_DEFER_COOLDOWN.as_secs() / (60 * 60),
+      resurface_delay_days: Db::DEFAULT_RESURFACE_DELAY.as_secs() / (24 * 60 * 60),
+    }
+  }
+}
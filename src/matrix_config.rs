@@ -0,0 +1,34 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct MatrixConfig {
+  pub(crate) homeserver: Url,
+  /// Set to `default` to match Matrix users with Discord users
+  #[serde(default = "MatrixConfig::default_pool")]
+  pub(crate) pool:       String,
+  pub(crate) token:      TokenSource,
+}
+
+impl MatrixConfig {
+  fn default_pool() -> String {
+    "matrix".into()
+  }
+
+  pub(crate) fn problems(&self) -> Result<(), String> {
+    let scheme = self.homeserver.scheme();
+
+    if !matches!(scheme, "http" | "https") {
+      return Err(format!(
+        "`matrix.homeserver` has scheme `{}`, expected `http` or `https`",
+        scheme
+      ));
+    }
+
+    if self.pool.is_empty() {
+      return Err("`matrix.pool` may not be empty".into());
+    }
+
+    self.token.validate("matrix.token")
+  }
+}
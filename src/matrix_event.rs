@@ -0,0 +1,87 @@
+use crate::common::*;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct MatrixEvent {
+  #[serde(default)]
+  pub(crate) content:  Value,
+  pub(crate) event_id: String,
+  pub(crate) sender:   String,
+  #[serde(rename = "type")]
+  pub(crate) kind:     String,
+}
+
+impl MatrixEvent {
+  pub(crate) fn body(&self) -> Option<&str> {
+    if self.kind != "m.room.message" {
+      return None;
+    }
+
+    self.content["body"].as_str()
+  }
+
+  /// The variation selector is removed from the key
+  pub(crate) fn annotation(&self) -> Option<(&str, &str)> {
+    if self.kind != "m.reaction" {
+      return None;
+    }
+
+    let relates_to = &self.content["m.relates_to"];
+
+    if relates_to["rel_type"].as_str() != Some("m.annotation") {
+      return None;
+    }
+
+    Some((
+      relates_to["event_id"].as_str()?,
+      relates_to["key"].as_str()?.trim_end_matches('\u{fe0f}'),
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn event(kind: &str, content: Value) -> MatrixEvent {
+    MatrixEvent {
+      event_id: "$event".into(),
+      sender: "@alice:example.org".into(),
+      kind: kind.into(),
+      content,
+    }
+  }
+
+  #[test]
+  fn body() {
+    assert_eq!(
+      event("m.room.message", json!({"msgtype": "m.text", "body": "hi"})).body(),
+      Some("hi")
+    );
+    assert_eq!(event("m.room.topic", json!({"body": "hi"})).body(), None);
+  }
+
+  #[test]
+  fn annotation() {
+    let content = json!({
+      "m.relates_to": {
+        "rel_type": "m.annotation",
+        "event_id": "$prompt",
+        "key": "👍\u{fe0f}",
+      },
+    });
+
+    assert_eq!(
+      event("m.reaction", content.clone()).annotation(),
+      Some(("$prompt", "👍"))
+    );
+    assert_eq!(event("m.room.message", content).annotation(), None);
+    assert_eq!(
+      event(
+        "m.reaction",
+        json!({"m.relates_to": {"rel_type": "m.reference"}})
+      )
+      .annotation(),
+      None
+    );
+  }
+}
@@ -0,0 +1,528 @@
+use crate::common::*;
+
+use {
+  hyper::{
+    body,
+    service::{make_service_fn, service_fn},
+    Body, Request, Server, StatusCode,
+  },
+  std::{convert::Infallible, iter, net::TcpListener},
+};
+
+type ApiResult = Result<Value, (StatusCode, &'static str)>;
+
+/// Simulated users act through methods rather than the API, and join rooms
+/// as soon as they're invited
+#[derive(Debug, Clone)]
+pub(crate) struct MatrixHomeserver {
+  changed: Arc<Notify>,
+  inner:   Arc<Mutex<Inner>>,
+  url:     Url,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+  account_data: BTreeMap<String, Value>,
+  avatars:      BTreeMap<String, String>,
+  /// Sync and pagination tokens are indices into this
+  events:       Vec<Value>,
+  members:      BTreeMap<String, BTreeSet<String>>,
+}
+
+impl MatrixHomeserver {
+  const BOT: &'static str = "@quwue:localhost";
+  const TIMEOUT: Duration = Duration::from_secs(30);
+  const TOKEN: &'static str = "quwue-access-token";
+  const TOKEN_VAR: &'static str = "QUWUE_TEST_MATRIX_TOKEN";
+
+  pub(crate) async fn start() -> Self {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.set_nonblocking(true).unwrap();
+
+    let homeserver = Self {
+      changed: Arc::new(Notify::new()),
+      inner:   Arc::new(Mutex::new(Inner::default())),
+      url:     format!("http://{}", listener.local_addr().unwrap())
+        .parse()
+        .unwrap(),
+    };
+
+    let clone = homeserver.clone();
+
+    let server = Server::from_tcp(listener)
+      .unwrap()
+      .serve(make_service_fn(move |_| {
+        let homeserver = clone.clone();
+        async move {
+          Ok::<_, Infallible>(service_fn(move |request| {
+            homeserver.clone().handle(request)
+          }))
+        }
+      }));
+
+    tokio::spawn(server);
+
+    env::set_var(Self::TOKEN_VAR, Self::TOKEN);
+
+    homeserver
+  }
+
+  pub(crate) fn config(&self) -> MatrixConfig {
+    MatrixConfig {
+      homeserver: self.url.clone(),
+      pool:       "matrix".into(),
+      token:      TokenSource::Env(Self::TOKEN_VAR.into()),
+    }
+  }
+
+  pub(crate) async fn open_direct_room(&self, user: &str) -> String {
+    let room = {
+      let mut inner = self.inner.lock().await;
+      let room = format!("!{}:localhost", inner.members.len());
+      inner
+        .members
+        .insert(room.clone(), iter::once(user.to_owned()).collect());
+      room
+    };
+
+    self
+      .push_event(
+        &room,
+        user,
+        "m.room.member",
+        Some(Self::BOT),
+        json!({"membership": "invite", "is_direct": true}),
+      )
+      .await;
+
+    room
+  }
+
+  pub(crate) async fn send_message(&self, user: &str, room: &str, body: &str) -> String {
+    self
+      .push_event(
+        room,
+        user,
+        "m.room.message",
+        None,
+        json!({"msgtype": "m.text", "body": body}),
+      )
+      .await
+  }
+
+  pub(crate) async fn react(&self, user: &str, room: &str, event_id: &str, key: &str) {
+    let content = json!({
+      "m.relates_to": {"rel_type": "m.annotation", "event_id": event_id, "key": key},
+    });
+
+    self
+      .push_event(room, user, "m.reaction", None, content)
+      .await;
+  }
+
+  pub(crate) async fn set_avatar(&self, user: &str, mxc: &str) {
+    self
+      .inner
+      .lock()
+      .await
+      .avatars
+      .insert(user.to_owned(), mxc.to_owned());
+  }
+
+  pub(crate) async fn bot_message(
+    &self,
+    room: &str,
+    n: usize,
+    reactions: usize,
+  ) -> (String, String) {
+    self
+      .wait_until(|inner| {
+        let message = Self::bot_events(inner, room, "m.room.message")
+          .filter(|event| event["content"]["msgtype"] == "m.text")
+          .nth(n)?;
+
+        let event_id = message["event_id"].as_str()?;
+
+        let count = Self::bot_events(inner, room, "m.reaction")
+          .filter(|event| event["content"]["m.relates_to"]["event_id"] == event_id)
+          .count();
+
+        if count < reactions {
+          return None;
+        }
+
+        Some((
+          event_id.to_owned(),
+          message["content"]["body"].as_str()?.to_owned(),
+        ))
+      })
+      .await
+  }
+
+  pub(crate) async fn images(&self, room: &str) -> Vec<String> {
+    let inner = self.inner.lock().await;
+
+    Self::bot_events(&inner, room, "m.room.message")
+      .filter(|event| event["content"]["msgtype"] == "m.image")
+      .filter_map(|event| event["content"]["url"].as_str())
+      .map(str::to_owned)
+      .collect()
+  }
+
+  fn bot_events<'a>(
+    inner: &'a Inner,
+    room: &'a str,
+    kind: &'a str,
+  ) -> impl Iterator<Item = &'a Value> + 'a {
+    inner.events.iter().filter(move |event| {
+      event["room_id"] == room && event["sender"] == Self::BOT && event["type"] == kind
+    })
+  }
+
+  fn is_bot_membership(event: &Value, membership: &str) -> bool {
+    event["type"] == "m.room.member"
+      && event["state_key"] == Self::BOT
+      && event["content"]["membership"] == membership
+  }
+
+  async fn wait_until<T>(&self, f: impl Fn(&Inner) -> Option<T>) -> T {
+    let deadline = Instant::now() + Self::TIMEOUT;
+
+    loop {
+      let changed = self.changed.notified();
+
+      if let Some(value) = f(&*self.inner.lock().await) {
+        return value;
+      }
+
+      let remaining = deadline.saturating_duration_since(Instant::now());
+
+      if time::timeout(remaining, changed).await.is_err() {
+        panic!("Timed out waiting for Matrix homeserver.");
+      }
+    }
+  }
+
+  async fn push_event(
+    &self,
+    room: &str,
+    sender: &str,
+    kind: &str,
+    state_key: Option<&str>,
+    content: Value,
+  ) -> String {
+    let event_id = {
+      let mut inner = self.inner.lock().await;
+
+      let event_id = format!("${}", inner.events.len());
+
+      let mut event = json!({
+        "content": content,
+        "event_id": event_id,
+        "room_id": room,
+        "sender": sender,
+        "type": kind,
+      });
+
+      if let Some(state_key) = state_key {
+        event["state_key"] = state_key.into();
+      }
+
+      inner.events.push(event);
+
+      event_id
+    };
+
+    self.changed.notify_waiters();
+
+    event_id
+  }
+
+  async fn handle(self, request: Request<Body>) -> Result<hyper::Response<Body>, Infallible> {
+    let authorization = format!("Bearer {}", Self::TOKEN);
+
+    if request
+      .headers()
+      .get("authorization")
+      .and_then(|value| value.to_str().ok())
+      != Some(authorization.as_str())
+    {
+      return Ok(Self::respond(Err((
+        StatusCode::UNAUTHORIZED,
+        "M_UNKNOWN_TOKEN",
+      ))));
+    }
+
+    let method = request.method().as_str().to_owned();
+
+    let path = request.uri().path().to_owned();
+
+    let query = url::form_urlencoded::parse(request.uri().query().unwrap_or_default().as_bytes())
+      .into_owned()
+      .collect::<BTreeMap<String, String>>();
+
+    let body = body::to_bytes(request.into_body()).await.unwrap();
+
+    let body = serde_json::from_slice::<Value>(&body).unwrap_or(Value::Null);
+
+    let segments = path
+      .strip_prefix("/_matrix/client/r0/")
+      .unwrap_or_default()
+      .split('/')
+      .collect::<Vec<&str>>();
+
+    let result = match (method.as_str(), segments.as_slice()) {
+      ("GET", ["account", "whoami"]) => Ok(json!({ "user_id": Self::BOT })),
+      ("GET", ["sync"]) => Ok(self.sync(&query).await),
+      ("POST", ["createRoom"]) => Ok(self.create_room(&body).await),
+      ("POST", ["join", room]) => self.join(room).await,
+      ("PUT", ["rooms", room, "send", kind, _transaction]) => self.send(room, kind, body).await,
+      ("GET", ["rooms", room, "joined_members"]) => self.joined_members(room).await,
+      ("GET", ["rooms", room, "context", event_id]) => self.context(room, event_id).await,
+      ("GET", ["rooms", room, "messages"]) => Ok(self.messages(room, &query).await),
+      ("GET", ["profile", user]) => self.profile(user).await,
+      ("GET", ["user", _, "account_data", kind]) => self
+        .inner
+        .lock()
+        .await
+        .account_data
+        .get(*kind)
+        .cloned()
+        .ok_or((StatusCode::NOT_FOUND, "M_NOT_FOUND")),
+      ("PUT", ["user", _, "account_data", kind]) => {
+        self
+          .inner
+          .lock()
+          .await
+          .account_data
+          .insert((*kind).to_owned(), body);
+        Ok(json!({}))
+      },
+      _ => Err((StatusCode::NOT_FOUND, "M_UNRECOGNIZED")),
+    };
+
+    Ok(Self::respond(result))
+  }
+
+  fn respond(result: ApiResult) -> hyper::Response<Body> {
+    let (status, body) = match result {
+      Ok(body) => (StatusCode::OK, body),
+      Err((status, errcode)) => (status, json!({"errcode": errcode, "error": errcode})),
+    };
+
+    hyper::Response::builder()
+      .status(status)
+      .header("content-type", "application/json")
+      .body(Body::from(body.to_string()))
+      .unwrap()
+  }
+
+  async fn sync(&self, query: &BTreeMap<String, String>) -> Value {
+    let since = query
+      .get("since")
+      .and_then(|since| since.parse::<usize>().ok());
+
+    if let Some(since) = since {
+      let timeout = query
+        .get("timeout")
+        .and_then(|timeout| timeout.parse().ok())
+        .map_or(Duration::from_secs(0), Duration::from_millis);
+
+      let deadline = Instant::now() + timeout;
+
+      loop {
+        let changed = self.changed.notified();
+
+        if self.inner.lock().await.events.len() > since {
+          break;
+        }
+
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if time::timeout(remaining, changed).await.is_err() {
+          break;
+        }
+      }
+    }
+
+    let inner = self.inner.lock().await;
+
+    let mut join = serde_json::Map::new();
+    let mut invite = serde_json::Map::new();
+
+    for (room, members) in &inner.members {
+      let events = inner
+        .events
+        .iter()
+        .enumerate()
+        .filter(|(_, event)| event["room_id"] == room.as_str())
+        .collect::<Vec<(usize, &Value)>>();
+
+      if members.contains(Self::BOT) {
+        let since = match since {
+          Some(since) => since,
+          None => continue,
+        };
+
+        // Like a real homeserver, include the history of newly joined rooms
+        let joined = events
+          .iter()
+          .any(|(index, event)| *index >= since && Self::is_bot_membership(event, "join"));
+
+        let timeline = events
+          .iter()
+          .filter(|(index, _)| joined || *index >= since)
+          .map(|(_, event)| (*event).clone())
+          .collect::<Vec<Value>>();
+
+        if !timeline.is_empty() {
+          join.insert(room.clone(), json!({"timeline": {"events": timeline}}));
+        }
+      } else if let Some((_, invitation)) = events
+        .iter()
+        .find(|(_, event)| Self::is_bot_membership(event, "invite"))
+      {
+        invite.insert(
+          room.clone(),
+          json!({"invite_state": {"events": [invitation]}}),
+        );
+      }
+    }
+
+    json!({
+      "next_batch": inner.events.len().to_string(),
+      "rooms": {"invite": invite, "join": join},
+    })
+  }
+
+  async fn create_room(&self, body: &Value) -> Value {
+    let mut inner = self.inner.lock().await;
+
+    let room = format!("!{}:localhost", inner.members.len());
+
+    let members = body["invite"]
+      .as_array()
+      .into_iter()
+      .flatten()
+      .filter_map(Value::as_str)
+      .chain(iter::once(Self::BOT))
+      .map(str::to_owned)
+      .collect();
+
+    inner.members.insert(room.clone(), members);
+
+    json!({ "room_id": room })
+  }
+
+  async fn join(&self, room: &str) -> ApiResult {
+    if !self
+      .inner
+      .lock()
+      .await
+      .members
+      .get_mut(room)
+      .ok_or((StatusCode::NOT_FOUND, "M_NOT_FOUND"))?
+      .insert(Self::BOT.to_owned())
+    {
+      return Ok(json!({ "room_id": room }));
+    }
+
+    self
+      .push_event(
+        room,
+        Self::BOT,
+        "m.room.member",
+        Some(Self::BOT),
+        json!({"membership": "join"}),
+      )
+      .await;
+
+    Ok(json!({ "room_id": room }))
+  }
+
+  async fn send(&self, room: &str, kind: &str, content: Value) -> ApiResult {
+    if !self
+      .inner
+      .lock()
+      .await
+      .members
+      .get(room)
+      .map_or(false, |members| members.contains(Self::BOT))
+    {
+      return Err((StatusCode::FORBIDDEN, "M_FORBIDDEN"));
+    }
+
+    let event_id = self.push_event(room, Self::BOT, kind, None, content).await;
+
+    Ok(json!({ "event_id": event_id }))
+  }
+
+  async fn joined_members(&self, room: &str) -> ApiResult {
+    let inner = self.inner.lock().await;
+
+    let joined = inner
+      .members
+      .get(room)
+      .ok_or((StatusCode::NOT_FOUND, "M_NOT_FOUND"))?
+      .iter()
+      .map(|member| (member.clone(), json!({})))
+      .collect::<serde_json::Map<String, Value>>();
+
+    Ok(json!({ "joined": joined }))
+  }
+
+  async fn context(&self, room: &str, event_id: &str) -> ApiResult {
+    let index = self
+      .inner
+      .lock()
+      .await
+      .events
+      .iter()
+      .position(|event| event["room_id"] == room && event["event_id"] == event_id)
+      .ok_or((StatusCode::NOT_FOUND, "M_NOT_FOUND"))?;
+
+    Ok(json!({ "end": (index + 1).to_string() }))
+  }
+
+  async fn messages(&self, room: &str, query: &BTreeMap<String, String>) -> Value {
+    let from = query
+      .get("from")
+      .and_then(|from| from.parse::<usize>().ok())
+      .unwrap_or_default();
+
+    let limit = query
+      .get("limit")
+      .and_then(|limit| limit.parse::<usize>().ok())
+      .unwrap_or(10);
+
+    let inner = self.inner.lock().await;
+
+    let chunk = inner
+      .events
+      .iter()
+      .enumerate()
+      .skip(from)
+      .filter(|(_, event)| event["room_id"] == room)
+      .take(limit)
+      .collect::<Vec<(usize, &Value)>>();
+
+    match chunk.last() {
+      Some((last, _)) => json!({
+        "chunk": chunk.iter().map(|(_, event)| event).collect::<Vec<&&Value>>(),
+        "end": (last + 1).to_string(),
+      }),
+      None => json!({ "chunk": [] }),
+    }
+  }
+
+  async fn profile(&self, user: &str) -> ApiResult {
+    self
+      .inner
+      .lock()
+      .await
+      .avatars
+      .get(user)
+      .map(|avatar| json!({ "avatar_url": avatar }))
+      .ok_or((StatusCode::NOT_FOUND, "M_NOT_FOUND"))
+  }
+}
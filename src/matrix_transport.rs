@@ -0,0 +1,719 @@
+use crate::common::*;
+
+/// Matrix user, room, and event IDs are interned in the database, and mapped
+/// to IDs with the high bit set, so that they never collide with Discord
+/// snowflakes.
+#[derive(Debug)]
+pub(crate) struct MatrixTransport {
+  client:           reqwest::Client,
+  db:               Db,
+  /// Held while reading and updating the bot's direct message rooms
+  direct_rooms:     Mutex<()>,
+  homeserver:       Url,
+  next_transaction: AtomicU64,
+  pending:          Mutex<VecDeque<Incoming>>,
+  pool:             String,
+  since:            Mutex<Option<String>>,
+  stop:             Notify,
+  stopped:          AtomicBool,
+  sync:             bool,
+  token:            String,
+  user:             String,
+  user_id:          UserId,
+}
+
+impl MatrixTransport {
+  const EVENT_NAMESPACE: &'static str = "matrix-event";
+  const ID_FLAG: u64 = 1 << 63;
+  const ROOM_NAMESPACE: &'static str = "matrix-room";
+  const SYNC_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+  const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+  const USER_NAMESPACE: &'static str = "matrix-user";
+
+  /// Only one instance should `sync`, so that each message is only processed
+  /// once
+  pub(crate) async fn new(db: Db, config: &MatrixConfig, sync: bool) -> Result<Self> {
+    #[allow(clippy::cast_possible_truncation)]
+    let first_transaction = SystemTime::now()
+      .duration_since(SystemTime::UNIX_EPOCH)
+      .unwrap_or_default()
+      .as_millis() as u64;
+
+    let mut transport = Self {
+      client: reqwest::Client::new(),
+      direct_rooms: Mutex::new(()),
+      homeserver: config.homeserver.clone(),
+      next_transaction: AtomicU64::new(first_transaction),
+      pending: Mutex::new(VecDeque::new()),
+      pool: config.pool.clone(),
+      since: Mutex::new(None),
+      stop: Notify::new(),
+      stopped: AtomicBool::new(false),
+      token: config.token.load()?,
+      user: String::new(),
+      user_id: UserId(0),
+      db,
+      sync,
+    };
+
+    let whoami = transport
+      .request(Method::GET, &["account", "whoami"], &[], None)
+      .await?;
+
+    transport.user = Self::string(&whoami, "user_id")?;
+    transport.user_id = UserId(transport.id(Self::USER_NAMESPACE, &transport.user).await?);
+
+    if sync {
+      transport.sync_once().await?;
+    }
+
+    Ok(transport)
+  }
+
+  async fn request(
+    &self,
+    method: Method,
+    path: &[&str],
+    query: &[(&str, String)],
+    body: Option<&Value>,
+  ) -> Result<Value> {
+    let url = Self::endpoint(&self.homeserver, &["client", "r0"], path).ok_or_else(|| {
+      Error::MatrixHomeserverUrl {
+        url: self.homeserver.clone(),
+      }
+    })?;
+
+    loop {
+      let mut request = self
+        .client
+        .request(method.clone(), url.clone())
+        .bearer_auth(&self.token)
+        .query(query);
+
+      if let Some(body) = body {
+        request = request.json(body);
+      }
+
+      let response = request.send().await?;
+
+      let status = response.status();
+
+      let value = response.json::<Value>().await?;
+
+      if status.is_success() {
+        return Ok(value);
+      }
+
+      let errcode = value["errcode"].as_str().unwrap_or_default();
+
+      if errcode == "M_LIMIT_EXCEEDED" {
+        if let Some(retry_after) = value["retry_after_ms"].as_u64() {
+          warn!(
+            "Rate limited by Matrix homeserver, retrying in {}ms.",
+            retry_after
+          );
+          tokio::time::sleep(Duration::from_millis(retry_after)).await;
+          continue;
+        }
+      }
+
+      return Err(Error::MatrixApi {
+        status:  status.as_u16(),
+        errcode: errcode.to_owned(),
+        message: value["error"].as_str().unwrap_or_default().to_owned(),
+      });
+    }
+  }
+
+  fn endpoint(homeserver: &Url, api: &[&str], path: &[&str]) -> Option<Url> {
+    let mut url = homeserver.clone();
+
+    url
+      .path_segments_mut()
+      .ok()?
+      .pop_if_empty()
+      .push("_matrix")
+      .extend(api)
+      .extend(path);
+
+    Some(url)
+  }
+
+  fn media_url(homeserver: &Url, mxc: &str) -> Option<String> {
+    let (server, media) = mxc.strip_prefix("mxc://")?.split_once('/')?;
+
+    Self::endpoint(homeserver, &["media", "r0", "download"], &[server, media]).map(String::from)
+  }
+
+  /// The inverse of `media_url`
+  fn mxc(homeserver: &Url, url: &str) -> Option<String> {
+    let prefix = Self::endpoint(homeserver, &["media", "r0", "download"], &[""])?;
+
+    Some(format!("mxc://{}", url.strip_prefix(prefix.as_str())?))
+  }
+
+  fn string(value: &Value, field: &str) -> Result<String> {
+    value[field]
+      .as_str()
+      .map(str::to_owned)
+      .ok_or_else(|| Error::MatrixResponseField {
+        field: field.to_owned(),
+      })
+  }
+
+  fn events(value: &Value) -> Vec<MatrixEvent> {
+    value
+      .as_array()
+      .into_iter()
+      .flatten()
+      .filter_map(|event| match MatrixEvent::deserialize(event) {
+        Ok(event) => Some(event),
+        Err(err) => {
+          warn!("Ignoring malformed Matrix event: {}", err);
+          None
+        },
+      })
+      .collect()
+  }
+
+  async fn id(&self, namespace: &str, matrix_id: &str) -> Result<u64> {
+    Ok(self.db.intern(namespace, matrix_id).await? | Self::ID_FLAG)
+  }
+
+  async fn matrix_id(&self, namespace: &str, id: u64) -> Result<String> {
+    self
+      .db
+      .external_id(namespace, id & !Self::ID_FLAG)
+      .await?
+      .ok_or(Error::MatrixIdUnknown { id })
+  }
+
+  async fn room(&self, channel_id: ChannelId) -> Result<String> {
+    self.matrix_id(Self::ROOM_NAMESPACE, channel_id.0).await
+  }
+
+  async fn mxid(&self, user_id: UserId) -> Result<String> {
+    self.matrix_id(Self::USER_NAMESPACE, user_id.0).await
+  }
+
+  async fn incoming(&self, room: &str, event: &MatrixEvent) -> Result<Option<Incoming>> {
+    if event.sender == self.user || (event.body().is_none() && event.annotation().is_none()) {
+      return Ok(None);
+    }
+
+    let sender = UserId(self.id(Self::USER_NAMESPACE, &event.sender).await?);
+
+    let (event_id, message_id, response) = if let Some((annotated, key)) = event.annotation() {
+      let message_id = MessageId(self.id(Self::EVENT_NAMESPACE, annotated).await?);
+      (
+        EventId::Reaction {
+          user_id: sender,
+          emoji: key.to_owned(),
+          message_id,
+        },
+        Some(message_id),
+        Response::unicode_reaction(key.to_owned()),
+      )
+    } else {
+      (
+        EventId::Message {
+          id: MessageId(self.id(Self::EVENT_NAMESPACE, &event.event_id).await?),
+        },
+        None,
+        Response::message(event.body().unwrap_or_default()),
+      )
+    };
+
+    Ok(Some(Incoming {
+      bot: Some(false),
+      channel_id: ChannelId(self.id(Self::ROOM_NAMESPACE, room).await?),
+      event: event_id,
+      user_id: sender,
+      message_id,
+      response,
+      sender,
+    }))
+  }
+
+  /// The first sync only establishes a starting point, since earlier
+  /// messages are processed by catch up
+  async fn sync_once(&self) -> Result<()> {
+    let mut since = self.since.lock().await;
+
+    let timeout = if since.is_some() {
+      Self::SYNC_TIMEOUT
+    } else {
+      Duration::from_secs(0)
+    };
+
+    let mut query = vec![("timeout", timeout.as_millis().to_string())];
+
+    if let Some(since) = &*since {
+      query.push(("since", since.clone()));
+    }
+
+    let response = self.request(Method::GET, &["sync"], &query, None).await?;
+
+    for (room, invite) in response["rooms"]["invite"]
+      .as_object()
+      .into_iter()
+      .flatten()
+    {
+      self.accept_invite(room, invite).await?;
+    }
+
+    if since.is_some() {
+      for (room, joined) in response["rooms"]["join"].as_object().into_iter().flatten() {
+        for event in Self::events(&joined["timeline"]["events"]) {
+          if let Some(incoming) = self.incoming(room, &event).await? {
+            self.pending.lock().await.push_back(incoming);
+          }
+        }
+      }
+    }
+
+    *since = Some(Self::string(&response, "next_batch")?);
+
+    Ok(())
+  }
+
+  async fn accept_invite(&self, room: &str, invite: &Value) -> Result<()> {
+    info!("Accepting invitation to Matrix room {}.", room);
+
+    self
+      .request(Method::POST, &["join", room], &[], Some(&json!({})))
+      .await?;
+
+    let membership = invite["invite_state"]["events"]
+      .as_array()
+      .into_iter()
+      .flatten()
+      .find(|event| {
+        event["type"].as_str() == Some("m.room.member")
+          && event["state_key"].as_str() == Some(self.user.as_str())
+      });
+
+    if let Some(membership) = membership {
+      if let (Some(true), Some(inviter)) = (
+        membership["content"]["is_direct"].as_bool(),
+        membership["sender"].as_str(),
+      ) {
+        let _guard = self.direct_rooms.lock().await;
+        self.add_direct_room(inviter, room).await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Stored in `m.direct` account data so that they survive restarts
+  async fn direct_rooms(&self) -> Result<Value> {
+    let path = ["user", &self.user, "account_data", "m.direct"];
+
+    match self.request(Method::GET, &path, &[], None).await {
+      Err(Error::MatrixApi { errcode, .. }) if errcode == "M_NOT_FOUND" => Ok(json!({})),
+      result => result,
+    }
+  }
+
+  /// Must be called while holding the `direct_rooms` lock
+  async fn add_direct_room(&self, user: &str, room: &str) -> Result<()> {
+    let mut direct_rooms = self.direct_rooms().await?;
+
+    if !direct_rooms.is_object() {
+      direct_rooms = json!({});
+    }
+
+    let rooms = &mut direct_rooms[user];
+
+    if !rooms.is_array() {
+      *rooms = json!([]);
+    }
+
+    if let Some(rooms) = rooms.as_array_mut() {
+      if rooms.iter().any(|existing| existing.as_str() == Some(room)) {
+        return Ok(());
+      }
+
+      rooms.push(room.into());
+    }
+
+    let path = ["user", &self.user, "account_data", "m.direct"];
+
+    self
+      .request(Method::PUT, &path, &[], Some(&direct_rooms))
+      .await?;
+
+    Ok(())
+  }
+
+  async fn send_event(&self, room: &str, kind: &str, content: &Value) -> Result<String> {
+    let transaction = self
+      .next_transaction
+      .fetch_add(1, Ordering::Relaxed)
+      .to_string();
+
+    let response = self
+      .request(
+        Method::PUT,
+        &["rooms", room, "send", kind, &transaction],
+        &[],
+        Some(content),
+      )
+      .await?;
+
+    Self::string(&response, "event_id")
+  }
+
+  async fn send_text(&self, room: &str, body: &str) -> Result<String> {
+    self
+      .send_event(
+        room,
+        "m.room.message",
+        &json!({"msgtype": "m.text", "body": body}),
+      )
+      .await
+  }
+}
+
+#[async_trait]
+impl Transport for MatrixTransport {
+  fn user_id(&self) -> UserId {
+    self.user_id
+  }
+
+  fn owns(&self, user_id: UserId) -> bool {
+    user_id.0 & Self::ID_FLAG != 0
+  }
+
+  fn pool(&self) -> &str {
+    &self.pool
+  }
+
+  async fn receive(&self) -> Option<Result<Incoming>> {
+    loop {
+      if self.stopped.load(Ordering::Relaxed) {
+        return None;
+      }
+
+      if let Some(incoming) = self.pending.lock().await.pop_front() {
+        return Some(Ok(incoming));
+      }
+
+      if !self.sync {
+        self.stop.notified().await;
+        continue;
+      }
+
+      tokio::select! {
+        () = self.stop.notified() => {},
+        result = self.sync_once() => if let Err(err) = result {
+          tokio::time::sleep(Self::SYNC_RETRY_INTERVAL).await;
+          return Some(Err(err));
+        },
+      }
+    }
+  }
+
+  fn stop(&self) {
+    self.stopped.store(true, Ordering::Relaxed);
+    self.stop.notify_one();
+  }
+
+  async fn missed_responses(
+    &self,
+    user_id: UserId,
+    prompt_message: PromptMessage,
+  ) -> Result<Vec<Incoming>> {
+    let user = self.mxid(user_id).await?;
+
+    let room = self.room(self.private_channel(user_id).await?).await?;
+
+    let prompt = self
+      .matrix_id(Self::EVENT_NAMESPACE, prompt_message.message_id.0)
+      .await?;
+
+    let context = self
+      .request(
+        Method::GET,
+        &["rooms", &room, "context", &prompt],
+        &[("limit", "0".into())],
+        None,
+      )
+      .await?;
+
+    let mut from = Self::string(&context, "end")?;
+
+    let mut missed = Vec::new();
+
+    loop {
+      let page = self
+        .request(
+          Method::GET,
+          &["rooms", &room, "messages"],
+          &[
+            ("dir", "f".into()),
+            ("from", from.clone()),
+            ("limit", "100".into()),
+          ],
+          None,
+        )
+        .await?;
+
+      for event in Self::events(&page["chunk"]) {
+        if event.sender == user {
+          missed.extend(self.incoming(&room, &event).await?);
+        }
+      }
+
+      from = match page["end"].as_str() {
+        Some(end) if end != from => end.to_owned(),
+        _ => break,
+      };
+    }
+
+    Ok(missed)
+  }
+
+  async fn private_channel(&self, user_id: UserId) -> Result<ChannelId> {
+    let user = self.mxid(user_id).await?;
+
+    let _guard = self.direct_rooms.lock().await;
+
+    let existing = self.direct_rooms().await?[&user]
+      .as_array()
+      .and_then(|rooms| rooms.last())
+      .and_then(Value::as_str)
+      .map(str::to_owned);
+
+    let room = if let Some(room) = existing {
+      room
+    } else {
+      let body = json!({
+        "invite": [user],
+        "is_direct": true,
+        "preset": "trusted_private_chat",
+      });
+
+      let response = self
+        .request(Method::POST, &["createRoom"], &[], Some(&body))
+        .await?;
+
+      let room = Self::string(&response, "room_id")?;
+
+      self.add_direct_room(&user, &room).await?;
+
+      room
+    };
+
+    Ok(ChannelId(self.id(Self::ROOM_NAMESPACE, &room).await?))
+  }
+
+  async fn is_private_channel(&self, channel_id: ChannelId) -> Result<bool> {
+    let room = self.room(channel_id).await?;
+
+    let members = self
+      .request(Method::GET, &["rooms", &room, "joined_members"], &[], None)
+      .await?;
+
+    Ok(
+      members["joined"]
+        .as_object()
+        .map_or(false, |joined| joined.len() <= 2),
+    )
+  }
+
+  async fn is_bot(&self, user_id: UserId) -> Result<bool> {
+    Ok(user_id == self.user_id)
+  }
+
+  async fn profile_image_url(&self, user_id: UserId) -> Result<Option<String>> {
+    let user = self.mxid(user_id).await?;
+
+    let profile = match self
+      .request(Method::GET, &["profile", &user], &[], None)
+      .await
+    {
+      Err(Error::MatrixApi { errcode, .. }) if errcode == "M_NOT_FOUND" => return Ok(None),
+      result => result?,
+    };
+
+    Ok(
+      profile["avatar_url"]
+        .as_str()
+        .and_then(|mxc| Self::media_url(&self.homeserver, mxc)),
+    )
+  }
+
+  async fn mention(&self, user_id: UserId) -> Result<String> {
+    self.mxid(user_id).await
+  }
+
+  async fn send_prompt(
+    &self,
+    _recipient_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+    image_url: Option<String>,
+    choices: &[Emoji],
+  ) -> Result<MessageId> {
+    let room = self.room(channel_id).await?;
+
+    let mut body = content.to_owned();
+
+    if let Some(image_url) = image_url {
+      if let Some(mxc) = Self::mxc(&self.homeserver, &image_url) {
+        self
+          .send_event(
+            &room,
+            "m.room.message",
+            &json!({"msgtype": "m.image", "body": "Profile picture", "url": mxc}),
+          )
+          .await?;
+      } else {
+        body.push('\n');
+        body.push_str(&image_url);
+      }
+    }
+
+    let event_id = self.send_text(&room, &body).await?;
+
+    for emoji in choices.iter().copied() {
+      let content = json!({
+        "m.relates_to": {
+          "rel_type": "m.annotation",
+          "event_id": event_id,
+          "key": emoji.unicode(),
+        },
+      });
+
+      self.send_event(&room, "m.reaction", &content).await?;
+    }
+
+    Ok(MessageId(self.id(Self::EVENT_NAMESPACE, &event_id).await?))
+  }
+
+  async fn send_message(&self, channel_id: ChannelId, content: &str) -> Result<()> {
+    let room = self.room(channel_id).await?;
+
+    self.send_text(&room, content).await?;
+
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const ALICE: &str = "@alice:localhost";
+  const BOB: &str = "@bob:localhost";
+
+  async fn bot(homeserver: &MatrixHomeserver) {
+    let db = Db::memory();
+
+    let transport = MatrixTransport::new(db.clone(), &homeserver.config(), true)
+      .await
+      .unwrap();
+
+    let bot = Bot::new(
+      db,
+      Assignment::new(0, 1, 1),
+      Config::default(),
+      None,
+      vec![Arc::new(transport)],
+      None,
+    );
+
+    tokio::spawn(bot.run());
+  }
+
+  async fn set_up(homeserver: &MatrixHomeserver, user: &str, bio: &str) -> String {
+    let room = homeserver.open_direct_room(user).await;
+
+    homeserver.send_message(user, &room, "hi").await;
+    let (welcome, text) = homeserver.bot_message(&room, 0, 1).await;
+    assert!(text.starts_with("Hi!\n"), "{}", text);
+
+    homeserver.react(user, &room, &welcome, "👍\u{fe0f}").await;
+    let (_, text) = homeserver.bot_message(&room, 1, 0).await;
+    assert_eq!(text, "Please enter a bio to show to other users.");
+
+    homeserver.send_message(user, &room, bio).await;
+    let (_, text) = homeserver.bot_message(&room, 2, 0).await;
+    assert!(text.starts_with("What are you into?"), "{}", text);
+
+    homeserver.send_message(user, &room, "none").await;
+
+    room
+  }
+
+  #[test]
+  fn media_urls() {
+    let homeserver = "https://matrix.example.com".parse().unwrap();
+
+    let url = MatrixTransport::media_url(&homeserver, "mxc://example.org/abc").unwrap();
+    assert_eq!(
+      url,
+      "https://matrix.example.com/_matrix/media/r0/download/example.org/abc"
+    );
+
+    assert_eq!(
+      MatrixTransport::mxc(&homeserver, &url).as_deref(),
+      Some("mxc://example.org/abc")
+    );
+    assert_eq!(
+      MatrixTransport::mxc(&homeserver, "https://cdn.discordapp.com/avatars/1/a.png"),
+      None
+    );
+    assert_eq!(
+      MatrixTransport::media_url(&homeserver, "https://example.org/abc"),
+      None
+    );
+  }
+
+  #[tokio::test]
+  async fn welcome_and_bio() {
+    let homeserver = MatrixHomeserver::start().await;
+    bot(&homeserver).await;
+
+    let room = set_up(&homeserver, ALICE, "Alice's bio").await;
+
+    let (_, text) = homeserver.bot_message(&room, 3, 0).await;
+    assert!(text.starts_with("You've seen all available matches."));
+  }
+
+  #[tokio::test]
+  async fn users_are_matched() {
+    let homeserver = MatrixHomeserver::start().await;
+    homeserver.set_avatar(ALICE, "mxc://localhost/alice").await;
+    bot(&homeserver).await;
+
+    let alice = set_up(&homeserver, ALICE, "Alice's bio").await;
+    homeserver.bot_message(&alice, 3, 0).await;
+
+    let bob = set_up(&homeserver, BOB, "Bob's bio").await;
+    let (candidate, text) = homeserver.bot_message(&bob, 3, 5).await;
+    assert_eq!(text, "New potential match:\nAlice's bio");
+    assert_eq!(homeserver.images(&bob).await, &["mxc://localhost/alice"]);
+
+    homeserver.react(BOB, &bob, &candidate, "👍").await;
+    let (candidate, text) = homeserver.bot_message(&alice, 4, 5).await;
+    assert_eq!(text, "New potential match:\nBob's bio");
+
+    homeserver.react(ALICE, &alice, &candidate, "👍").await;
+    let (_, text) = homeserver.bot_message(&alice, 5, 1).await;
+    assert!(
+      text.starts_with("You matched with @bob:localhost:\nBob's bio\n"),
+      "{}",
+      text
+    );
+
+    let (_, text) = homeserver.bot_message(&bob, 5, 1).await;
+    assert!(
+      text.starts_with("You matched with @alice:localhost:\nAlice's bio\n"),
+      "{}",
+      text
+    );
+  }
+}
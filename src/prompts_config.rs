@@ -0,0 +1,52 @@
+use crate::common::*;
+
+/// Replacement text for prompts that don't include another user's bio.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct PromptsConfig {
+  pub(crate) bio:       Option<String>,
+  pub(crate) quiescent: Option<String>,
+  pub(crate) tags:      Option<String>,
+  pub(crate) welcome:   Option<String>,
+}
+
+impl PromptsConfig {
+  pub(crate) fn text(&self, prompt: Prompt) -> Option<&str> {
+    match prompt {
+      Prompt::Bio => self.bio.as_deref(),
+      Prompt::Quiescent => self.quiescent.as_deref(),
+      Prompt::Tags => self.tags.as_deref(),
+      Prompt::Welcome => self.welcome.as_deref(),
+      Prompt::Candidate { .. }
+      | Prompt::Match { .. }
+      | Prompt::Age
+      | Prompt::AgeRange
+      | Prompt::UtcOffset
+      | Prompt::TimezoneWindow
+      | Prompt::Languages
+      | Prompt::Connections
+      | Prompt::QuestionChoice { .. }
+      | Prompt::QuestionAccepted { .. }
+      | Prompt::QuestionImportance { .. } => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn overrides() {
+    let prompts = PromptsConfig {
+      bio:       None,
+      quiescent: Some("All done!".into()),
+      tags:      None,
+      welcome:   None,
+    };
+
+    assert_eq!(prompts.text(Prompt::Quiescent), Some("All done!"));
+    assert_eq!(prompts.text(Prompt::Welcome), None);
+    assert_eq!(prompts.text(Prompt::Bio), None);
+  }
+}
@@ -2,7 +2,7 @@ use crate::common::*;
 
 static NEXT_ACTION_START_TIME: Mutex<Option<Instant>> = Mutex::const_new(None);
 
-pub(crate) async fn wait() {
+pub(crate) async fn wait(interval: Duration) {
   let mut next_action_start_time = NEXT_ACTION_START_TIME.lock().await;
 
   let now = Instant::now();
@@ -13,5 +13,5 @@ pub(crate) async fn wait() {
     tokio::time::sleep(duration).await;
   }
 
-  *next_action_start_time = Some(Instant::now() + Duration::from_millis(1100));
+  *next_action_start_time = Some(Instant::now() + interval);
 }
@@ -0,0 +1,22 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RateLimitConfig {
+  /// Minimum delay between consecutive Discord API actions
+  pub(crate) action_interval_milliseconds: u64,
+}
+
+impl RateLimitConfig {
+  pub(crate) fn action_interval(&self) -> Duration {
+    Duration::from_millis(self.action_interval_milliseconds)
+  }
+}
+
+impl Default for RateLimitConfig {
+  fn default() -> Self {
+    Self {
+      action_interval_milliseconds: 1100,
+    }
+  }
+}
@@ -1,10 +1,13 @@
 use crate::common::*;
 
-pub(crate) fn init() -> Result<Runtime> {
-  let runtime = tokio::runtime::Builder::new_multi_thread()
-    .enable_all()
-    .build()
-    .context(error::Runtime)?;
+pub(crate) fn init(worker_threads: Option<usize>) -> Result<Runtime> {
+  let mut builder = tokio::runtime::Builder::new_multi_thread();
+
+  if let Some(worker_threads) = worker_threads {
+    builder.worker_threads(worker_threads);
+  }
+
+  let runtime = builder.enable_all().build().context(error::Runtime)?;
 
   Ok(runtime)
 }
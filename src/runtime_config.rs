@@ -0,0 +1,8 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct RuntimeConfig {
+  /// Number of tokio worker threads, defaults to the number of CPU cores
+  pub(crate) worker_threads: Option<usize>,
+}
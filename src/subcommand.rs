@@ -0,0 +1,18 @@
+use crate::common::*;
+
+#[derive(StructOpt)]
+pub(crate) enum Subcommand {
+  #[structopt(about = "Manage configuration")]
+  Config(ConfigSubcommand),
+  #[structopt(about = "Play through prompt flows as simulated users against a scratch database")]
+  Repl,
+}
+
+impl Subcommand {
+  pub(crate) fn run(&self, arguments: &Arguments) -> Result<()> {
+    match self {
+      Self::Config(subcommand) => subcommand.run(arguments),
+      Self::Repl => Bot::repl(arguments),
+    }
+  }
+}
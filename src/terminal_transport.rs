@@ -0,0 +1,603 @@
+use crate::common::*;
+
+use std::{io::BufRead, str::FromStr, thread};
+
+/// Each user's private channel has the same ID as the user, and `:public`
+/// posts to a server channel with ID 0
+#[derive(Debug)]
+pub(crate) struct TerminalTransport {
+  ids:     AtomicU64,
+  input:   Mutex<mpsc::UnboundedReceiver<String>>,
+  output:  mpsc::UnboundedSender<String>,
+  prompts: Mutex<BTreeMap<UserId, (MessageId, Vec<Emoji>)>>,
+  stop:    Notify,
+  stopped: AtomicBool,
+  user:    Mutex<Option<UserId>>,
+  users:   Mutex<BTreeMap<UserId, String>>,
+}
+
+impl TerminalTransport {
+  const BOT: UserId = UserId(u64::MAX);
+  const HELP: &'static str = concat!(
+    "Lines are sent as messages from the current user. Commands:\n",
+    "  :as NAME      Respond as NAME, who is created if they don't exist yet\n",
+    "  :react EMOJI  React to the current user's latest prompt with an emoji,\n",
+    "                its name, or its number in the list of choices\n",
+    "  :public TEXT  Post TEXT as the current user in a server channel\n",
+    "  :users        List users\n",
+    "  :help         Show this message\n",
+    "  :quit         Exit",
+  );
+  const PUBLIC: ChannelId = ChannelId(0);
+
+  pub(crate) fn new(
+    input: mpsc::UnboundedReceiver<String>,
+    output: mpsc::UnboundedSender<String>,
+  ) -> Self {
+    Self {
+      ids: AtomicU64::new(0),
+      input: Mutex::new(input),
+      prompts: Mutex::new(BTreeMap::new()),
+      stop: Notify::new(),
+      stopped: AtomicBool::new(false),
+      user: Mutex::new(None),
+      users: Mutex::new(BTreeMap::new()),
+      output,
+    }
+  }
+
+  /// Must be called from within a runtime
+  pub(crate) fn stdio() -> Self {
+    let (input, receiver) = mpsc::unbounded_channel();
+    let (sender, mut output) = mpsc::unbounded_channel::<String>();
+
+    // A blocking task would keep the runtime from shutting down
+    thread::spawn(move || {
+      for line in io::stdin().lock().lines() {
+        match line {
+          Ok(line) =>
+            if input.send(line).is_err() {
+              break;
+            },
+          Err(err) => {
+            eprintln!("Failed to read from standard input: {}", err);
+            break;
+          },
+        }
+      }
+    });
+
+    tokio::spawn(async move {
+      while let Some(content) = output.recv().await {
+        println!("{}", content);
+      }
+    });
+
+    let transport = Self::new(receiver, sender);
+
+    transport.print(Self::HELP);
+
+    transport
+  }
+
+  fn print(&self, content: &str) {
+    self.output.send(content.to_owned()).ok();
+  }
+
+  fn next_id(&self) -> u64 {
+    self.ids.fetch_add(1, Ordering::Relaxed) + 1
+  }
+
+  async fn name(&self, user_id: UserId) -> Result<String> {
+    self
+      .users
+      .lock()
+      .await
+      .get(&user_id)
+      .cloned()
+      .ok_or(Error::TerminalUserUnknown { user_id })
+  }
+
+  async fn deliver(&self, channel_id: ChannelId, content: &str) -> Result<()> {
+    let name = if channel_id == Self::PUBLIC {
+      "#public".into()
+    } else {
+      self.name(UserId(channel_id.0)).await?
+    };
+
+    self.print(
+      &content
+        .lines()
+        .map(|line| format!("[{}] {}", name, line))
+        .collect::<Vec<String>>()
+        .join("\n"),
+    );
+
+    Ok(())
+  }
+
+  async fn incoming(&self, line: &str) -> Option<Incoming> {
+    let (command, argument) = match line.strip_prefix(':') {
+      Some(command) => {
+        let mut split = command.splitn(2, ' ');
+        (
+          split.next().map(str::trim),
+          split.next().map(str::trim).unwrap_or_default(),
+        )
+      },
+      None => (None, line),
+    };
+
+    let public = match command {
+      None => false,
+      Some("public") if !argument.is_empty() => true,
+      Some("as") if !argument.is_empty() => {
+        self.switch(argument).await;
+        return None;
+      },
+      Some("react") if !argument.is_empty() => return self.reaction(argument).await,
+      Some("users") => {
+        let users = self.users.lock().await;
+        if users.is_empty() {
+          self.print("No users yet.");
+        } else {
+          self.print(&users.values().cloned().collect::<Vec<String>>().join("\n"));
+        }
+        return None;
+      },
+      Some("help") => {
+        self.print(Self::HELP);
+        return None;
+      },
+      Some(_) => {
+        self.print(&format!("Unrecognized command `{}`. Try `:help`.", line));
+        return None;
+      },
+    };
+
+    let user_id = self.current_user().await?;
+
+    let id = MessageId(self.next_id());
+
+    Some(Incoming {
+      bot: Some(false),
+      channel_id: if public {
+        Self::PUBLIC
+      } else {
+        ChannelId(user_id.0)
+      },
+      event: EventId::Message { id },
+      message_id: None,
+      response: Response::message(argument),
+      sender: user_id,
+      user_id,
+    })
+  }
+
+  async fn current_user(&self) -> Option<UserId> {
+    let user = *self.user.lock().await;
+
+    if user.is_none() {
+      self.print("Choose a user to respond as with `:as NAME` first.");
+    }
+
+    user
+  }
+
+  async fn switch(&self, name: &str) {
+    let mut users = self.users.lock().await;
+
+    let user_id = if let Some((user_id, _)) = users.iter().find(|(_, user)| *user == name) {
+      *user_id
+    } else {
+      let user_id = UserId(self.next_id());
+      users.insert(user_id, name.to_owned());
+      user_id
+    };
+
+    *self.user.lock().await = Some(user_id);
+
+    self.print(&format!("Responding as {}.", name));
+  }
+
+  async fn reaction(&self, argument: &str) -> Option<Incoming> {
+    let user_id = self.current_user().await?;
+
+    let (message_id, choices) = if let Some(prompt) = self.prompts.lock().await.get(&user_id) {
+      prompt.clone()
+    } else {
+      self.print("No prompt to react to.");
+      return None;
+    };
+
+    let response = if let Some(emoji) = argument
+      .parse::<usize>()
+      .ok()
+      .and_then(|number| choices.get(number.checked_sub(1)?))
+    {
+      Response::Reaction(*emoji)
+    } else if let Ok(emoji) = Emoji::from_str(argument) {
+      Response::Reaction(emoji)
+    } else {
+      Response::unicode_reaction(argument.to_owned())
+    };
+
+    let emoji = match &response {
+      Response::Reaction(emoji) => emoji.unicode().to_owned(),
+      _ => argument.to_owned(),
+    };
+
+    Some(Incoming {
+      bot: Some(false),
+      channel_id: ChannelId(user_id.0),
+      event: EventId::Reaction {
+        user_id,
+        message_id,
+        emoji,
+      },
+      message_id: Some(message_id),
+      sender: user_id,
+      response,
+      user_id,
+    })
+  }
+}
+
+#[async_trait]
+impl Transport for TerminalTransport {
+  fn user_id(&self) -> UserId {
+    Self::BOT
+  }
+
+  fn owns(&self, _user_id: UserId) -> bool {
+    true
+  }
+
+  fn pool(&self) -> &str {
+    Db::DEFAULT_POOL
+  }
+
+  async fn receive(&self) -> Option<Result<Incoming>> {
+    loop {
+      if self.stopped.load(Ordering::Relaxed) {
+        return None;
+      }
+
+      let line = tokio::select! {
+        () = self.stop.notified() => continue,
+        line = async { self.input.lock().await.recv().await } => line?,
+      };
+
+      let line = line.trim();
+
+      if line == ":quit" {
+        return None;
+      }
+
+      if line.is_empty() {
+        continue;
+      }
+
+      if let Some(incoming) = self.incoming(line).await {
+        return Some(Ok(incoming));
+      }
+    }
+  }
+
+  fn stop(&self) {
+    self.stopped.store(true, Ordering::Relaxed);
+    self.stop.notify_one();
+  }
+
+  async fn missed_responses(
+    &self,
+    _user_id: UserId,
+    _prompt_message: PromptMessage,
+  ) -> Result<Vec<Incoming>> {
+    Ok(Vec::new())
+  }
+
+  async fn private_channel(&self, user_id: UserId) -> Result<ChannelId> {
+    Ok(ChannelId(user_id.0))
+  }
+
+  async fn is_private_channel(&self, channel_id: ChannelId) -> Result<bool> {
+    Ok(channel_id != Self::PUBLIC)
+  }
+
+  async fn is_bot(&self, user_id: UserId) -> Result<bool> {
+    Ok(user_id == Self::BOT)
+  }
+
+  async fn profile_image_url(&self, _user_id: UserId) -> Result<Option<String>> {
+    Ok(None)
+  }
+
+  async fn mention(&self, user_id: UserId) -> Result<String> {
+    Ok(format!("@{}", self.name(user_id).await?))
+  }
+
+  async fn send_prompt(
+    &self,
+    recipient_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+    image_url: Option<String>,
+    choices: &[Emoji],
+  ) -> Result<MessageId> {
+    let mut content = content.to_owned();
+
+    if let Some(image_url) = image_url {
+      content.push_str(&format!("\nImage: {}", image_url));
+    }
+
+    if !choices.is_empty() {
+      content.push_str("\nChoices:");
+      for (i, emoji) in choices.iter().enumerate() {
+        content.push_str(&format!(" {}. {}", i + 1, emoji.unicode()));
+      }
+    }
+
+    self.deliver(channel_id, &content).await?;
+
+    let message_id = MessageId(self.next_id());
+
+    self
+      .prompts
+      .lock()
+      .await
+      .insert(recipient_id, (message_id, choices.to_vec()));
+
+    Ok(message_id)
+  }
+
+  async fn send_message(&self, channel_id: ChannelId, content: &str) -> Result<()> {
+    self.deliver(channel_id, content).await
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Repl {
+    input:  mpsc::UnboundedSender<String>,
+    output: mpsc::UnboundedReceiver<String>,
+  }
+
+  impl Repl {
+    fn start() -> Self {
+      let (input, receiver) = mpsc::unbounded_channel();
+      let (sender, output) = mpsc::unbounded_channel();
+
+      let bot = Bot::new(
+        Db::memory(),
+        Assignment::new(0, 1, 1),
+        Config::default(),
+        None,
+        vec![Arc::new(TerminalTransport::new(receiver, sender))],
+        None,
+      );
+
+      tokio::spawn(bot.run());
+
+      Self { input, output }
+    }
+
+    fn send_quietly(&mut self, line: &str) {
+      self.input.send(line.into()).unwrap();
+    }
+
+    async fn send(&mut self, line: &str, expected: &str) {
+      self.input.send(line.into()).unwrap();
+
+      let output = time::timeout(Duration::from_secs(10), self.output.recv())
+        .await
+        .expect("Timed out waiting for output")
+        .unwrap();
+
+      assert!(
+        output.starts_with(expected),
+        "Expected output starting with {:?}, got {:?}",
+        expected,
+        output
+      );
+    }
+  }
+
+  #[tokio::test]
+  async fn commands() {
+    let mut repl = Repl::start();
+
+    repl.send("hi", "Choose a user to respond as").await;
+    repl.send(":users", "No users yet.").await;
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send(":react 1", "No prompt to react to.").await;
+    repl
+      .send(":frobnicate", "Unrecognized command `:frobnicate`.")
+      .await;
+    repl.send(":users", "alice").await;
+  }
+
+  #[tokio::test]
+  async fn public_messages_are_answered_with_guidance() {
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl
+      .send(
+        ":public hi",
+        "[#public] Please send me a direct message instead of posting in a server channel.",
+      )
+      .await;
+    repl.send("hi", "[alice] Hi!").await;
+  }
+
+  #[tokio::test]
+  async fn users_see_their_prompts() {
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send("hi", "[alice] Hi!\n").await;
+    repl
+      .send(
+        ":react 1",
+        "[alice] Please enter a bio to show to other users.",
+      )
+      .await;
+    repl.send("Alice's bio", "[alice] What are you into?").await;
+    repl
+      .send("music", "[alice] You've seen all available matches.")
+      .await;
+
+    repl.send(":as bob", "Responding as bob.").await;
+    repl.send("hi", "[bob] Hi!\n").await;
+    repl
+      .send(
+        ":react thumbsup",
+        "[bob] Please enter a bio to show to other users.",
+      )
+      .await;
+    repl.send("Bob's bio", "[bob] What are you into?").await;
+    repl
+      .send(
+        "books, music",
+        concat!(
+          "[bob] New potential match:\n[bob] Alice's bio\n",
+          "[bob] You both like: music\n",
+          "[bob] Choices: 1. 👍 2. 👎 3. ⏭\u{fe0f} 4. ⭐ 5. 🚩",
+        ),
+      )
+      .await;
+    repl
+      .send(":react 3", "[bob] You've seen all available matches.")
+      .await;
+  }
+
+  #[tokio::test]
+  async fn responses_can_be_undone() {
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send("hi", "[alice] Hi!").await;
+    repl.send(":react 1", "[alice] Please enter a bio").await;
+    repl.send("Alice's bio", "[alice] What are you into?").await;
+    repl.send("none", "[alice] You've seen all").await;
+
+    repl.send(":as bob", "Responding as bob.").await;
+    repl.send("hi", "[bob] Hi!").await;
+    repl.send(":react 1", "[bob] Please enter a bio").await;
+    repl.send("Bob's bio", "[bob] What are you into?").await;
+    repl.send("none", "[bob] New potential match:").await;
+    repl.send(":react 2", "[bob] You've seen all").await;
+    repl
+      .send("!undo", "[bob] New potential match:\n[bob] Alice's bio")
+      .await;
+  }
+
+  #[tokio::test]
+  async fn preferences_are_mutual() {
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send("hi", "[alice] Hi!").await;
+    repl.send(":react 1", "[alice] Please enter a bio").await;
+    repl.send("Alice's bio", "[alice] What are you into?").await;
+    repl.send("none", "[alice] You've seen all").await;
+
+    repl.send("!prefs", "[alice] How old are you?").await;
+    repl.send("skip", "[alice] Which ages").await;
+    repl.send("3", "[alice] What's your timezone").await;
+    repl.send("skip", "[alice] Which languages").await;
+    repl.send("skip", "[alice] What are you looking for?").await;
+    repl.send("any", "[alice] You've seen all").await;
+
+    // Bob has no preferences, but isn't in the age bracket Alice wants
+    repl.send(":as bob", "Responding as bob.").await;
+    repl.send("hi", "[bob] Hi!").await;
+    repl.send(":react 1", "[bob] Please enter a bio").await;
+    repl.send("Bob's bio", "[bob] What are you into?").await;
+    repl.send("none", "[bob] You've seen all").await;
+
+    repl.send("!prefs", "[bob] How old are you?").await;
+    repl.send("3", "[bob] Which ages").await;
+    repl.send("any", "[bob] What's your timezone").await;
+    repl.send("skip", "[bob] Which languages").await;
+    repl.send("skip", "[bob] What are you looking for?").await;
+    repl
+      .send("any", "[bob] New potential match:\n[bob] Alice's bio")
+      .await;
+  }
+
+  #[tokio::test]
+  async fn prompts_can_be_answered_with_reactions() {
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send("hi", "[alice] Hi!").await;
+    repl.send(":react 1", "[alice] Please enter a bio").await;
+    repl.send("Alice's bio", "[alice] What are you into?").await;
+    repl.send(":react 1", "[alice] You've seen all").await;
+
+    repl.send("!prefs", "[alice] How old are you?").await;
+    repl.send(":react track_next", "[alice] Which ages").await;
+    repl.send(":react 3", "[alice] What's your timezone").await;
+    repl.send(":react 1", "[alice] Which languages").await;
+  }
+
+  #[tokio::test]
+  async fn questionnaire_compatibility_is_shown_on_candidates() {
+    // Skip every question but the first, expecting `last` after the last
+    async fn skip_rest(repl: &mut Repl, user: &str, last: &str) {
+      for _ in 2..model::Questionnaire::QUESTIONS.len() {
+        repl.send("skip", &format!("[{}] ", user)).await;
+      }
+      repl.send("skip", last).await;
+    }
+
+    let mut repl = Repl::start();
+
+    repl.send(":as alice", "Responding as alice.").await;
+    repl.send("hi", "[alice] Hi!").await;
+    repl.send(":react 1", "[alice] Please enter a bio").await;
+    repl.send("Alice's bio", "[alice] What are you into?").await;
+    repl.send("none", "[alice] You've seen all").await;
+
+    repl
+      .send(
+        "!questions",
+        "[alice] How do you like to spend a free evening?",
+      )
+      .await;
+    repl
+      .send(":react 2", "[alice] Which answers would you accept")
+      .await;
+    repl.send_quietly(":react 2");
+    repl.send_quietly(":react 3");
+    repl.send(":react thumbsup", "[alice] How important").await;
+    repl.send(":react 4", "[alice] How quickly").await;
+    skip_rest(&mut repl, "alice", "[alice] You've seen all").await;
+
+    repl.send(":as bob", "Responding as bob.").await;
+    repl.send("hi", "[bob] Hi!").await;
+    repl.send(":react 1", "[bob] Please enter a bio").await;
+    repl.send("Bob's bio", "[bob] What are you into?").await;
+    repl.send("none", "[bob] New potential match:").await;
+
+    repl
+      .send(
+        "!questions",
+        "[bob] How do you like to spend a free evening?",
+      )
+      .await;
+    repl.send("2", "[bob] Which answers would you accept").await;
+    repl.send("any", "[bob] How important").await;
+    repl.send("3", "[bob] How quickly").await;
+    skip_rest(
+      &mut repl,
+      "bob",
+      "[bob] New potential match:\n[bob] Alice's bio\n[bob] Questionnaire match: 100%",
+    )
+    .await;
+  }
+}
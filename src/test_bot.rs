@@ -71,11 +71,10 @@ impl TestBot {
   }
 
   pub(crate) async fn get_message(&self, message_id: MessageId) -> Message {
-    let channel_id = TestDispatcher::get_instance().await.channel();
-    self
-      .bot
+    let test_dispatcher = TestDispatcher::get_instance().await;
+    test_dispatcher
       .client()
-      .message(channel_id, message_id)
+      .message(test_dispatcher.channel().discord(), message_id.discord())
       .exec()
       .await
       .unwrap()
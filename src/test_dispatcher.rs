@@ -56,7 +56,10 @@ impl TestDispatcher {
           if let Some(test_message) = self.test_run_id.filter(&message.content) {
             if let Some(channel) = self.channels.read().await.get(&test_message.test_user_id()) {
               channel
-                .send((message.id, TestEvent::Message(test_message.text)))
+                .send((
+                  MessageId::from_discord(message.id),
+                  TestEvent::Message(test_message.text),
+                ))
                 .expect("message send failed");
             }
           }
@@ -92,7 +95,10 @@ impl TestDispatcher {
           if let Some(test_message) = self.test_run_id.filter(&message.content) {
             if let Some(channel) = self.channels.read().await.get(&test_message.test_user_id()) {
               channel
-                .send((message.id, TestEvent::Reaction(emoji)))
+                .send((
+                  MessageId::from_discord(message.id),
+                  TestEvent::Reaction(emoji),
+                ))
                 .expect("message send failed");
             }
           }
@@ -318,11 +324,11 @@ impl TestDispatcher {
   }
 
   pub(crate) async fn send_message(&self, test_user_id: &TestUserId, msg: &str) {
-    rate_limit::wait().await;
+    rate_limit::wait(RateLimitConfig::default().action_interval()).await;
     let content = self.test_run_id.prefix_message(test_user_id, msg);
     self
       .client()
-      .create_message(self.channel())
+      .create_message(self.channel().discord())
       .content(&content)
       .unwrap()
       .exec()
@@ -331,10 +337,16 @@ impl TestDispatcher {
   }
 
   pub(crate) async fn send_reaction(&self, id: MessageId, emoji: Emoji) {
-    rate_limit::wait().await;
+    rate_limit::wait(RateLimitConfig::default().action_interval()).await;
     self
       .client()
-      .create_reaction(self.channel(), id, &emoji.into())
+      .create_reaction(
+        self.channel().discord(),
+        id.discord(),
+        &RequestReactionType::Unicode {
+          name: emoji.unicode(),
+        },
+      )
       .exec()
       .await
       .unwrap();
@@ -345,6 +357,6 @@ impl TestDispatcher {
   }
 
   pub(crate) fn channel(&self) -> ChannelId {
-    self.channel.id
+    ChannelId::from_discord(self.channel.id)
   }
 }
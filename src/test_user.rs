@@ -70,7 +70,7 @@ impl TestUser {
       TestEvent::Message(have) => assert_eq!(have, want, "unexpected message"),
       TestEvent::Reaction(emoji) => panic!(
         "Got reaction {} but expected message `{}`",
-        emoji.char(),
+        emoji.unicode(),
         want
       ),
     };
@@ -84,7 +84,7 @@ impl TestUser {
       TestEvent::Message(content) => panic!(
         "Got message `{}` but expected reaction {}",
         content,
-        want.char(),
+        want.unicode(),
       ),
     };
     id
@@ -96,13 +96,13 @@ impl TestUser {
         &self
           .bot
           .db()
-          .prompt_text_outside_update_transaction(prompt)
+          .prompt_text_outside_update_transaction(self.id(), prompt)
           .await,
       )
       .await;
 
     for emoji in prompt.reactions() {
-      assert_eq!(self.expect_reaction(*emoji).await, id);
+      assert_eq!(self.expect_reaction(emoji).await, id);
     }
 
     id
@@ -136,5 +136,7 @@ impl TestUser {
     self.send_reaction(id, Emoji::ThumbsUp).await;
     self.expect_prompt(Prompt::Bio).await;
     self.send_message(&format!("{}'s bio!", self.name())).await;
+    self.expect_prompt(Prompt::Tags).await;
+    self.send_message("none").await;
   }
 }
@@ -0,0 +1,38 @@
+use crate::common::*;
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(try_from = "TokenTable")]
+pub(crate) enum TokenSource {
+  Env(String),
+  /// Leading and trailing whitespace is ignored
+  File(PathBuf),
+}
+
+impl TokenSource {
+  pub(crate) fn load(&self) -> Result<String> {
+    match self {
+      Self::Env(var) => env::var(var).context(error::Token { var }),
+      Self::File(path) => Ok(
+        fs::read_to_string(path)
+          .context(error::TokenFile { path })?
+          .trim()
+          .to_owned(),
+      ),
+    }
+  }
+
+  pub(crate) fn validate(&self, key: &str) -> Result<(), String> {
+    match self {
+      Self::Env(var) if var.is_empty() => Err(format!("`{}.env` may not be empty", key)),
+      Self::File(path) if path.as_os_str().is_empty() =>
+        Err(format!("`{}.file` may not be empty", key)),
+      Self::Env(_) | Self::File(_) => Ok(()),
+    }
+  }
+}
+
+impl Default for TokenSource {
+  fn default() -> Self {
+    Self::Env("QUWUE_TOKEN".into())
+  }
+}
@@ -0,0 +1,21 @@
+use crate::common::*;
+
+/// `toml` can't deserialize enums from tables with headers, like `[token]`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TokenTable {
+  env:  Option<String>,
+  file: Option<PathBuf>,
+}
+
+impl TryFrom<TokenTable> for TokenSource {
+  type Error = &'static str;
+
+  fn try_from(table: TokenTable) -> Result<Self, Self::Error> {
+    match (table.env, table.file) {
+      (Some(var), None) => Ok(Self::Env(var)),
+      (None, Some(path)) => Ok(Self::File(path)),
+      (Some(_), Some(_)) | (None, None) => Err("expected exactly one of `env` or `file`"),
+    }
+  }
+}
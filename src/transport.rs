@@ -0,0 +1,46 @@
+use crate::common::*;
+
+/// Each transport owns a disjoint range of user IDs, so that responses and
+/// interrupts can be routed to the transport that the user is on
+#[async_trait]
+pub(crate) trait Transport: Debug + Send + Sync {
+  fn user_id(&self) -> UserId;
+
+  fn owns(&self, user_id: UserId) -> bool;
+
+  fn pool(&self) -> &str;
+
+  /// Returns `None` once the transport has been stopped
+  async fn receive(&self) -> Option<Result<Incoming>>;
+
+  fn stop(&self);
+
+  /// Responses sent since `user_id`'s current prompt message
+  async fn missed_responses(
+    &self,
+    user_id: UserId,
+    prompt_message: PromptMessage,
+  ) -> Result<Vec<Incoming>>;
+
+  async fn private_channel(&self, user_id: UserId) -> Result<ChannelId>;
+
+  async fn is_private_channel(&self, channel_id: ChannelId) -> Result<bool>;
+
+  async fn is_bot(&self, user_id: UserId) -> Result<bool>;
+
+  async fn profile_image_url(&self, user_id: UserId) -> Result<Option<String>>;
+
+  async fn mention(&self, user_id: UserId) -> Result<String>;
+
+  /// Returns the ID of the prompt message, which reactions refer to
+  async fn send_prompt(
+    &self,
+    recipient_id: UserId,
+    channel_id: ChannelId,
+    content: &str,
+    image_url: Option<String>,
+    choices: &[Emoji],
+  ) -> Result<MessageId>;
+
+  async fn send_message(&self, channel_id: ChannelId, content: &str) -> Result<()>;
+}